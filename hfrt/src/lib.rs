@@ -1,27 +1,158 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer, MintTo};
+use anchor_lang::solana_program::compute_units::sol_remaining_compute_units;
+use anchor_lang::solana_program::program_option::COption;
+use anchor_lang::system_program;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{self, Burn, Mint, Token, TokenAccount, Transfer, MintTo, SetAuthority};
 
 declare_id!("A86NRtxqJiyKm4da9jmA1TH1erjUG3ULcPXhS6wdyQk7");
 
-/// A constant for the staking vault seed with a `'static` lifetime.
+/// Seed prefix for the per-mint staking vault PDAs: `[VAULT_SEED, mint]`.
 const VAULT_SEED: &[u8] = b"staking-vault";
-/// Module-level seeds for the staking vault PDA.
-const STAKING_VAULT_SEEDS: &[&[u8]] = &[VAULT_SEED];
-/// Module-level signer seeds array for the staking vault PDA.
-const STAKING_VAULT_SIGNER: &[&[&[u8]]] = &[STAKING_VAULT_SEEDS];
+
+/// Seed for the PDA that is the HFRT mint authority.
+const MINT_AUTHORITY_SEED: &[u8] = b"mint-authority";
+
+/// Highest multiplier returned by `calculate_rebate_multiplier`.
+const MAX_REBATE_MULTIPLIER: u8 = 5;
+/// Rolling-volume tier thresholds in whole HFRT, scaled to the mint's decimals at initialization.
+pub const BASE_TIER_THRESHOLDS: [u64; 3] = [10, 50, 100];
+/// Default effective rebate ceiling: never mint more than 100% of volume.
+const DEFAULT_MAX_EFFECTIVE_REBATE_BPS: u16 = 10_000;
+/// Default rebate denominator: rates are parts per thousand of volume.
+const DEFAULT_REBATE_DENOMINATOR: u64 = 1000;
+
+/// Seed prefix for a referrer's accrual PDA: `[REFERRER_SEED, referrer]`.
+const REFERRER_SEED: &[u8] = b"referrer";
+
+/// Seed prefix for a voter's early-reward PDA: `[VOTER_SEED, voter]`.
+const VOTER_SEED: &[u8] = b"voter";
+
+/// Seed prefix for a voter's record of one proposal vote: `[VOTE_RECORD_SEED, proposal, voter]`.
+const VOTE_RECORD_SEED: &[u8] = b"vote-record";
+
+/// Seed prefix for a claim's accounting receipt: `[RECEIPT_SEED, owner, claim_nonce (le bytes)]`.
+const RECEIPT_SEED: &[u8] = b"receipt";
+
+/// Seed prefixes for a tokenized stake position and the staker's position token account, both keyed by
+/// the position mint.
+const TOKENIZED_POSITION_SEED: &[u8] = b"position";
+const POSITION_TOKEN_SEED: &[u8] = b"position-token";
+
+/// Seed prefix for a trader's frozen airdrop snapshot: `[SNAPSHOT_SEED, snapshot_id (le bytes), trader]`.
+const SNAPSHOT_SEED: &[u8] = b"snapshot";
+
+/// Seed for the PDA that owns the reward-token vault.
+const REWARD_VAULT_AUTHORITY_SEED: &[u8] = b"reward-vault-authority";
+
+/// Seeds for the trader registry and its pages: `[TRADER_REGISTRY_SEED]` and
+/// `[TRADER_REGISTRY_PAGE_SEED, page (le bytes)]`.
+const TRADER_REGISTRY_SEED: &[u8] = b"trader-registry";
+const TRADER_REGISTRY_PAGE_SEED: &[u8] = b"trader-registry-page";
+/// Trader PDAs listed on each `TraderRegistryPage`.
+pub const TRADERS_PER_REGISTRY_PAGE: usize = 32;
+
+/// Maximum number of relayers on the allowlist.
+pub const MAX_RELAYERS: usize = 10;
+
+/// Number of recent trades kept on each `Trader` for sliding-window wash detection.
+pub const RECENT_TRADE_COUNT: usize = 4;
+
+/// Minimum seconds between trades before `FrequentTrades` applies.
+pub const MIN_TRADE_INTERVAL: i64 = 5;
+/// Minimum seconds between large trades before a trade is treated as a wash trade.
+pub const WASH_TRADE_INTERVAL: i64 = 10;
+/// Trade size above which (or at which, with `governance.wash_threshold_inclusive`) the wash interval applies.
+pub const WASH_TRADE_THRESHOLD: u64 = 1_000_000;
+/// Seconds within which opposite-side fills of similar size are treated as self-matching.
+pub const SELF_MATCH_WINDOW: i64 = 10;
+/// Maximum size difference, in basis points of the larger fill, for two fills to count as matching.
+pub const SELF_MATCH_TOLERANCE_BPS: u64 = 1_000;
+/// Maximum number of fills accepted by one `record_trade_batch` call, bounding compute.
+pub const MAX_BATCH_TRADES: usize = 8;
+/// Maximum number of `MarketVolume` accounts accepted by one `batch_record_trades_for_markets` call.
+pub const MAX_BATCH_MARKETS: usize = 4;
+/// Maximum number of traders recorded by one `bulk_snapshot` call, keeping it within compute limits.
+pub const MAX_BULK_SNAPSHOT: usize = 8;
+/// Maximum number of traders compounded by one `auto_compound_all` call.
+pub const MAX_BATCH_COMPOUND: usize = 8;
+/// Volume per `governance.points_per_volume` loyalty points.
+pub const LOYALTY_VOLUME_UNIT: u64 = 1_000_000;
+/// Lamports per SOL, the unit `governance.priority_hfrt_per_sol` converts reimbursed priority spend from.
+pub const LAMPORTS_PER_SOL: u64 = 1_000_000_000;
+
+/// Reason codes reported by `can_claim` in the `ClaimEligibility` event.
+pub const CLAIM_ELIGIBLE: u8 = 0;
+pub const CLAIM_BLOCKED_NO_VOLUME: u8 = 1;
+pub const CLAIM_BLOCKED_ZERO_REBATE: u8 = 2;
+pub const CLAIM_BLOCKED_PAUSED: u8 = 3;
+pub const CLAIM_BLOCKED_ADDRESS: u8 = 4;
+pub const CLAIM_BLOCKED_BELOW_MINIMUM: u8 = 5;
+pub const CLAIM_BLOCKED_REBATES_PAUSED: u8 = 6;
+
+/// Destinations for a rejected proposal's forfeited bond, stored in `governance.bond_forfeit_policy`.
+pub const BOND_FORFEIT_TREASURY: u8 = 0;
+pub const BOND_FORFEIT_BURN: u8 = 1;
+pub const BOND_FORFEIT_REDISTRIBUTE: u8 = 2;
+
+/// Proposal kinds, each enabled by bit `1 << kind` of `governance.allowed_proposal_kinds`.
+pub const PROPOSAL_KIND_FEE_DISCOUNT: u8 = 0;
+
+/// Venues a `record_trade` fill can be attributed to, each with its own optional governance rebate rate.
+pub const VENUE_SPOT: u8 = 0;
+pub const VENUE_PERP: u8 = 1;
+
+/// Instruction ids reported in `ComputeUsed` events while `governance.profiling_enabled` is set.
+pub const PROFILE_RECORD_TRADE: u8 = 0;
+pub const PROFILE_RECORD_TRADE_BATCH: u8 = 1;
+pub const PROFILE_CLAIM_REBATE: u8 = 2;
 
 #[program]
 pub mod hfrt {
     use super::*;
 
     /// Initializes the global state and sets up the HFRT mint.
-    pub fn initialize(ctx: Context<Initialize>, fee_discount: u8) -> Result<()> {
-        let state = &mut ctx.accounts.global_state;
-        state.authority = ctx.accounts.authority.key();
-        state.hfrt_mint = ctx.accounts.hfrt_mint.key();
-        state.fee_discount = fee_discount;
-        state.bump = ctx.bumps.global_state; // Retrieve bump via dot notation.
-        Ok(())
+    /// `mint_authority_bump` is the bump of the `mint-authority` PDA that is the mint's authority;
+    /// it need not be canonical and is stored so later mints sign with the exact same address.
+    pub fn initialize(ctx: Context<Initialize>, fee_discount: u8, mint_authority_bump: u8) -> Result<()> {
+        write_global_state(
+            &mut ctx.accounts.global_state,
+            ctx.program_id,
+            ctx.accounts.authority.key(),
+            &ctx.accounts.hfrt_mint,
+            ctx.accounts.mint_authority.key(),
+            fee_discount,
+            mint_authority_bump,
+            ctx.bumps.global_state, // Retrieve bump via dot notation.
+        )
+    }
+
+    /// Retry-safe `initialize` for deployment scripts: initializes on first call, then succeeds as a
+    /// no-op while the existing global state matches the arguments, and errors on any conflict.
+    pub fn ensure_initialized(ctx: Context<EnsureInitialized>, fee_discount: u8, mint_authority_bump: u8) -> Result<()> {
+        let state = &ctx.accounts.global_state;
+        // A renounced state also has a default authority, but must never be re-initialized.
+        if state.authority != Pubkey::default() || state.renounced {
+            require!(
+                state.authority == ctx.accounts.authority.key()
+                    && state.hfrt_mint == ctx.accounts.hfrt_mint.key()
+                    && state.fee_discount == fee_discount
+                    && state.mint_authority_bump == mint_authority_bump,
+                ErrorCode::InitializationConflict
+            );
+            return Ok(());
+        }
+        write_global_state(
+            &mut ctx.accounts.global_state,
+            ctx.program_id,
+            ctx.accounts.authority.key(),
+            &ctx.accounts.hfrt_mint,
+            ctx.accounts.mint_authority.key(),
+            fee_discount,
+            mint_authority_bump,
+            ctx.bumps.global_state,
+        )
     }
 
     /// Initializes the Governance account for managing rebate parameters.
@@ -31,536 +162,6886 @@ pub mod hfrt {
         max_fee_discount: u8,
     ) -> Result<()> {
         let gov = &mut ctx.accounts.governance;
+        gov.max_effective_rebate_bps = DEFAULT_MAX_EFFECTIVE_REBATE_BPS;
+        gov.rebate_denominator = DEFAULT_REBATE_DENOMINATOR;
+        validate_effective_rebate_rate(gov, rebate_rate)?;
         gov.rebate_rate = rebate_rate;
+        gov.volatile_rebate_rate = rebate_rate;
         gov.max_fee_discount = max_fee_discount;
+        gov.inclusive_tier_boundaries = true;
+        gov.allowed_proposal_kinds = u32::MAX;
         gov.authority = ctx.accounts.authority.key();
         Ok(())
     }
 
     /// Updates the rebate rate via governance.
+    /// Changes are rate-limited: at most one per `rate_change_cooldown` seconds, each moving the rate
+    /// by at most `max_rate_change` (0 leaves the step unbounded).
     pub fn update_rebate_rate(ctx: Context<UpdateGovernance>, new_rate: u8) -> Result<()> {
         let gov = &mut ctx.accounts.governance;
         require!(new_rate <= gov.max_fee_discount, ErrorCode::InvalidRebateRate);
+        validate_effective_rebate_rate(gov, new_rate)?;
+        let clock = Clock::get()?;
+        let since_change = safe_elapsed(gov.last_rate_change, clock.unix_timestamp, gov.reject_negative_elapsed)?;
+        require!(since_change >= gov.rate_change_cooldown, ErrorCode::RateChangeCooldown);
+        require!(
+            gov.max_rate_change == 0 || new_rate.abs_diff(gov.rebate_rate) <= gov.max_rate_change,
+            ErrorCode::RateChangeTooLarge
+        );
         gov.rebate_rate = new_rate;
+        gov.last_rate_change = clock.unix_timestamp;
         Ok(())
     }
 
-    /// Records a trade by updating the trader’s 24-hour rolling volume.
-    /// Resets the volume if more than 24 hours have elapsed.
-    /// Checks for wash trades and for too-frequent trading (sybil resistance).
-    pub fn record_trade(ctx: Context<RecordTrade>, trade_amount: u64) -> Result<()> {
-        let trader = &mut ctx.accounts.trader;
-        let clock = Clock::get()?;
-        let current_time = clock.unix_timestamp;
+    /// Configures the `update_rebate_rate` rate limit. Zero for either disables that limit.
+    pub fn set_rate_change_limits(
+        ctx: Context<UpdateGovernance>,
+        rate_change_cooldown: i64,
+        max_rate_change: u8,
+    ) -> Result<()> {
+        require!(rate_change_cooldown >= 0, ErrorCode::InvalidRateChangeLimits);
+        let gov = &mut ctx.accounts.governance;
+        gov.rate_change_cooldown = rate_change_cooldown;
+        gov.max_rate_change = max_rate_change;
+        Ok(())
+    }
+
+    /// Updates the elevated rebate rate applied while volatility mode is on.
+    pub fn update_volatile_rebate_rate(ctx: Context<UpdateGovernance>, new_rate: u8) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        require!(new_rate <= gov.max_fee_discount, ErrorCode::InvalidRebateRate);
+        validate_effective_rebate_rate(gov, new_rate)?;
+        gov.volatile_rebate_rate = new_rate;
+        Ok(())
+    }
+
+    /// Sets the rebate rates for volume attributed to spot and perp venues; 0 leaves that venue on the
+    /// active rate.
+    pub fn set_venue_rebate_rates(ctx: Context<UpdateGovernance>, spot_rate: u8, perp_rate: u8) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        for rate in [spot_rate, perp_rate] {
+            require!(rate <= gov.max_fee_discount, ErrorCode::InvalidRebateRate);
+            validate_effective_rebate_rate(gov, rate)?;
+        }
+        gov.spot_rebate_rate = spot_rate;
+        gov.perp_rebate_rate = perp_rate;
+        Ok(())
+    }
+
+    /// Configures the linear rebate-rate mode, which scales the rate from `min_rate` to `max_rate`
+    /// as rolling volume moves from `volume_min` to `volume_max`.
+    pub fn set_linear_rate(
+        ctx: Context<UpdateGovernance>,
+        enabled: bool,
+        min_rate: u8,
+        max_rate: u8,
+        volume_min: u64,
+        volume_max: u64,
+    ) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        require!(min_rate <= max_rate && volume_min < volume_max, ErrorCode::InvalidLinearRate);
+        require!(max_rate <= gov.max_fee_discount, ErrorCode::InvalidRebateRate);
+        validate_effective_rebate_rate(gov, max_rate)?;
+        gov.linear_rate_mode = enabled;
+        gov.linear_min_rate = min_rate;
+        gov.linear_max_rate = max_rate;
+        gov.linear_volume_min = volume_min;
+        gov.linear_volume_max = volume_max;
+        Ok(())
+    }
+
+    /// Sets the ceiling on the effective rebate (rate at the top multiplier) as basis points of volume.
+    /// The current rebate rates must already satisfy the new ceiling.
+    pub fn set_max_effective_rebate_bps(ctx: Context<UpdateGovernance>, max_effective_rebate_bps: u16) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        gov.max_effective_rebate_bps = max_effective_rebate_bps;
+        validate_effective_rebate_rate(gov, gov.rebate_rate)?;
+        validate_effective_rebate_rate(gov, gov.volatile_rebate_rate)?;
+        validate_effective_rebate_rate(gov, gov.spot_rebate_rate)?;
+        validate_effective_rebate_rate(gov, gov.perp_rebate_rate)?;
+        Ok(())
+    }
 
-        // Check for potential wash trading.
-        if is_wash_trade(trader.last_update, current_time, trade_amount) {
-            return Err(ErrorCode::WashTrade.into());
+    /// Changes the denominator rebate rates are divided by. Unless `rescale_rates` is false, every rate
+    /// (and the `max_fee_discount` and `max_rate_change` bounds on it) is rescaled by
+    /// `new_denominator / old_denominator` so effective rebates are unchanged; rescaled rates round down.
+    pub fn set_rebate_denominator_and_migrate(
+        ctx: Context<UpdateGovernance>,
+        new_denominator: u64,
+        rescale_rates: bool,
+    ) -> Result<()> {
+        require!(new_denominator > 0, ErrorCode::InvalidDenominator);
+        let gov = &mut ctx.accounts.governance;
+        let old_denominator = rebate_denominator(gov);
+        let old_rate = gov.rebate_rate;
+        if rescale_rates {
+            let rescale = |rate: u8| rescale_rebate_rate(rate, old_denominator, new_denominator);
+            gov.rebate_rate = rescale(gov.rebate_rate)?;
+            gov.volatile_rebate_rate = rescale(gov.volatile_rebate_rate)?;
+            gov.spot_rebate_rate = rescale(gov.spot_rebate_rate)?;
+            gov.perp_rebate_rate = rescale(gov.perp_rebate_rate)?;
+            gov.linear_min_rate = rescale(gov.linear_min_rate)?;
+            gov.linear_max_rate = rescale(gov.linear_max_rate)?;
+            gov.max_fee_discount = rescale(gov.max_fee_discount)?;
+            gov.max_rate_change = rescale(gov.max_rate_change)?;
         }
-        // Check if trades occur too frequently.
-        if detect_frequent_trades(trader.last_update, current_time) {
-            return Err(ErrorCode::FrequentTrades.into());
+        gov.rebate_denominator = new_denominator;
+        for rate in [gov.rebate_rate, gov.volatile_rebate_rate, gov.spot_rebate_rate, gov.perp_rebate_rate] {
+            validate_effective_rebate_rate(gov, rate)?;
         }
+        validate_effective_rebate_rate(gov, gov.linear_max_rate)?;
+        emit!(RebateDenominatorChanged {
+            old_denominator,
+            new_denominator,
+            old_rate,
+            new_rate: gov.rebate_rate,
+        });
+        Ok(())
+    }
 
-        if current_time - trader.last_update >= 24 * 3600 {
-            trader.rolling_volume = trade_amount;
+    /// Sets the emergency council, which may pause (but not unpause) the program.
+    pub fn set_emergency_council(ctx: Context<UpdateGlobalState>, emergency_council: Pubkey) -> Result<()> {
+        ctx.accounts.global_state.emergency_council = emergency_council;
+        Ok(())
+    }
+
+    /// Pauses or unpauses trading, claims, staking and compounding.
+    /// The authority can do both; the emergency council can only pause.
+    /// `reason` is an integrator-defined code recorded with the pause time; both clear on unpause.
+    pub fn set_paused(ctx: Context<SetPaused>, paused: bool, reason: u8) -> Result<()> {
+        let state = &mut ctx.accounts.global_state;
+        let signer = ctx.accounts.signer.key();
+        let is_council = state.emergency_council != Pubkey::default() && signer == state.emergency_council;
+        require!(
+            signer == state.authority || (paused && is_council),
+            ErrorCode::Unauthorized
+        );
+        let clock = Clock::get()?;
+        state.paused = paused;
+        if paused {
+            state.pause_reason = reason;
+            state.paused_at = clock.unix_timestamp;
+            emit!(ProgramPaused {
+                reason,
+                at: clock.unix_timestamp,
+                by: signer,
+            });
         } else {
-            trader.rolling_volume = trader
-                .rolling_volume
-                .checked_add(trade_amount)
-                .ok_or(ErrorCode::Overflow)?;
+            state.pause_reason = 0;
+            state.paused_at = 0;
+            emit!(ProgramUnpaused {
+                at: clock.unix_timestamp,
+                by: signer,
+            });
         }
-        trader.last_update = current_time;
+        Ok(())
+    }
 
-        emit!(TradeRecorded {
-            owner: trader.owner,
-            trade_amount,
-            rolling_volume: trader.rolling_volume,
+    /// Pauses rebate claims and compounds alone, leaving trading, staking and governance running.
+    /// As with `set_paused`, the emergency council may pause but only the authority may resume.
+    pub fn set_rebate_paused(ctx: Context<SetPaused>, rebate_paused: bool) -> Result<()> {
+        let state = &mut ctx.accounts.global_state;
+        let signer = ctx.accounts.signer.key();
+        let is_council = state.emergency_council != Pubkey::default() && signer == state.emergency_council;
+        require!(
+            signer == state.authority || (rebate_paused && is_council),
+            ErrorCode::Unauthorized
+        );
+        state.rebate_paused = rebate_paused;
+        emit!(RebatePauseChanged {
+            rebate_paused,
+            by: signer,
         });
         Ok(())
     }
 
-    /// Claims an HFRT rebate based on the recorded 24-hour trading volume.
-    /// The rebate is computed using the governance rebate rate and a multiplier.
-    pub fn claim_rebate(ctx: Context<ClaimRebate>) -> Result<()> {
-        let rebate_amount = {
-            let trader = &mut ctx.accounts.trader;
-            let base_rebate = trader
-                .rolling_volume
-                .checked_mul(ctx.accounts.governance.rebate_rate as u64)
-                .ok_or(ErrorCode::Overflow)?
-                .checked_div(1000)
-                .ok_or(ErrorCode::Overflow)?;
-            // Apply a multiplier based on volume.
-            let multiplier = calculate_rebate_multiplier(trader.rolling_volume);
-            let total_rebate = base_rebate.checked_mul(multiplier as u64).ok_or(ErrorCode::Overflow)?;
-            trader.rolling_volume = 0;
-            total_rebate
-        };
-        token::mint_to(ctx.accounts.into_mint_to_context(), rebate_amount)?;
-
-        let owner = ctx.accounts.trader.owner;
-        emit!(RebateClaimed { owner, rebate_amount });
+    /// Hands the HFRT mint authority from the `mint-authority` PDA to `new_authority` (e.g. a multisig)
+    /// for decommissioning. Every program-side mint fails afterwards.
+    pub fn transfer_mint_authority(ctx: Context<TransferMintAuthority>, new_authority: Pubkey) -> Result<()> {
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::set_authority(
+            ctx.accounts.into_set_authority_context().with_signer(signer_seeds),
+            AuthorityType::MintTokens,
+            Some(new_authority),
+        )?;
+        emit!(MintAuthorityTransferred {
+            hfrt_mint: ctx.accounts.hfrt_mint.key(),
+            new_authority,
+        });
         Ok(())
     }
 
-    /// Stakes HFRT tokens by transferring them from the trader’s token account into the staking vault.
-    /// Records the stake start time if this is the first stake.
-    pub fn stake_tokens(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
-        token::transfer(ctx.accounts.into_transfer_to_vault_context(), amount)?;
-        {
-            let trader = &mut ctx.accounts.trader;
-            trader.staked_amount = trader
-                .staked_amount
-                .checked_add(amount)
-                .ok_or(ErrorCode::Overflow)?;
-            if trader.stake_start_time == 0 {
-                let clock = Clock::get()?;
-                trader.stake_start_time = clock.unix_timestamp;
-            }
-        }
+    /// Sets the HFRT supply cap enforced on every program mint. It can never be set below the
+    /// current circulating supply. A cap of zero (the initial value) means uncapped.
+    pub fn set_max_total_supply(ctx: Context<SetMaxTotalSupply>, new_cap: u64) -> Result<()> {
+        require!(new_cap >= ctx.accounts.hfrt_mint.supply, ErrorCode::InvalidSupplyCap);
+        let state = &mut ctx.accounts.global_state;
+        let old_cap = state.max_total_supply;
+        state.max_total_supply = new_cap;
+        emit!(SupplyCapChanged { old_cap, new_cap });
         Ok(())
     }
 
-    /// Unstakes HFRT tokens by transferring them back from the staking vault.
-    /// Applies a dynamic unstake penalty based on staking duration.
-    pub fn unstake_tokens(ctx: Context<UnstakeTokens>, amount: u64) -> Result<()> {
-        let amount_after_penalty = {
-            let trader = &mut ctx.accounts.trader;
-            require!(trader.staked_amount >= amount, ErrorCode::InsufficientStake);
-            let clock = Clock::get()?;
-            let penalty = calculate_dynamic_unstake_penalty(trader.stake_start_time, clock.unix_timestamp, amount);
-            let amount_after_penalty = amount.checked_sub(penalty).ok_or(ErrorCode::Overflow)?;
-            trader.staked_amount = trader
-                .staked_amount
-                .checked_sub(amount)
-                .ok_or(ErrorCode::Overflow)?;
-            if trader.staked_amount == 0 {
-                trader.stake_start_time = 0;
-            }
-            amount_after_penalty
-        };
-        token::transfer(ctx.accounts.into_transfer_from_vault_context(), amount_after_penalty)?;
+    /// Permanently gives up admin control: the authority is set to the default pubkey, which can never sign,
+    /// so every authority-gated instruction fails from then on. The emergency council is cleared too: only the
+    /// authority can unpause, so a council pause after renouncing could never be lifted.
+    pub fn renounce_authority(ctx: Context<UpdateGlobalState>) -> Result<()> {
+        let state = &mut ctx.accounts.global_state;
+        let previous_authority = state.authority;
+        state.authority = Pubkey::default();
+        state.emergency_council = Pubkey::default();
+        state.renounced = true;
+        emit!(AuthorityRenounced {
+            previous_authority,
+            at: Clock::get()?.unix_timestamp,
+        });
         Ok(())
     }
 
-    /// Auto-compounds staking rewards by minting the rebate directly to the staking vault.
-    pub fn auto_compound(ctx: Context<AutoCompound>) -> Result<()> {
-        let rebate_amount = {
-            let trader = &mut ctx.accounts.trader;
-            let base_rebate = trader
-                .rolling_volume
-                .checked_mul(ctx.accounts.governance.rebate_rate as u64)
-                .ok_or(ErrorCode::Overflow)?
-                .checked_div(1000)
-                .ok_or(ErrorCode::Overflow)?;
-            let multiplier = calculate_rebate_multiplier(trader.rolling_volume);
-            let total_rebate = base_rebate.checked_mul(multiplier as u64).ok_or(ErrorCode::Overflow)?;
-            trader.rolling_volume = 0;
-            total_rebate
-        };
-        token::mint_to(ctx.accounts.into_mint_to_vault_context(), rebate_amount)?;
-        {
-            let trader = &mut ctx.accounts.trader;
-            trader.staked_amount = trader.staked_amount.checked_add(rebate_amount).ok_or(ErrorCode::Overflow)?;
-            if trader.stake_start_time == 0 {
-                let clock = Clock::get()?;
-                trader.stake_start_time = clock.unix_timestamp;
-            }
-        }
+    /// Boosts claims by `boost_bps` while the last complete `window` of protocol-wide volume stayed under
+    /// `volume_threshold`, to reward activity when the market is quiet. A zero window or boost disables it.
+    pub fn set_low_activity_boost(
+        ctx: Context<UpdateGlobalState>,
+        window: i64,
+        volume_threshold: u64,
+        boost_bps: u16,
+    ) -> Result<()> {
+        require!(window >= 0 && boost_bps <= 10_000, ErrorCode::InvalidLowActivityBoost);
+        let state = &mut ctx.accounts.global_state;
+        state.activity_window = window;
+        state.low_activity_threshold = volume_threshold;
+        state.low_activity_boost_bps = boost_bps;
         Ok(())
     }
 
-    /// Creates a new DAO proposal to update the fee discount.
-    pub fn create_dao_proposal(
-        ctx: Context<CreateDAOProposal>,
-        proposal_id: u64,
-        new_fee_discount: u8,
+    /// Caps the HFRT minted by every program mint path (claims, compounds, referral, keeper and voter rewards,
+    /// point redemptions) per `epoch_duration` seconds. A zero cap disables it.
+    pub fn set_epoch_inflation_cap(
+        ctx: Context<UpdateGlobalState>,
+        max_mint_per_epoch: u64,
+        epoch_duration: i64,
     ) -> Result<()> {
-        let proposal = &mut ctx.accounts.dao_proposal;
-        proposal.proposal_id = proposal_id;
-        proposal.proposer = ctx.accounts.proposer.key();
-        proposal.new_fee_discount = new_fee_discount;
-        proposal.votes_for = 0;
-        proposal.votes_against = 0;
+        require!(max_mint_per_epoch == 0 || epoch_duration > 0, ErrorCode::InvalidEpochDuration);
+        let state = &mut ctx.accounts.global_state;
+        state.max_mint_per_epoch = max_mint_per_epoch;
+        state.epoch_duration = epoch_duration;
         Ok(())
     }
 
-    /// Votes on an existing DAO proposal.
-    pub fn vote_dao_proposal(ctx: Context<VoteDAOProposal>, vote_for: bool) -> Result<()> {
-        let proposal = &mut ctx.accounts.dao_proposal;
-        if vote_for {
-            proposal.votes_for = proposal.votes_for.checked_add(1).ok_or(ErrorCode::Overflow)?;
-        } else {
-            proposal.votes_against = proposal.votes_against.checked_add(1).ok_or(ErrorCode::Overflow)?;
-        }
+    /// Moves the HFRT staking vault from the raw `[VAULT_SEED, hfrt_mint]` PDA token account to that PDA's
+    /// associated token account, transferring the whole balance and pointing `global_state.hfrt_vault` at it.
+    /// Runs once; the transfer and the switch happen atomically, and the new vault must hold the full balance.
+    pub fn migrate_vault(ctx: Context<MigrateVault>) -> Result<()> {
+        require!(!ctx.accounts.global_state.vault_migrated, ErrorCode::VaultAlreadyMigrated);
+        let amount = ctx.accounts.legacy_vault.amount;
+        let hfrt_mint = ctx.accounts.global_state.hfrt_mint;
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, hfrt_mint.as_ref(), &[ctx.bumps.legacy_vault]]];
+        token::transfer(ctx.accounts.into_transfer_to_new_vault_context().with_signer(vault_seeds), amount)?;
+        ctx.accounts.legacy_vault.reload()?;
+        ctx.accounts.new_vault.reload()?;
+        require!(
+            ctx.accounts.legacy_vault.amount == 0 && ctx.accounts.new_vault.amount == amount,
+            ErrorCode::VaultMigrationIncomplete
+        );
+        let state = &mut ctx.accounts.global_state;
+        state.hfrt_vault = ctx.accounts.new_vault.key();
+        state.vault_migrated = true;
+        emit!(VaultMigrated {
+            old_vault: ctx.accounts.legacy_vault.key(),
+            new_vault: state.hfrt_vault,
+            amount,
+        });
         Ok(())
     }
 
-    /// Executes a DAO proposal if it has passed, updating the fee discount.
-    pub fn execute_dao_proposal(ctx: Context<ExecuteDAOProposal>) -> Result<()> {
-        let proposal = &ctx.accounts.dao_proposal;
-        require!(proposal.votes_for > proposal.votes_against, ErrorCode::ProposalRejected);
-        let global_state = &mut ctx.accounts.global_state;
-        global_state.fee_discount = proposal.new_fee_discount;
+    /// Moves the balance of the original single HFRT vault (seeded by "staking-vault" alone) into the current
+    /// HFRT vault, so stake still held there becomes unstakeable again. Authority-only; safe to repeat.
+    pub fn drain_legacy_vault(ctx: Context<DrainLegacyVault>) -> Result<()> {
+        let amount = ctx.accounts.legacy_vault.amount;
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, &[ctx.bumps.legacy_vault]]];
+        token::transfer(ctx.accounts.into_transfer_to_hfrt_vault_context().with_signer(vault_seeds), amount)?;
+        emit!(LegacyVaultDrained {
+            legacy_vault: ctx.accounts.legacy_vault.key(),
+            hfrt_vault: ctx.accounts.hfrt_vault.key(),
+            amount,
+        });
         Ok(())
     }
-}
 
-/// Returns a multiplier for the rebate based on the 24‑hour trading volume.
-fn calculate_rebate_multiplier(trade_volume: u64) -> u8 {
-    if trade_volume >= 100_000_000 {
-        5
-    } else if trade_volume >= 50_000_000 {
-        3
-    } else if trade_volume >= 10_000_000 {
-        2
-    } else {
-        1
+    /// Sets the cap on total HFRT staked across all traders; 0 disables it. Stakes and compounds that would
+    /// exceed it are rejected, but a cap below the current total does not unwind existing stake.
+    pub fn set_max_total_staked(ctx: Context<UpdateGlobalState>, max_total_staked: u64) -> Result<()> {
+        ctx.accounts.global_state.max_total_staked = max_total_staked;
+        Ok(())
     }
-}
 
-/// Returns true if trades occur too frequently (less than 5 seconds apart).
-fn detect_frequent_trades(last_trade_time: i64, current_time: i64) -> bool {
-    let min_time_between_trades = 5;
-    (current_time - last_trade_time) < min_time_between_trades
-}
+    /// Sets the keeper/oracle key allowed to toggle volatility mode alongside the authority.
+    pub fn set_volatility_oracle(ctx: Context<UpdateGlobalState>, oracle: Pubkey) -> Result<()> {
+        ctx.accounts.global_state.volatility_oracle = oracle;
+        Ok(())
+    }
 
-/// Returns true if a trade is considered a wash trade.
-fn is_wash_trade(last_trade_time: i64, current_time: i64, trade_amount: u64) -> bool {
-    let min_time_between_trades = 10;
-    trade_amount > 1_000_000 && (current_time - last_trade_time) < min_time_between_trades
-}
+    /// Switches the rebate regime. While on, rebates use `governance.volatile_rebate_rate`.
+    /// Callable by the authority or the configured volatility oracle.
+    pub fn set_volatility_mode(ctx: Context<SetVolatilityMode>, enabled: bool) -> Result<()> {
+        let state = &mut ctx.accounts.global_state;
+        let signer = ctx.accounts.signer.key();
+        require!(
+            signer == state.authority || signer == state.volatility_oracle,
+            ErrorCode::Unauthorized
+        );
+        state.volatility_mode = enabled;
+        emit!(VolatilityModeChanged { enabled, by: signer });
+        Ok(())
+    }
 
-/// Calculates a dynamic unstake penalty based on staking duration.
-/// Penalty: 10% if staked less than 7 days, 5% if less than 14 days, 2% otherwise.
-fn calculate_dynamic_unstake_penalty(stake_start_time: i64, current_time: i64, amount: u64) -> u64 {
-    let duration = current_time - stake_start_time;
-    let penalty_percentage = if duration < 7 * 24 * 3600 {
-        10
-    } else if duration < 14 * 24 * 3600 {
-        5
-    } else {
-        2
-    };
-    amount * penalty_percentage / 100
-}
+    /// Creates the trader state PDA (seeded by "trader" and the owner) and records its creation time.
+    pub fn initialize_trader(ctx: Context<InitializeTrader>) -> Result<()> {
+        let trader_key = ctx.accounts.trader.key();
+        let trader = &mut ctx.accounts.trader;
+        let clock = Clock::get()?;
+        trader.owner = ctx.accounts.owner.key();
+        trader.created_at = clock.unix_timestamp;
+        // Registration is optional here; an unregistered trader can still be added with `register_trader`.
+        match (ctx.accounts.trader_registry.as_mut(), ctx.accounts.registry_page.as_mut()) {
+            (Some(registry), Some(page)) => register_trader_in_page(registry, page, trader, trader_key)?,
+            (None, None) => {}
+            _ => return Err(ErrorCode::WrongRegistryPage.into()),
+        }
+        Ok(())
+    }
 
-/// Calculates execution priority based on HFRT balance (lower number means higher priority).
-fn calculate_execution_priority(hfrt_balance: u64) -> u8 {
-    if hfrt_balance >= 1_000_000 {
-        1
-    } else if hfrt_balance >= 500_000 {
-        2
-    } else if hfrt_balance >= 100_000 {
-        3
-    } else {
-        5
+    /// Creates the trader registry, which lists Trader PDAs on fixed-size pages so clients can page
+    /// through them without `getProgramAccounts`.
+    pub fn initialize_trader_registry(ctx: Context<InitializeTraderRegistry>) -> Result<()> {
+        ctx.accounts.trader_registry.bump = ctx.bumps.trader_registry;
+        Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct Initialize<'info> {
-    /// Global state PDA (seeded by "global-state")
-    #[account(
-        init,
-        payer = authority,
-        seeds = [b"global-state"],
-        bump,
-        space = 8 + GlobalState::LEN,
-    )]
-    pub global_state: Account<'info, GlobalState>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    /// The mint for HFRT tokens.
-    pub hfrt_mint: Account<'info, Mint>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-    pub token_program: Program<'info, Token>,
-}
+    /// Opens the registry page that the next registered trader lands on. Anyone may pay for it, but only
+    /// the current page can be opened.
+    pub fn open_trader_registry_page(ctx: Context<OpenTraderRegistryPage>, page: u32) -> Result<()> {
+        require!(
+            page as u64 == ctx.accounts.trader_registry.trader_count / TRADERS_PER_REGISTRY_PAGE as u64,
+            ErrorCode::WrongRegistryPage
+        );
+        let registry_page = &mut ctx.accounts.registry_page;
+        registry_page.page = page;
+        registry_page.bump = ctx.bumps.registry_page;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct InitializeGovernance<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Governance::LEN,
-    )]
-    pub governance: Account<'info, Governance>,
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    pub system_program: Program<'info, System>,
-}
+    /// Adds a trader created without registry accounts to the registry. Permissionless.
+    pub fn register_trader(ctx: Context<RegisterTrader>) -> Result<()> {
+        let trader_key = ctx.accounts.trader.key();
+        register_trader_in_page(
+            &mut ctx.accounts.trader_registry,
+            &mut ctx.accounts.registry_page,
+            &mut ctx.accounts.trader,
+            trader_key,
+        )
+    }
 
-#[derive(Accounts)]
-pub struct UpdateGovernance<'info> {
-    #[account(mut, has_one = authority)]
-    pub governance: Account<'info, Governance>,
-    pub authority: Signer<'info>,
-}
+    /// Creates the caller's volume bucket for `market`, used for per-venue rebates.
+    pub fn initialize_market_volume(ctx: Context<InitializeMarketVolume>, market: Pubkey) -> Result<()> {
+        let market_volume = &mut ctx.accounts.market_volume;
+        market_volume.owner = ctx.accounts.owner.key();
+        market_volume.market = market;
+        market_volume.bump = ctx.bumps.market_volume;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct RecordTrade<'info> {
-    /// Trader state account (must be pre-initialized).
-    #[account(mut, has_one = owner)]
-    pub trader: Account<'info, Trader>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-}
+    /// First step of a trader ownership transfer: the current owner nominates `new_owner`.
+    /// `owner` has no other setter; it only changes once the nominee accepts.
+    pub fn transfer_trader_ownership(ctx: Context<TransferTraderOwnership>, new_owner: Pubkey) -> Result<()> {
+        ctx.accounts.trader.pending_owner = new_owner;
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ClaimRebate<'info> {
-    /// Trader state account (must be pre-initialized).
-    #[account(mut, has_one = owner)]
-    pub trader: Account<'info, Trader>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// The HFRT mint.
-    #[account(mut)]
-    pub hfrt_mint: Account<'info, Mint>,
-    /// Trader’s token account for receiving rebates.
-    #[account(mut)]
-    pub trader_token_account: Account<'info, TokenAccount>,
-    /// PDA mint authority (seeded by "mint-authority").
-    #[account(
-        seeds = [b"mint-authority"],
-        bump,
-    )]
-    /// CHECK: This PDA is derived deterministically.
-    pub mint_authority: UncheckedAccount<'info>,
-    /// Governance account for rebate rate configuration.
-    #[account(mut)]
-    pub governance: Account<'info, Governance>,
-    pub token_program: Program<'info, Token>,
-}
+    /// Second step of a trader ownership transfer: the nominated owner accepts.
+    /// The trader PDA keeps its original address (seeded by the first owner).
+    pub fn accept_trader_ownership(ctx: Context<AcceptTraderOwnership>) -> Result<()> {
+        let trader = &mut ctx.accounts.trader;
+        require!(
+            trader.pending_owner != Pubkey::default() && trader.pending_owner == ctx.accounts.new_owner.key(),
+            ErrorCode::NotPendingOwner
+        );
+        let previous_owner = trader.owner;
+        trader.owner = trader.pending_owner;
+        trader.pending_owner = Pubkey::default();
+        emit!(TraderOwnershipTransferred {
+            previous_owner,
+            new_owner: trader.owner,
+        });
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct StakeTokens<'info> {
-    /// Trader state account (must be pre-initialized).
-    #[account(mut, has_one = owner)]
-    pub trader: Account<'info, Trader>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// Trader’s HFRT token account.
-    #[account(mut)]
-    pub trader_token_account: Account<'info, TokenAccount>,
-    /// Staking vault PDA (seeded by "staking-vault").
-    #[account(
-        mut,
-        seeds = [VAULT_SEED],
-        bump,
-    )]
-    /// CHECK: This PDA holds staked tokens.
-    pub staking_vault: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+    /// Configures the anti-whale curve: past `diminishing_threshold` lifetime claimed rebates, payouts shrink
+    /// in proportion, down to `diminishing_floor_bps` of the undiminished amount. A zero threshold disables it.
+    pub fn set_diminishing_returns(
+        ctx: Context<UpdateGovernance>,
+        diminishing_threshold: u64,
+        diminishing_floor_bps: u16,
+    ) -> Result<()> {
+        require!(diminishing_floor_bps <= 10_000, ErrorCode::InvalidDiminishingParams);
+        let gov = &mut ctx.accounts.governance;
+        gov.diminishing_threshold = diminishing_threshold;
+        gov.diminishing_floor_bps = diminishing_floor_bps;
+        Ok(())
+    }
+
+    /// Configures the account age after which traders earn a veteran rebate bonus.
+    /// A zero `veteran_age` disables the bonus.
+    pub fn set_veteran_params(ctx: Context<UpdateGovernance>, veteran_age: i64, veteran_bonus_bps: u16) -> Result<()> {
+        require!(veteran_age >= 0 && veteran_bonus_bps <= 10_000, ErrorCode::InvalidVeteranParams);
+        let gov = &mut ctx.accounts.governance;
+        gov.veteran_age = veteran_age;
+        gov.veteran_bonus_bps = veteran_bonus_bps;
+        Ok(())
+    }
+
+    /// Pins the canonical governance account. Every instruction that mints, pays or enforces trade limits
+    /// from governance settings requires this account, so a self-made governance cannot supply its own values.
+    pub fn set_governance(ctx: Context<SetGovernance>) -> Result<()> {
+        ctx.accounts.global_state.governance = ctx.accounts.governance.key();
+        Ok(())
+    }
+
+    /// Sets the trusted off-chain reporter allowed to record trades for any trader.
+    /// `Pubkey::default()` disables third-party reporting.
+    pub fn set_trusted_reporter(ctx: Context<UpdateGlobalState>, trusted_reporter: Pubkey) -> Result<()> {
+        ctx.accounts.global_state.trusted_reporter = trusted_reporter;
+        Ok(())
+    }
+
+    /// Schedules a fee discount change that takes effect at `effective_at`, replacing any pending schedule.
+    /// Until then `current_fee_discount` keeps returning the old value.
+    pub fn schedule_fee_discount(ctx: Context<UpdateGlobalState>, new_discount: u8, effective_at: i64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(effective_at > clock.unix_timestamp, ErrorCode::InvalidFeeSchedule);
+        let state = &mut ctx.accounts.global_state;
+        state.scheduled_fee_discount = new_discount;
+        state.fee_discount_effective_at = effective_at;
+        emit!(FeeDiscountScheduled {
+            new_discount,
+            effective_at,
+        });
+        Ok(())
+    }
+
+    /// Permissionless keeper call that applies a scheduled fee discount once it is due.
+    pub fn apply_scheduled_discount(ctx: Context<ApplyScheduledDiscount>) -> Result<()> {
+        let clock = Clock::get()?;
+        let state = &mut ctx.accounts.global_state;
+        require!(
+            state.fee_discount_effective_at != 0 && clock.unix_timestamp >= state.fee_discount_effective_at,
+            ErrorCode::FeeScheduleNotDue
+        );
+        state.fee_discount = state.scheduled_fee_discount;
+        state.scheduled_fee_discount = 0;
+        state.fee_discount_effective_at = 0;
+        Ok(())
+    }
+
+    /// Records a trade by updating the trader’s 24-hour rolling volume.
+    /// Resets the volume if more than 24 hours have elapsed.
+    /// Checks for wash trades and for too-frequent trading (sybil resistance).
+    /// The signer must be the trader's owner or the trusted reporter.
+    /// With `market` set, the volume is routed to that market's `MarketVolume` bucket instead of the
+    /// aggregate rolling volume; the bucket account must be passed as `market_volume`.
+    /// With `is_maker` set, the single-trade wash check only blocks self-matching: a fill on one side
+    /// shortly after a similar-sized fill on the other.
+    pub fn record_trade(
+        ctx: Context<RecordTrade>,
+        trade_amount: u64,
+        market: Option<Pubkey>,
+        is_maker: Option<bool>,
+        venue_type: Option<u8>,
+    ) -> Result<()> {
+        let checkpoint = compute_checkpoint(&ctx.accounts.governance);
+        // Checked before any other guard so a paused program always reports the pause; the event stays
+        // readable from the failed transaction's logs.
+        if ctx.accounts.global_state.paused {
+            emit!(TradeRejectedPaused {
+                owner: ctx.accounts.trader.owner,
+            });
+            return err!(ErrorCode::ProgramPaused);
+        }
+        if let Some(venue) = venue_type {
+            require!(market.is_none() && (venue == VENUE_SPOT || venue == VENUE_PERP), ErrorCode::InvalidVenueType);
+        }
+        require!(
+            is_authorized_reporter(&ctx.accounts.trader, &ctx.accounts.global_state, &ctx.accounts.reporter.key()),
+            ErrorCode::UnauthorizedReporter
+        );
+        let gov = &ctx.accounts.governance;
+        let trader = &mut ctx.accounts.trader;
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+        let recorded = apply_trade(
+            trader,
+            gov,
+            &mut ctx.accounts.global_state,
+            ctx.accounts.market_volume.as_deref_mut(),
+            trade_amount,
+            market,
+            is_maker,
+            venue_type,
+            current_time,
+        )?;
+        if !recorded {
+            emit_compute_used(PROFILE_RECORD_TRADE, checkpoint);
+            return Ok(());
+        }
+
+        // Opted-in traders compound their rebate inline once the reinvest cooldown has passed.
+        if trader.auto_reinvest && market.is_none() && !trader.blocked {
+            let since_reinvest = safe_elapsed(trader.last_reinvest_at, current_time, gov.reject_negative_elapsed)?;
+            let pending_rebate = trader_rebate(trader, gov, &ctx.accounts.global_state, current_time)?;
+            let pending_rebate = apply_bps_bonus(pending_rebate, streak_boost_bps(trader, gov))?;
+            // A claim or compound already made this slot defers the reinvest instead of failing the trade.
+            // So does a reinvest that would breach the global stake cap, or one while rebates are paused.
+            if pending_rebate > 0
+                && !ctx.accounts.global_state.rebate_paused
+                && since_reinvest >= gov.reinvest_cooldown
+                && trader.last_rebate_slot != clock.slot
+                && !exceeds_global_stake_cap(&ctx.accounts.global_state, pending_rebate)
+            {
+                let (Some(hfrt_mint), Some(staking_vault), Some(mint_authority), Some(token_program)) = (
+                    ctx.accounts.hfrt_mint.as_ref(),
+                    ctx.accounts.staking_vault.as_ref(),
+                    ctx.accounts.mint_authority.as_ref(),
+                    ctx.accounts.token_program.as_ref(),
+                ) else {
+                    return Err(ErrorCode::ReinvestAccountsMissing.into());
+                };
+                let rebate_amount = take_compound_rebate(trader, gov, &ctx.accounts.global_state)?;
+                check_supply_cap(&ctx.accounts.global_state, hfrt_mint.supply, rebate_amount)?;
+                record_epoch_mint(&mut ctx.accounts.global_state, rebate_amount)?;
+                let cpi_accounts = MintTo {
+                    mint: hfrt_mint.to_account_info(),
+                    to: staking_vault.to_account_info(),
+                    authority: mint_authority.to_account_info(),
+                };
+                let bump = ctx.accounts.global_state.mint_authority_bump;
+                let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+                token::mint_to(
+                    CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds),
+                    rebate_amount,
+                )?;
+                credit_compounded_stake(trader, &mut ctx.accounts.global_state, rebate_amount)?;
+                track_rebate_minted(&mut ctx.accounts.global_state, rebate_amount);
+                trader.last_reinvest_at = current_time;
+            }
+        }
+        emit_compute_used(PROFILE_RECORD_TRADE, checkpoint);
+        Ok(())
+    }
+
+    /// Records several fills into the aggregate rolling volume in one call.
+    /// Wash checks run per element, never on the sum: the fills are treated as arriving in order at the
+    /// current time, so the first is checked against the trader's last trade and each later one against
+    /// the fill before it with zero elapsed time, plus the recent-trade window. A batch therefore trips
+    /// whenever the same fills reported one by one would. The frequency check applies once to the call.
+    /// In strict mode any wash element aborts the whole batch; in soft mode it is flagged and skipped.
+    pub fn record_trade_batch(ctx: Context<RecordTradeBatch>, trade_amounts: Vec<u64>) -> Result<()> {
+        let checkpoint = compute_checkpoint(&ctx.accounts.governance);
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        require!(
+            !trade_amounts.is_empty() && trade_amounts.len() <= MAX_BATCH_TRADES,
+            ErrorCode::InvalidBatchSize
+        );
+        require!(
+            is_authorized_reporter(&ctx.accounts.trader, &ctx.accounts.global_state, &ctx.accounts.reporter.key()),
+            ErrorCode::UnauthorizedReporter
+        );
+        let gov = &ctx.accounts.governance;
+        let trader = &mut ctx.accounts.trader;
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+        decay_wash_flags(trader, gov, current_time);
+        let elapsed = safe_elapsed(trader.last_update, current_time, gov.reject_negative_elapsed)?;
+
+        if detect_frequent_trades(elapsed) {
+            if trader.fast_trade_count >= gov.frequent_trade_allowance {
+                let next_eligible_trade_time = trader.last_update.saturating_add(MIN_TRADE_INTERVAL);
+                return Err(throttle_trade(trader, next_eligible_trade_time, ErrorCode::FrequentTrades));
+            }
+            trader.fast_trade_count += 1;
+        } else {
+            trader.fast_trade_count = 0;
+        }
+
+        let mut rolling_volume = trader.rolling_volume;
+        let mut volume_clamped = false;
+        let mut total_amount: u64 = 0;
+        let mut flagged_count: u8 = 0;
+        for (index, &trade_amount) in trade_amounts.iter().enumerate() {
+            let element_elapsed = if index == 0 { elapsed } else { 0 };
+            if is_wash_trade(element_elapsed, trade_amount, gov.wash_threshold_inclusive)
+                || is_window_wash_trade(trader, current_time, trade_amount, gov.wash_window_secs, gov.wash_window_volume)
+            {
+                if !gov.soft_wash_handling {
+                    let next_eligible_trade_time = current_time.saturating_add(WASH_TRADE_INTERVAL);
+                    return Err(throttle_trade(trader, next_eligible_trade_time, ErrorCode::WashTrade));
+                }
+                flag_wash_trade(trader, current_time)?;
+                flagged_count += 1;
+                push_recent_trade(trader, current_time, trade_amount);
+                continue;
+            }
+            let volume = accumulate_volume(rolling_volume, element_elapsed, trade_amount)?;
+            let (volume, clamped) = clamp_volume(volume, gov.max_rolling_volume);
+            rolling_volume = volume;
+            volume_clamped |= clamped;
+            total_amount = total_amount.checked_add(trade_amount).ok_or(ErrorCode::VolumeOverflow)?;
+            push_recent_trade(trader, current_time, trade_amount);
+        }
+        if rolling_volume > trader.rolling_volume {
+            trader.last_volume_increase = current_time;
+        }
+        trader.rolling_volume = rolling_volume;
+        credit_venue_volume(trader, None, elapsed, 0);
+        trader.last_update = current_time;
+        track_volume_recorded(&mut ctx.accounts.global_state, total_amount, current_time);
+        accrue_loyalty_points(trader, gov, total_amount);
+        trader.next_eligible_trade_time = if trader.fast_trade_count >= gov.frequent_trade_allowance {
+            current_time.saturating_add(MIN_TRADE_INTERVAL)
+        } else {
+            current_time
+        };
+
+        let current_multiplier = calculate_rebate_multiplier(
+            rolling_volume,
+            &ctx.accounts.global_state.tier_thresholds,
+            gov.inclusive_tier_boundaries,
+        );
+        if current_multiplier > trader.max_tier_reached {
+            trader.max_tier_reached = current_multiplier;
+        }
+        emit!(TradeBatchRecorded {
+            owner: trader.owner,
+            trade_count: trade_amounts.len() as u8,
+            flagged_count,
+            total_amount,
+            rolling_volume,
+            current_multiplier,
+            volume_clamped,
+        });
+        emit_compute_used(PROFILE_RECORD_TRADE_BATCH, checkpoint);
+        Ok(())
+    }
+
+    /// Records fills across several venues in one call. Each fill's `market_index` selects one of the
+    /// trader's `MarketVolume` buckets passed as writable remaining accounts (at most `MAX_BATCH_MARKETS`).
+    /// Wash checks run per fill against that bucket's last update, as in `record_trade_batch`; the
+    /// frequency check applies once to the call. Emits one `MarketTradesRecorded` per bucket that had fills.
+    pub fn batch_record_trades_for_markets<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BatchRecordTradesForMarkets<'info>>,
+        fills: Vec<MarketFill>,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        require!(!fills.is_empty() && fills.len() <= MAX_BATCH_TRADES, ErrorCode::InvalidBatchSize);
+        require!(ctx.remaining_accounts.len() <= MAX_BATCH_MARKETS, ErrorCode::TooManyMarkets);
+        require!(
+            is_authorized_reporter(&ctx.accounts.trader, &ctx.accounts.global_state, &ctx.accounts.reporter.key()),
+            ErrorCode::UnauthorizedReporter
+        );
+        let gov = &ctx.accounts.governance;
+        let trader = &mut ctx.accounts.trader;
+        let clock = Clock::get()?;
+        let current_time = clock.unix_timestamp;
+        decay_wash_flags(trader, gov, current_time);
+        let elapsed = safe_elapsed(trader.last_update, current_time, gov.reject_negative_elapsed)?;
+
+        if detect_frequent_trades(elapsed) {
+            if trader.fast_trade_count >= gov.frequent_trade_allowance {
+                let next_eligible_trade_time = trader.last_update.saturating_add(MIN_TRADE_INTERVAL);
+                return Err(throttle_trade(trader, next_eligible_trade_time, ErrorCode::FrequentTrades));
+            }
+            trader.fast_trade_count += 1;
+        } else {
+            trader.fast_trade_count = 0;
+        }
+
+        let mut market_volumes: Vec<Account<'info, MarketVolume>> = Vec::with_capacity(ctx.remaining_accounts.len());
+        for info in ctx.remaining_accounts.iter() {
+            require!(
+                market_volumes.iter().all(|market_volume| market_volume.key() != info.key()),
+                ErrorCode::MarketMismatch
+            );
+            let market_volume = Account::<MarketVolume>::try_from(info)?;
+            require!(market_volume.owner == trader.owner, ErrorCode::MarketMismatch);
+            market_volumes.push(market_volume);
+        }
+
+        let mut fill_counts = vec![0u8; market_volumes.len()];
+        let mut totals = vec![0u64; market_volumes.len()];
+        let mut clamped = vec![false; market_volumes.len()];
+        for fill in fills.iter() {
+            let index = fill.market_index as usize;
+            let market_volume = market_volumes.get_mut(index).ok_or(ErrorCode::MarketMismatch)?;
+            activate_market(trader, gov, market_volume)?;
+            let market_elapsed = safe_elapsed(market_volume.last_update, current_time, gov.reject_negative_elapsed)?;
+            if is_wash_trade(market_elapsed, fill.amount, gov.wash_threshold_inclusive)
+                || is_window_wash_trade(trader, current_time, fill.amount, gov.wash_window_secs, gov.wash_window_volume)
+            {
+                if !gov.soft_wash_handling {
+                    let next_eligible_trade_time = current_time.saturating_add(WASH_TRADE_INTERVAL);
+                    return Err(throttle_trade(trader, next_eligible_trade_time, ErrorCode::WashTrade));
+                }
+                flag_wash_trade(trader, current_time)?;
+                market_volume.last_update = current_time;
+                push_recent_trade(trader, current_time, fill.amount);
+                continue;
+            }
+            let volume = accumulate_volume(market_volume.rolling_volume, market_elapsed, fill.amount)?;
+            let (volume, volume_clamped) = clamp_volume(volume, gov.max_rolling_volume);
+            if volume > market_volume.rolling_volume {
+                trader.last_volume_increase = current_time;
+            }
+            market_volume.rolling_volume = volume;
+            market_volume.last_update = current_time;
+            fill_counts[index] += 1;
+            totals[index] = totals[index].checked_add(fill.amount).ok_or(ErrorCode::VolumeOverflow)?;
+            clamped[index] |= volume_clamped;
+            push_recent_trade(trader, current_time, fill.amount);
+            track_volume_recorded(&mut ctx.accounts.global_state, fill.amount, current_time);
+            accrue_loyalty_points(trader, gov, fill.amount);
+        }
+        trader.last_update = current_time;
+        trader.next_eligible_trade_time = if trader.fast_trade_count >= gov.frequent_trade_allowance {
+            current_time.saturating_add(MIN_TRADE_INTERVAL)
+        } else {
+            current_time
+        };
+
+        for (index, market_volume) in market_volumes.iter().enumerate() {
+            market_volume.exit(ctx.program_id)?;
+            if fill_counts[index] > 0 {
+                emit!(MarketTradesRecorded {
+                    owner: trader.owner,
+                    market: market_volume.market,
+                    fill_count: fill_counts[index],
+                    total_amount: totals[index],
+                    rolling_volume: market_volume.rolling_volume,
+                    volume_clamped: clamped[index],
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Creates the accrual account for `referrer`, who pays for it. Required before anyone can name them.
+    pub fn initialize_referrer_state(ctx: Context<InitializeReferrerState>) -> Result<()> {
+        let referrer_state = &mut ctx.accounts.referrer_state;
+        referrer_state.referrer = ctx.accounts.referrer.key();
+        referrer_state.pending_referral = 0;
+        referrer_state.total_withdrawn = 0;
+        referrer_state.bump = ctx.bumps.referrer_state;
+        Ok(())
+    }
+
+    /// Names the trader's referrer. Can be set once and never to the trader's own owner.
+    pub fn set_referrer(ctx: Context<SetReferrer>) -> Result<()> {
+        let trader = &mut ctx.accounts.trader;
+        require!(trader.referrer == Pubkey::default(), ErrorCode::ReferrerAlreadySet);
+        require!(ctx.accounts.referrer_state.referrer != trader.owner, ErrorCode::InvalidReferrer);
+        trader.referrer = ctx.accounts.referrer_state.referrer;
+        Ok(())
+    }
+
+    /// Mints the referrer's accrued referral rewards to their token account, which only has to exist now.
+    pub fn withdraw_referral(ctx: Context<WithdrawReferral>) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        let amount = ctx.accounts.referrer_state.pending_referral;
+        require!(amount > 0, ErrorCode::NoPendingReferral);
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, amount)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_context().with_signer(signer_seeds), amount)?;
+
+        let referrer_state = &mut ctx.accounts.referrer_state;
+        referrer_state.pending_referral = 0;
+        referrer_state.total_withdrawn = referrer_state.total_withdrawn.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+        emit!(ReferralWithdrawn {
+            referrer: referrer_state.referrer,
+            amount,
+            total_withdrawn: referrer_state.total_withdrawn,
+        });
+        Ok(())
+    }
+
+    /// Sets the share of each referred trader's rebate accrued to their referrer, in basis points.
+    pub fn set_referral_bps(ctx: Context<UpdateGovernance>, referral_bps: u16) -> Result<()> {
+        require!(referral_bps <= 10_000, ErrorCode::InvalidReferralBps);
+        ctx.accounts.governance.referral_bps = referral_bps;
+        Ok(())
+    }
+
+    /// Opts the trader in or out of compounding their rebate inline on every `record_trade`.
+    pub fn set_auto_reinvest(ctx: Context<SetAutoReinvest>, enabled: bool) -> Result<()> {
+        ctx.accounts.trader.auto_reinvest = enabled;
+        Ok(())
+    }
+
+    /// Sets the minimum seconds between inline reinvestments made by `record_trade`.
+    pub fn set_reinvest_cooldown(ctx: Context<UpdateGovernance>, reinvest_cooldown: i64) -> Result<()> {
+        require!(reinvest_cooldown >= 0, ErrorCode::InvalidReinvestCooldown);
+        ctx.accounts.governance.reinvest_cooldown = reinvest_cooldown;
+        Ok(())
+    }
+
+    /// Records `lamports` of priority fees paid by the trader, reimbursed in HFRT at the next rebate claim at
+    /// `governance.priority_rebate_bps` and converted at `governance.priority_hfrt_per_sol`. Only the trusted
+    /// reporter, which attests to the fees from the trader's landed transactions, may report; traders cannot
+    /// self-report. Reports are also capped at `governance.priority_spend_cap` per 24-hour window.
+    pub fn record_priority_spend(ctx: Context<RecordPrioritySpend>, lamports: u64) -> Result<()> {
+        let global_state = &ctx.accounts.global_state;
+        require!(!global_state.paused, ErrorCode::ProgramPaused);
+        require!(
+            global_state.trusted_reporter != Pubkey::default()
+                && ctx.accounts.reporter.key() == global_state.trusted_reporter,
+            ErrorCode::UnauthorizedReporter
+        );
+        let gov = &ctx.accounts.governance;
+        let trader = &mut ctx.accounts.trader;
+        let clock = Clock::get()?;
+        let window_elapsed =
+            safe_elapsed(trader.priority_window_start, clock.unix_timestamp, gov.reject_negative_elapsed)?;
+        if window_elapsed >= 24 * 3600 {
+            trader.priority_window_start = clock.unix_timestamp;
+            trader.priority_window_spend = 0;
+        }
+        let window_spend = trader.priority_window_spend.checked_add(lamports).ok_or(ErrorCode::Overflow)?;
+        require!(
+            gov.priority_spend_cap == 0 || window_spend <= gov.priority_spend_cap,
+            ErrorCode::PrioritySpendCapExceeded
+        );
+        trader.priority_window_spend = window_spend;
+        trader.pending_priority_spend =
+            trader.pending_priority_spend.checked_add(lamports).ok_or(ErrorCode::Overflow)?;
+        emit!(PrioritySpendRecorded {
+            owner: trader.owner,
+            lamports,
+            pending_priority_spend: trader.pending_priority_spend,
+        });
+        Ok(())
+    }
+
+    /// Configures priority-fee reimbursement: `priority_rebate_bps` of reported spend is reimbursed at claim,
+    /// minted as `priority_hfrt_per_sol` HFRT base units per SOL (0 disables reimbursement), and at most
+    /// `priority_spend_cap` lamports may be reported per trader per day (0 leaves it uncapped).
+    pub fn set_priority_rebate(
+        ctx: Context<UpdateGovernance>,
+        priority_rebate_bps: u16,
+        priority_spend_cap: u64,
+        priority_hfrt_per_sol: u64,
+    ) -> Result<()> {
+        require!(priority_rebate_bps <= 10_000, ErrorCode::InvalidPriorityRebateBps);
+        let gov = &mut ctx.accounts.governance;
+        gov.priority_rebate_bps = priority_rebate_bps;
+        gov.priority_spend_cap = priority_spend_cap;
+        gov.priority_hfrt_per_sol = priority_hfrt_per_sol;
+        Ok(())
+    }
+
+    /// Authority-only correction of a trader's rolling volume for dispute resolution.
+    pub fn force_reset_volume(ctx: Context<ForceResetVolume>, new_volume: u64) -> Result<()> {
+        let trader = &mut ctx.accounts.trader;
+        let old_volume = trader.rolling_volume;
+        trader.rolling_volume = new_volume;
+        clamp_venue_volumes(trader);
+        emit!(VolumeForceReset {
+            owner: trader.owner,
+            old: old_volume,
+            new: new_volume,
+            by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    /// Authority-only repair of a trader's stake start time (e.g. zeroed by a migration while stake exists).
+    /// The new time may not be in the future.
+    pub fn correct_stake_start_time(ctx: Context<CorrectStakeStartTime>, new_time: i64) -> Result<()> {
+        let clock = Clock::get()?;
+        require!(new_time <= clock.unix_timestamp, ErrorCode::InvalidStakeStartTime);
+        let trader = &mut ctx.accounts.trader;
+        let old_time = trader.stake_start_time;
+        trader.stake_start_time = new_time;
+        emit!(StakeStartCorrected {
+            owner: trader.owner,
+            old: old_time,
+            new: new_time,
+            by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    /// Permissionless maintenance: zeroes a trader's rolling volume once it is more than 24 hours stale.
+    /// The caller is paid `governance.keeper_reward` in HFRT, but only when volume was actually expired.
+    pub fn expire_stale_volume(ctx: Context<ExpireStaleVolume>) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        let clock = Clock::get()?;
+        let trader = &mut ctx.accounts.trader;
+        let elapsed = safe_elapsed(
+            trader.last_update,
+            clock.unix_timestamp,
+            ctx.accounts.governance.reject_negative_elapsed,
+        )?;
+        if trader.rolling_volume == 0 || elapsed < 24 * 3600 {
+            return Ok(());
+        }
+        let old_volume = trader.rolling_volume;
+        trader.rolling_volume = 0;
+        clamp_venue_volumes(trader);
+        emit!(VolumeForceReset {
+            owner: trader.owner,
+            old: old_volume,
+            new: 0,
+            by: ctx.accounts.keeper.key(),
+        });
+
+        let reward = ctx.accounts.governance.keeper_reward;
+        if reward == 0 {
+            return Ok(());
+        }
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, reward)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, reward)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_context().with_signer(signer_seeds), reward)?;
+        emit!(KeeperRewarded {
+            keeper: ctx.accounts.keeper.key(),
+            amount: reward,
+        });
+        Ok(())
+    }
+
+    /// Sets the HFRT amount minted to keepers for productive maintenance calls (0 disables rewards).
+    pub fn set_keeper_reward(ctx: Context<UpdateGovernance>, keeper_reward: u64) -> Result<()> {
+        ctx.accounts.governance.keeper_reward = keeper_reward;
+        Ok(())
+    }
+
+    /// Blocks or unblocks a trader for compliance. Blocked traders cannot claim, compound or unstake.
+    pub fn set_trader_blocked(ctx: Context<SetTraderBlocked>, blocked: bool) -> Result<()> {
+        ctx.accounts.trader.blocked = blocked;
+        Ok(())
+    }
+
+    /// Claims an HFRT rebate based on the recorded 24-hour trading volume.
+    /// The rebate is computed using the governance rebate rate and a multiplier.
+    ///
+    /// Composable via CPI: a calling program may use one of its own PDAs as the trader `owner`
+    /// (signing for it with `invoke_signed`) and pass a token account it controls as
+    /// `trader_token_account`. The mint itself is signed here with the `mint-authority` PDA,
+    /// so callers never supply HFRT signer seeds.
+    ///
+    /// A referred trader's claim also accrues `governance.referral_bps` of the rebate to the referrer's
+    /// `ReferrerState`, which must then be passed; the referrer mints it later with `withdraw_referral`.
+    pub fn claim_rebate(ctx: Context<ClaimRebate>) -> Result<()> {
+        let checkpoint = compute_checkpoint(&ctx.accounts.governance);
+        let claimed = take_claimable_rebate(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &ctx.accounts.global_state,
+        )?;
+        let rebate_amount = claimed.amount;
+        accrue_referral(
+            &ctx.accounts.trader,
+            &ctx.accounts.governance,
+            ctx.accounts.referrer_state.as_deref_mut(),
+            rebate_amount,
+        )?;
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, rebate_amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, rebate_amount)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_context().with_signer(signer_seeds), rebate_amount)?;
+        track_rebate_minted(&mut ctx.accounts.global_state, rebate_amount);
+
+        let owner = ctx.accounts.trader.owner;
+        let claim_nonce = ctx.accounts.trader.claim_nonce;
+        if let Some(receipt) = ctx.accounts.claim_receipt.as_mut() {
+            receipt.owner = owner;
+            receipt.claim_nonce = claim_nonce;
+            receipt.amount = rebate_amount;
+            receipt.claimed_at = Clock::get()?.unix_timestamp;
+            receipt.rebate_rate = claimed.rebate_rate;
+            receipt.multiplier = claimed.multiplier;
+            receipt.bump = ctx.bumps.claim_receipt.ok_or(ErrorCode::ClaimReceiptMissing)?;
+        }
+        ctx.accounts.trader.claim_nonce = claim_nonce.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        let volatility_mode = ctx.accounts.global_state.volatility_mode;
+        emit!(RebateClaimed {
+            owner,
+            rebate_amount,
+            volatility_mode,
+            veteran_bonus_applied: claimed.veteran_bonus_applied,
+            low_activity_boost_applied: claimed.low_activity_boost_applied,
+        });
+        emit_compute_used(PROFILE_CLAIM_REBATE, checkpoint);
+        Ok(())
+    }
+
+    /// Claims an HFRT rebate on a single market's volume bucket, tiered on that market's volume alone.
+    /// Referred traders accrue referral rewards as in `claim_rebate`.
+    pub fn claim_market_rebate(ctx: Context<ClaimMarketRebate>) -> Result<()> {
+        let claimed = claimable_rebate(
+            &ctx.accounts.trader,
+            ctx.accounts.market_volume.rolling_volume,
+            (0, 0),
+            0,
+            &ctx.accounts.governance,
+            &ctx.accounts.global_state,
+        )?;
+        mark_rebate_slot(&mut ctx.accounts.trader)?;
+        ctx.accounts.market_volume.rolling_volume = 0;
+        let rebate_amount = claimed.amount;
+        ctx.accounts.trader.lifetime_rebates = ctx.accounts.trader.lifetime_rebates.saturating_add(rebate_amount);
+        accrue_referral(
+            &ctx.accounts.trader,
+            &ctx.accounts.governance,
+            ctx.accounts.referrer_state.as_deref_mut(),
+            rebate_amount,
+        )?;
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, rebate_amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, rebate_amount)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_context().with_signer(signer_seeds), rebate_amount)?;
+        track_rebate_minted(&mut ctx.accounts.global_state, rebate_amount);
+
+        emit!(MarketRebateClaimed {
+            owner: ctx.accounts.trader.owner,
+            market: ctx.accounts.market_volume.market,
+            rebate_amount,
+            veteran_bonus_applied: claimed.veteran_bonus_applied,
+        });
+        Ok(())
+    }
+
+    /// Claims the rebate in one call, minting `stake_bps` of it to the staking vault as compounded stake
+    /// and the remainder to `trader_token_account`. Referred traders accrue referral rewards as in `claim_rebate`.
+    pub fn claim_rebate_split(ctx: Context<ClaimRebateSplit>, stake_bps: u16) -> Result<()> {
+        require!(stake_bps <= 10_000, ErrorCode::InvalidStakeBps);
+        let claimed = take_claimable_rebate(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &ctx.accounts.global_state,
+        )?;
+        let rebate_amount = claimed.amount;
+        accrue_referral(
+            &ctx.accounts.trader,
+            &ctx.accounts.governance,
+            ctx.accounts.referrer_state.as_deref_mut(),
+            rebate_amount,
+        )?;
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, rebate_amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, rebate_amount)?;
+        let (wallet_amount, staked_amount) = split_by_bps(rebate_amount, stake_bps)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        if wallet_amount > 0 {
+            token::mint_to(ctx.accounts.into_mint_to_context().with_signer(signer_seeds), wallet_amount)?;
+        }
+        if staked_amount > 0 {
+            token::mint_to(ctx.accounts.into_mint_to_vault_context().with_signer(signer_seeds), staked_amount)?;
+            credit_compounded_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, staked_amount)?;
+        }
+        track_rebate_minted(&mut ctx.accounts.global_state, rebate_amount);
+
+        emit!(RebateSplitClaimed {
+            owner: ctx.accounts.trader.owner,
+            wallet_amount,
+            staked_amount,
+            stake_bps,
+            veteran_bonus_applied: claimed.veteran_bonus_applied,
+        });
+        Ok(())
+    }
+
+    /// Sets the SPL token that `claim_rebate_in_reward_token` pays out from the funded reward vault.
+    pub fn set_reward_mint(ctx: Context<UpdateGlobalState>, reward_mint: Pubkey) -> Result<()> {
+        ctx.accounts.global_state.reward_mint = reward_mint;
+        Ok(())
+    }
+
+    /// Claims the rebate in the configured reward token instead of minting HFRT.
+    /// The HFRT-denominated rebate is rescaled to the reward mint's decimals and transferred from the reward vault.
+    /// Referred traders accrue the referral share in HFRT, as in `claim_rebate`.
+    pub fn claim_rebate_in_reward_token(ctx: Context<ClaimRebateInRewardToken>) -> Result<()> {
+        let rebate_amount = take_claimable_rebate(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &ctx.accounts.global_state,
+        )?
+        .amount;
+        accrue_referral(
+            &ctx.accounts.trader,
+            &ctx.accounts.governance,
+            ctx.accounts.referrer_state.as_deref_mut(),
+            rebate_amount,
+        )?;
+        let payout_amount = scale_to_decimals(
+            rebate_amount,
+            ctx.accounts.hfrt_mint.decimals,
+            ctx.accounts.reward_mint.decimals,
+        )?;
+        require!(
+            ctx.accounts.reward_vault.amount >= payout_amount,
+            ErrorCode::InsufficientRewardVault
+        );
+        let bump = ctx.bumps.reward_vault_authority;
+        let signer_seeds: &[&[&[u8]]] = &[&[REWARD_VAULT_AUTHORITY_SEED, &[bump]]];
+        token::transfer(
+            ctx.accounts.into_transfer_from_reward_vault_context().with_signer(signer_seeds),
+            payout_amount,
+        )?;
+
+        emit!(RewardTokenRebateClaimed {
+            owner: ctx.accounts.trader.owner,
+            rebate_amount,
+            payout_amount,
+            reward_mint: ctx.accounts.reward_mint.key(),
+        });
+        Ok(())
+    }
+
+    /// Spends `points` of the trader's loyalty points for `governance.point_redemption_rate` HFRT each,
+    /// minted to their token account.
+    pub fn redeem_points(ctx: Context<RedeemPoints>, points: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        require!(!ctx.accounts.trader.blocked, ErrorCode::AddressBlocked);
+        let rate = ctx.accounts.governance.point_redemption_rate;
+        require!(rate > 0, ErrorCode::PointsRedemptionDisabled);
+        let trader = &mut ctx.accounts.trader;
+        trader.loyalty_points = trader.loyalty_points.checked_sub(points).ok_or(ErrorCode::InsufficientPoints)?;
+        let hfrt_amount = points.checked_mul(rate).ok_or(ErrorCode::RebateOverflow)?;
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, hfrt_amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, hfrt_amount)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_context().with_signer(signer_seeds), hfrt_amount)?;
+        emit!(PointsRedeemed {
+            owner: ctx.accounts.trader.owner,
+            points,
+            hfrt_amount,
+            remaining_points: ctx.accounts.trader.loyalty_points,
+        });
+        Ok(())
+    }
+
+    /// Configures loyalty points: `points_per_volume` points accrue per `LOYALTY_VOLUME_UNIT` of credited
+    /// volume, and each point redeems for `point_redemption_rate` HFRT (0 disables redemption).
+    pub fn set_loyalty_points(
+        ctx: Context<UpdateGovernance>,
+        points_per_volume: u64,
+        point_redemption_rate: u64,
+    ) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        gov.points_per_volume = points_per_volume;
+        gov.point_redemption_rate = point_redemption_rate;
+        Ok(())
+    }
+
+    /// Read-only check that runs the same preconditions as `claim_rebate` without mutating state.
+    /// Emits a `ClaimEligibility` event whose reason code identifies the blocking condition.
+    pub fn can_claim(ctx: Context<CanClaim>) -> Result<()> {
+        let trader = &ctx.accounts.trader;
+        let governance = &ctx.accounts.governance;
+        let global_state = &ctx.accounts.global_state;
+        let payout = rebate_payout(
+            trader,
+            trader.rolling_volume,
+            (trader.spot_volume, trader.perp_volume),
+            priority_reimbursement(trader, governance)?,
+            governance,
+            global_state,
+            Clock::get()?.unix_timestamp,
+        )?;
+        let reason_code = claim_block_reason(trader, trader.rolling_volume, governance, global_state, payout.amount);
+        emit!(ClaimEligibility {
+            owner: trader.owner,
+            eligible: reason_code == CLAIM_ELIGIBLE,
+            reason_code,
+        });
+        Ok(())
+    }
+
+    /// Creates the staking vault for `stake_mint`: a token account at the vault authority PDA (seeded by
+    /// "staking-vault" and the mint) that is its own owner. Run once per mint, HFRT included, before it can
+    /// be staked. Authority-only.
+    pub fn initialize_stake_vault(ctx: Context<InitializeStakeVault>) -> Result<()> {
+        emit!(StakeVaultInitialized {
+            mint: ctx.accounts.stake_mint.key(),
+            vault: ctx.accounts.staking_vault.key(),
+        });
+        Ok(())
+    }
+
+    /// Creates the trader's stake position for a non-HFRT `stake_mint`, tracked separately from HFRT stake.
+    pub fn initialize_stake_position(ctx: Context<InitializeStakePosition>) -> Result<()> {
+        let position = &mut ctx.accounts.stake_position;
+        position.trader = ctx.accounts.trader.key();
+        position.mint = ctx.accounts.stake_mint.key();
+        position.bump = ctx.bumps.stake_position;
+        Ok(())
+    }
+
+    /// Stakes tokens by transferring them from the trader’s token account into the vault for `stake_mint`.
+    /// The stake start time becomes the amount-weighted average of the existing position and the new stake,
+    /// so adding to a position shifts the lock clock rather than inheriting the old lock.
+    /// HFRT stake is tracked on the trader; any other mint is tracked on its `StakePosition`.
+    pub fn stake_tokens(ctx: Context<StakeTokens>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        token::transfer(ctx.accounts.into_transfer_to_vault_context(), amount)?;
+        if ctx.accounts.stake_mint.key() != ctx.accounts.global_state.hfrt_mint {
+            let position = ctx.accounts.stake_position.as_mut().ok_or(ErrorCode::StakePositionRequired)?;
+            let clock = Clock::get()?;
+            position.stake_start_time = weighted_stake_start_time(
+                position.amount,
+                position.stake_start_time,
+                amount,
+                clock.unix_timestamp,
+            )?;
+            position.amount = position.amount.checked_add(amount).ok_or(ErrorCode::StakeOverflow)?;
+            emit!(PositionStaked {
+                owner: ctx.accounts.trader.owner,
+                mint: position.mint,
+                amount,
+                staked_amount: position.amount,
+            });
+            return Ok(());
+        }
+        credit_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, amount)?;
+        emit!(TokensStaked {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            staked_amount: ctx.accounts.trader.staked_amount,
+            total_stakers: ctx.accounts.global_state.total_stakers,
+        });
+        Ok(())
+    }
+
+    /// Stakes HFRT and records a trade in one transaction, so a new trader is set up in a single call.
+    /// The stake lands first; the trade then runs the same checks as an aggregate `record_trade` reported
+    /// by the owner, and either failing reverts both. Inline auto-reinvest is not attempted.
+    pub fn stake_and_record_trade(
+        ctx: Context<StakeAndRecordTrade>,
+        stake_amount: u64,
+        trade_amount: u64,
+    ) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        token::transfer(ctx.accounts.into_transfer_to_vault_context(), stake_amount)?;
+        credit_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, stake_amount)?;
+        emit!(TokensStaked {
+            owner: ctx.accounts.trader.owner,
+            amount: stake_amount,
+            staked_amount: ctx.accounts.trader.staked_amount,
+            total_stakers: ctx.accounts.global_state.total_stakers,
+        });
+        let current_time = Clock::get()?.unix_timestamp;
+        apply_trade(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &mut ctx.accounts.global_state,
+            None,
+            trade_amount,
+            None,
+            None,
+            None,
+            current_time,
+        )?;
+        Ok(())
+    }
+
+    /// Unstakes HFRT tokens by transferring them back from the staking vault.
+    /// Applies a dynamic unstake penalty based on staking duration.
+    /// While the trader's HFRT stake is above `governance.large_unstake_threshold`, it can only be unstaked
+    /// through `request_unstake` and `withdraw_unstake`.
+    pub fn unstake_tokens(ctx: Context<UnstakeTokens>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        let stake_mint = ctx.accounts.stake_mint.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, stake_mint.as_ref(), &[ctx.bumps.vault_authority]]];
+        if stake_mint != ctx.accounts.global_state.hfrt_mint {
+            require!(!ctx.accounts.trader.blocked, ErrorCode::AddressBlocked);
+            let position = ctx.accounts.stake_position.as_mut().ok_or(ErrorCode::StakePositionRequired)?;
+            let amount_after_penalty = debit_stake_position(position, &ctx.accounts.governance, amount)?;
+            let staked_amount = position.amount;
+            check_unstake_payout(&ctx.accounts.staking_vault, amount, amount_after_penalty)?;
+            token::transfer(
+                ctx.accounts.into_transfer_from_vault_context().with_signer(vault_seeds),
+                amount_after_penalty,
+            )?;
+            emit!(PositionUnstaked {
+                owner: ctx.accounts.trader.owner,
+                mint: stake_mint,
+                amount,
+                penalty: amount - amount_after_penalty,
+                staked_amount,
+            });
+            return Ok(());
+        }
+        require!(
+            !unstake_notice_required(&ctx.accounts.trader, &ctx.accounts.governance),
+            ErrorCode::UnstakeNoticeRequired
+        );
+        let amount_after_penalty = debit_unstake(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &mut ctx.accounts.global_state,
+            amount,
+        )?;
+        check_unstake_payout(&ctx.accounts.staking_vault, amount, amount_after_penalty)?;
+        token::transfer(
+            ctx.accounts.into_transfer_from_vault_context().with_signer(vault_seeds),
+            amount_after_penalty,
+        )?;
+        emit!(TokensUnstaked {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            penalty: amount - amount_after_penalty,
+            staked_amount: ctx.accounts.trader.staked_amount,
+            total_stakers: ctx.accounts.global_state.total_stakers,
+            new_stake_start_time: ctx.accounts.trader.stake_start_time,
+            fully_unstaked: ctx.accounts.trader.staked_amount == 0,
+        });
+        Ok(())
+    }
+
+    /// Chooses the penalty tier for a stake whose start time is ahead of the clock: the lowest penalty with
+    /// `min_penalty`, otherwise the highest. Has no effect while `reject_negative_elapsed` rejects such stakes.
+    pub fn set_skewed_stake_penalty(ctx: Context<UpdateGovernance>, min_penalty: bool) -> Result<()> {
+        ctx.accounts.governance.skewed_stake_min_penalty = min_penalty;
+        Ok(())
+    }
+
+    /// Chooses whether a partial unstake restarts the lock clock on the remaining stake.
+    pub fn set_partial_unstake_resets_clock(ctx: Context<UpdateGovernance>, resets_clock: bool) -> Result<()> {
+        ctx.accounts.governance.partial_unstake_resets_clock = resets_clock;
+        Ok(())
+    }
+
+    /// Stakes `amount` of `stake_mint` as a transferable position: a fresh supply-one position mint is
+    /// created and its token minted to the staker, then the mint authority is dropped. Whoever holds the
+    /// position token can redeem it with `unstake_position`; the stake is not tied to any `Trader`.
+    pub fn stake_tokens_as_position(ctx: Context<StakeTokensAsPosition>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        require!(amount > 0, ErrorCode::InvalidPositionAmount);
+        token::transfer(ctx.accounts.into_transfer_to_vault_context(), amount)?;
+
+        let clock = Clock::get()?;
+        let position = &mut ctx.accounts.tokenized_position;
+        position.position_mint = ctx.accounts.position_mint.key();
+        position.stake_mint = ctx.accounts.stake_mint.key();
+        position.amount = amount;
+        position.stake_start_time = clock.unix_timestamp;
+        position.bump = ctx.bumps.tokenized_position;
+
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_position_context().with_signer(signer_seeds), 1)?;
+        token::set_authority(
+            ctx.accounts.into_set_authority_context().with_signer(signer_seeds),
+            AuthorityType::MintTokens,
+            None,
+        )?;
+        if ctx.accounts.stake_mint.key() == ctx.accounts.global_state.hfrt_mint {
+            let state = &mut ctx.accounts.global_state;
+            require!(!exceeds_global_stake_cap(state, amount), ErrorCode::GlobalStakeCapExceeded);
+            state.total_staked = state.total_staked.checked_add(amount).ok_or(ErrorCode::StakeOverflow)?;
+        }
+        emit!(PositionTokenized {
+            owner: ctx.accounts.owner.key(),
+            position_mint: ctx.accounts.position_mint.key(),
+            stake_mint: ctx.accounts.stake_mint.key(),
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Redeems a tokenized position: the holder burns the position token and receives the staked tokens,
+    /// less the usual unstake penalty on the position's own clock. The position account is closed to them.
+    pub fn unstake_position(ctx: Context<UnstakePosition>) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        let clock = Clock::get()?;
+        let amount = ctx.accounts.tokenized_position.amount;
+        let staked_duration = stake_duration(
+            ctx.accounts.tokenized_position.stake_start_time,
+            clock.unix_timestamp,
+            &ctx.accounts.governance,
+        )?;
+        let penalty = calculate_dynamic_unstake_penalty(staked_duration, amount);
+        let amount_after_penalty = amount.checked_sub(penalty).ok_or(ErrorCode::PenaltyOverflow)?;
+
+        check_unstake_payout(&ctx.accounts.staking_vault, amount, amount_after_penalty)?;
+
+        token::burn(ctx.accounts.into_burn_position_context(), 1)?;
+        let stake_mint = ctx.accounts.stake_mint.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, stake_mint.as_ref(), &[ctx.bumps.vault_authority]]];
+        token::transfer(
+            ctx.accounts.into_transfer_from_vault_context().with_signer(vault_seeds),
+            amount_after_penalty,
+        )?;
+        if stake_mint == ctx.accounts.global_state.hfrt_mint {
+            let state = &mut ctx.accounts.global_state;
+            state.total_staked = state.total_staked.checked_sub(amount).ok_or(ErrorCode::StakeOverflow)?;
+        }
+        emit!(PositionRedeemed {
+            holder: ctx.accounts.holder.key(),
+            position_mint: ctx.accounts.position_mint.key(),
+            amount,
+            penalty,
+        });
+        Ok(())
+    }
+
+    /// Configures the minimum-notice unstake flow: while a trader's HFRT stake is above
+    /// `large_unstake_threshold`, unstakes must be requested and can be withdrawn after `unstake_notice_period`
+    /// seconds. A zero threshold disables it.
+    pub fn set_unstake_notice(
+        ctx: Context<UpdateGovernance>,
+        large_unstake_threshold: u64,
+        unstake_notice_period: i64,
+    ) -> Result<()> {
+        require!(unstake_notice_period >= 0, ErrorCode::InvalidUnstakeNotice);
+        let gov = &mut ctx.accounts.governance;
+        gov.large_unstake_threshold = large_unstake_threshold;
+        gov.unstake_notice_period = unstake_notice_period;
+        Ok(())
+    }
+
+    /// Requests a large unstake, recording it on a PDA that unlocks after the notice period.
+    pub fn request_unstake(ctx: Context<RequestUnstake>, amount: u64) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        let gov = &ctx.accounts.governance;
+        require!(unstake_notice_required(&ctx.accounts.trader, gov), ErrorCode::UnstakeNoticeNotRequired);
+        require!(ctx.accounts.trader.staked_amount >= amount, ErrorCode::InsufficientStake);
+        let clock = Clock::get()?;
+        let pending = &mut ctx.accounts.pending_unstake;
+        pending.trader = ctx.accounts.trader.key();
+        pending.amount = amount;
+        pending.unlock_at = clock
+            .unix_timestamp
+            .checked_add(gov.unstake_notice_period)
+            .ok_or(ErrorCode::Overflow)?;
+        pending.bump = ctx.bumps.pending_unstake;
+
+        emit!(UnstakeRequested {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            unlock_at: pending.unlock_at,
+        });
+        Ok(())
+    }
+
+    /// Executes a requested unstake once its notice period has elapsed, applying the usual penalty.
+    pub fn withdraw_unstake(ctx: Context<WithdrawUnstake>) -> Result<()> {
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        let clock = Clock::get()?;
+        require!(
+            clock.unix_timestamp >= ctx.accounts.pending_unstake.unlock_at,
+            ErrorCode::UnstakeNoticeNotElapsed
+        );
+        let amount = ctx.accounts.pending_unstake.amount;
+        let amount_after_penalty = debit_unstake(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &mut ctx.accounts.global_state,
+            amount,
+        )?;
+        check_unstake_payout(&ctx.accounts.staking_vault, amount, amount_after_penalty)?;
+        let hfrt_mint = ctx.accounts.global_state.hfrt_mint;
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, hfrt_mint.as_ref(), &[ctx.bumps.vault_authority]]];
+        token::transfer(
+            ctx.accounts.into_transfer_from_vault_context().with_signer(vault_seeds),
+            amount_after_penalty,
+        )?;
+        emit!(TokensUnstaked {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            penalty: amount - amount_after_penalty,
+            staked_amount: ctx.accounts.trader.staked_amount,
+            total_stakers: ctx.accounts.global_state.total_stakers,
+            new_stake_start_time: ctx.accounts.trader.stake_start_time,
+            fully_unstaked: ctx.accounts.trader.staked_amount == 0,
+        });
+        Ok(())
+    }
+
+    /// Read-only health check of the HFRT staking vault: emits its balance against `total_staked`.
+    /// Positive drift is expected as unstake penalties accrue in the vault.
+    pub fn get_vault_stats(ctx: Context<GetVaultStats>) -> Result<()> {
+        let vault_balance = ctx.accounts.staking_vault.amount;
+        let total_staked = ctx.accounts.global_state.total_staked;
+        let drift = i64::try_from(vault_balance as i128 - total_staked as i128).map_err(|_| ErrorCode::StakeOverflow)?;
+        emit!(VaultStats {
+            vault_balance,
+            total_staked,
+            drift,
+            total_stakers: ctx.accounts.global_state.total_stakers,
+        });
+        Ok(())
+    }
+
+    /// Read-only dashboard snapshot: emits `ProtocolSummary` with the global totals and active rates.
+    pub fn protocol_summary(ctx: Context<GetProtocolSummary>) -> Result<()> {
+        let state = &ctx.accounts.global_state;
+        emit!(ProtocolSummary {
+            total_volume: state.total_volume,
+            total_rebates_minted: state.total_rebates_minted,
+            total_staked: state.total_staked,
+            total_stakers: state.total_stakers,
+            fee_discount: current_fee_discount(state, Clock::get()?.unix_timestamp),
+            rebate_rate: ctx.accounts.governance.rebate_rate,
+            paused: state.paused,
+        });
+        Ok(())
+    }
+
+    /// Authority keeper that freezes many traders' volume and stake into `Snapshot` PDAs for airdrop
+    /// snapshot `snapshot_id`. Remaining accounts are `(trader, snapshot)` pairs; traders already captured
+    /// in this snapshot are skipped.
+    pub fn bulk_snapshot<'info>(
+        ctx: Context<'_, '_, 'info, 'info, BulkSnapshot<'info>>,
+        snapshot_id: u64,
+    ) -> Result<()> {
+        let pairs = ctx.remaining_accounts.chunks(2);
+        require!(
+            ctx.remaining_accounts.len() % 2 == 0 && (1..=MAX_BULK_SNAPSHOT).contains(&pairs.len()),
+            ErrorCode::InvalidBatchSize
+        );
+        let clock = Clock::get()?;
+        let id_bytes = snapshot_id.to_le_bytes();
+        let space = 8 + Snapshot::LEN;
+        let lamports = Rent::get()?.minimum_balance(space);
+        let mut recorded: u8 = 0;
+        let mut skipped: u8 = 0;
+        for pair in pairs {
+            let (trader_info, snapshot_info) = (&pair[0], &pair[1]);
+            let trader = Account::<Trader>::try_from(trader_info)?;
+            let (expected, bump) =
+                Pubkey::find_program_address(&[SNAPSHOT_SEED, &id_bytes, trader_info.key.as_ref()], ctx.program_id);
+            require_keys_eq!(snapshot_info.key(), expected, ErrorCode::SnapshotMismatch);
+            if snapshot_info.owner == ctx.program_id {
+                skipped += 1;
+                continue;
+            }
+            let signer_seeds: &[&[&[u8]]] = &[&[SNAPSHOT_SEED, &id_bytes, trader_info.key.as_ref(), &[bump]]];
+            create_pda_account(
+                &ctx.accounts.authority.to_account_info(),
+                snapshot_info,
+                &ctx.accounts.system_program.to_account_info(),
+                lamports,
+                space,
+                ctx.program_id,
+                signer_seeds,
+            )?;
+            let mut snapshot = Account::<Snapshot>::try_from_unchecked(snapshot_info)?;
+            snapshot.trader = trader_info.key();
+            snapshot.owner = trader.owner;
+            snapshot.snapshot_id = snapshot_id;
+            snapshot.rolling_volume = trader.rolling_volume;
+            snapshot.staked_amount = trader.staked_amount;
+            snapshot.lifetime_rebates = trader.lifetime_rebates;
+            snapshot.taken_at = clock.unix_timestamp;
+            snapshot.bump = bump;
+            snapshot.exit(ctx.program_id)?;
+            recorded += 1;
+        }
+        emit!(BulkSnapshotTaken {
+            snapshot_id,
+            recorded,
+            skipped,
+        });
+        Ok(())
+    }
+
+    /// Read-only preview of `unstake_tokens` at the current time: emits the penalty, net amount and tier.
+    pub fn simulate_unstake(ctx: Context<SimulateUnstake>, amount: u64) -> Result<()> {
+        let trader = &ctx.accounts.trader;
+        require!(trader.staked_amount >= amount, ErrorCode::InsufficientStake);
+        let clock = Clock::get()?;
+        let staked_duration = stake_duration(trader.stake_start_time, clock.unix_timestamp, &ctx.accounts.governance)?;
+        let (penalty_tier, _) = unstake_penalty_tier(staked_duration);
+        let penalty = blended_unstake_penalty(trader, &ctx.accounts.governance, clock.unix_timestamp, amount)?;
+        let free_amount = amount.min(free_unstake_remaining(trader, &ctx.accounts.governance, clock.unix_timestamp)?);
+        let penalty = penalty_on_excess(penalty, amount, free_amount);
+        let net = amount.checked_sub(penalty).ok_or(ErrorCode::PenaltyOverflow)?;
+        emit!(UnstakeSimulation {
+            amount,
+            penalty,
+            net,
+            penalty_tier,
+        });
+        Ok(())
+    }
+
+    /// Auto-compounds staking rewards by minting the rebate directly to the staking vault.
+    pub fn auto_compound(ctx: Context<AutoCompound>) -> Result<()> {
+        let rebate_amount = take_compound_rebate(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &ctx.accounts.global_state,
+        )?;
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, rebate_amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, rebate_amount)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_vault_context().with_signer(signer_seeds), rebate_amount)?;
+        credit_compounded_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, rebate_amount)?;
+        track_rebate_minted(&mut ctx.accounts.global_state, rebate_amount);
+        Ok(())
+    }
+
+    /// Read-only projection of a trader's staked balance after `periods` auto-compounds at current parameters.
+    /// Each period is assumed to rebuild today's rolling volume; rebates are priced on volume rather than
+    /// stake, so the balance grows by the same compounded rebate every period.
+    pub fn estimate_compound_growth(ctx: Context<EstimateCompoundGrowth>, periods: u32) -> Result<()> {
+        let trader = &ctx.accounts.trader;
+        let governance = &ctx.accounts.governance;
+        let now = Clock::get()?.unix_timestamp;
+        let per_period = trader_rebate(trader, governance, &ctx.accounts.global_state, now)?;
+        let per_period = apply_bps_bonus(per_period, streak_boost_bps(trader, governance))?;
+        let growth = per_period.checked_mul(periods as u64).ok_or(ErrorCode::RebateOverflow)?;
+        let projected_balance = trader.staked_amount.checked_add(growth).ok_or(ErrorCode::StakeOverflow)?;
+        emit!(GrowthProjection {
+            periods,
+            projected_balance,
+        });
+        Ok(())
+    }
+
+    /// Creates the relayer allowlist PDA. Authority-only, called once.
+    pub fn initialize_relayer_allowlist(ctx: Context<InitializeRelayerAllowlist>) -> Result<()> {
+        ctx.accounts.relayer_allowlist.relayers = Vec::new();
+        ctx.accounts.relayer_allowlist.bump = ctx.bumps.relayer_allowlist;
+        Ok(())
+    }
+
+    /// Adds a relayer to the allowlist so it may drive relayer instructions on behalf of traders.
+    pub fn approve_relayer(ctx: Context<UpdateRelayerAllowlist>, relayer: Pubkey) -> Result<()> {
+        let allowlist = &mut ctx.accounts.relayer_allowlist;
+        if !allowlist.relayers.contains(&relayer) {
+            require!(allowlist.relayers.len() < MAX_RELAYERS, ErrorCode::RelayerAllowlistFull);
+            allowlist.relayers.push(relayer);
+        }
+        Ok(())
+    }
+
+    /// Removes a relayer from the allowlist.
+    pub fn revoke_relayer(ctx: Context<UpdateRelayerAllowlist>, relayer: Pubkey) -> Result<()> {
+        ctx.accounts.relayer_allowlist.relayers.retain(|r| *r != relayer);
+        Ok(())
+    }
+
+    /// Auto-compounds a trader's rebate on their behalf. The signer must be an approved relayer.
+    pub fn relay_auto_compound(ctx: Context<RelayAutoCompound>) -> Result<()> {
+        require!(
+            ctx.accounts.relayer_allowlist.relayers.contains(&ctx.accounts.relayer.key()),
+            ErrorCode::UnauthorizedRelayer
+        );
+        let rebate_amount = take_compound_rebate(
+            &mut ctx.accounts.trader,
+            &ctx.accounts.governance,
+            &ctx.accounts.global_state,
+        )?;
+        check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, rebate_amount)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, rebate_amount)?;
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(ctx.accounts.into_mint_to_vault_context().with_signer(signer_seeds), rebate_amount)?;
+        credit_compounded_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, rebate_amount)?;
+        track_rebate_minted(&mut ctx.accounts.global_state, rebate_amount);
+        Ok(())
+    }
+
+    /// Compounds up to `MAX_BATCH_COMPOUND` traders, passed as writable remaining accounts, in one relayer
+    /// transaction. Only traders with `auto_reinvest` set are compounded; blocked traders, those inside
+    /// `governance.reinvest_cooldown` or already processed this slot, those with nothing to compound and
+    /// those that would breach the global stake cap are skipped. The total is minted to the vault at once.
+    pub fn auto_compound_all<'info>(ctx: Context<'_, '_, 'info, 'info, AutoCompoundAll<'info>>) -> Result<()> {
+        require!(
+            ctx.accounts.relayer_allowlist.relayers.contains(&ctx.accounts.relayer.key()),
+            ErrorCode::UnauthorizedRelayer
+        );
+        require!(
+            (1..=MAX_BATCH_COMPOUND).contains(&ctx.remaining_accounts.len()),
+            ErrorCode::InvalidBatchSize
+        );
+        require!(!ctx.accounts.global_state.paused, ErrorCode::ProgramPaused);
+        require!(!ctx.accounts.global_state.rebate_paused, ErrorCode::RebatesPaused);
+        let clock = Clock::get()?;
+        let gov = &ctx.accounts.governance;
+        let global_state = &mut ctx.accounts.global_state;
+        let mut total_amount: u64 = 0;
+        let mut compounded_count: u8 = 0;
+        let mut skipped_count: u8 = 0;
+        for info in ctx.remaining_accounts.iter() {
+            let mut trader = Account::<Trader>::try_from(info)?;
+            let since_reinvest =
+                safe_elapsed(trader.last_reinvest_at, clock.unix_timestamp, gov.reject_negative_elapsed)?;
+            let eligible = trader.auto_reinvest
+                && !trader.blocked
+                && since_reinvest >= gov.reinvest_cooldown
+                && trader.last_rebate_slot != clock.slot;
+            let pending_rebate = if eligible {
+                let rebate = trader_rebate(&trader, gov, global_state, clock.unix_timestamp)?;
+                apply_bps_bonus(rebate, streak_boost_bps(&trader, gov))?
+            } else {
+                0
+            };
+            if pending_rebate == 0 || exceeds_global_stake_cap(global_state, pending_rebate) {
+                skipped_count += 1;
+                continue;
+            }
+            let rebate_amount = take_compound_rebate(&mut trader, gov, global_state)?;
+            credit_compounded_stake(&mut trader, global_state, rebate_amount)?;
+            track_rebate_minted(global_state, rebate_amount);
+            trader.last_reinvest_at = clock.unix_timestamp;
+            trader.exit(ctx.program_id)?;
+            total_amount = total_amount.checked_add(rebate_amount).ok_or(ErrorCode::RebateOverflow)?;
+            compounded_count += 1;
+        }
+        if total_amount > 0 {
+            check_supply_cap(&ctx.accounts.global_state, ctx.accounts.hfrt_mint.supply, total_amount)?;
+            record_epoch_mint(&mut ctx.accounts.global_state, total_amount)?;
+            let bump = ctx.accounts.global_state.mint_authority_bump;
+            let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+            token::mint_to(ctx.accounts.into_mint_to_vault_context().with_signer(signer_seeds), total_amount)?;
+        }
+        emit!(AutoCompoundBatch {
+            relayer: ctx.accounts.relayer.key(),
+            compounded_count,
+            skipped_count,
+            total_amount,
+        });
+        Ok(())
+    }
+
+    /// Chooses whether negative elapsed time (clock skew) is rejected or treated as zero elapsed.
+    pub fn set_reject_negative_elapsed(ctx: Context<UpdateGovernance>, reject: bool) -> Result<()> {
+        ctx.accounts.governance.reject_negative_elapsed = reject;
+        Ok(())
+    }
+
+    /// Configures sliding-window wash detection: more than `wash_window_volume` traded across the
+    /// recent-trade buffer within `wash_window_secs` is treated as wash trading. A zero volume disables it.
+    pub fn set_wash_window(ctx: Context<UpdateGovernance>, wash_window_secs: i64, wash_window_volume: u64) -> Result<()> {
+        require!(wash_window_secs >= 0, ErrorCode::InvalidWashWindow);
+        let gov = &mut ctx.accounts.governance;
+        gov.wash_window_secs = wash_window_secs;
+        gov.wash_window_volume = wash_window_volume;
+        Ok(())
+    }
+
+    /// Sets how long a trader's rolling volume may go without growing before each further such window
+    /// drops their claim multiplier by one, down to 1. Zero disables the decay.
+    pub fn set_multiplier_decay_window(ctx: Context<UpdateGovernance>, multiplier_decay_window: i64) -> Result<()> {
+        require!(multiplier_decay_window >= 0, ErrorCode::InvalidMultiplierDecayWindow);
+        ctx.accounts.governance.multiplier_decay_window = multiplier_decay_window;
+        Ok(())
+    }
+
+    /// Sets how long a trader must go without a new wash flag for each flag to decay away. Zero keeps flags
+    /// forever.
+    pub fn set_wash_decay_window(ctx: Context<UpdateGovernance>, wash_decay_window: i64) -> Result<()> {
+        require!(wash_decay_window >= 0, ErrorCode::InvalidWashDecayWindow);
+        ctx.accounts.governance.wash_decay_window = wash_decay_window;
+        Ok(())
+    }
+
+    /// Caps how many markets a single trader may start recording volume in, bounding the `MarketVolume`
+    /// state one owner can put to use. Zero disables the cap.
+    pub fn set_max_markets_per_trader(ctx: Context<UpdateGovernance>, max_markets_per_trader: u16) -> Result<()> {
+        ctx.accounts.governance.max_markets_per_trader = max_markets_per_trader;
+        Ok(())
+    }
+
+    /// Sets the cap on any trader's rolling volume, limiting rebate exposure. Zero disables the cap.
+    pub fn set_max_rolling_volume(ctx: Context<UpdateGovernance>, max_rolling_volume: u64) -> Result<()> {
+        ctx.accounts.governance.max_rolling_volume = max_rolling_volume;
+        Ok(())
+    }
+
+    /// Chooses whether a volume exactly on a tier threshold earns the higher tier (`>=`) or not (`>`).
+    pub fn set_inclusive_tier_boundaries(ctx: Context<UpdateGovernance>, inclusive: bool) -> Result<()> {
+        ctx.accounts.governance.inclusive_tier_boundaries = inclusive;
+        Ok(())
+    }
+
+    /// Lets each trader unstake up to `allowance` HFRT per `period` seconds without penalty; the dynamic
+    /// penalty applies only to the excess. A zero allowance disables it.
+    pub fn set_free_unstake_allowance(ctx: Context<UpdateGovernance>, allowance: u64, period: i64) -> Result<()> {
+        require!(allowance == 0 || period > 0, ErrorCode::InvalidFreeUnstakePeriod);
+        let governance = &mut ctx.accounts.governance;
+        governance.free_unstake_allowance = allowance;
+        governance.free_unstake_period = period;
+        Ok(())
+    }
+
+    /// Requires `min_stake` staked HFRT for claims to earn a volume multiplier above 1. Zero disables it.
+    pub fn set_min_stake_for_multiplier(ctx: Context<UpdateGovernance>, min_stake: u64) -> Result<()> {
+        ctx.accounts.governance.min_stake_for_multiplier = min_stake;
+        Ok(())
+    }
+
+    /// Sets the smallest rebate a claim may mint; smaller claims fail with `RebateTooSmall`. Zero disables it.
+    pub fn set_min_rebate_to_mint(ctx: Context<UpdateGovernance>, min_rebate_to_mint: u64) -> Result<()> {
+        ctx.accounts.governance.min_rebate_to_mint = min_rebate_to_mint;
+        Ok(())
+    }
+
+    /// Turns on `ComputeUsed` events from the profiled instructions (`PROFILE_*`), for tuning compute budgets.
+    pub fn set_profiling_enabled(ctx: Context<UpdateGovernance>, profiling_enabled: bool) -> Result<()> {
+        ctx.accounts.governance.profiling_enabled = profiling_enabled;
+        Ok(())
+    }
+
+    /// Sets whether a trade of exactly `WASH_TRADE_THRESHOLD` is subject to the wash-trade check.
+    pub fn set_wash_threshold_inclusive(ctx: Context<UpdateGovernance>, wash_threshold_inclusive: bool) -> Result<()> {
+        ctx.accounts.governance.wash_threshold_inclusive = wash_threshold_inclusive;
+        Ok(())
+    }
+
+    /// Switches wash-trade handling between strict (reject the trade) and soft (record and flag it
+    /// without crediting rebate volume).
+    pub fn set_soft_wash_handling(ctx: Context<UpdateGovernance>, soft_wash_handling: bool) -> Result<()> {
+        ctx.accounts.governance.soft_wash_handling = soft_wash_handling;
+        Ok(())
+    }
+
+    /// Sets how many consecutive fast trades are tolerated before `FrequentTrades` trips.
+    /// The counter resets once the trader slows down.
+    pub fn set_frequent_trade_allowance(ctx: Context<UpdateGovernance>, frequent_trade_allowance: u8) -> Result<()> {
+        ctx.accounts.governance.frequent_trade_allowance = frequent_trade_allowance;
+        Ok(())
+    }
+
+    /// Sets the slash size above which a two-step propose/confirm flow is required, and its delay.
+    pub fn set_slash_params(
+        ctx: Context<UpdateGovernance>,
+        slash_confirm_threshold: u64,
+        slash_confirm_delay: i64,
+    ) -> Result<()> {
+        require!(slash_confirm_delay >= 0, ErrorCode::InvalidSlashParams);
+        let gov = &mut ctx.accounts.governance;
+        gov.slash_confirm_threshold = slash_confirm_threshold;
+        gov.slash_confirm_delay = slash_confirm_delay;
+        Ok(())
+    }
+
+    /// Authority-only recovery for a trader who cannot unstake normally (e.g. a frozen or closed token
+    /// account): moves `amount` of their HFRT stake from the vault to `recovery_token_account`, a fresh
+    /// account owned by the trader's owner. No penalty, lock or notice period applies.
+    pub fn force_unstake_to_owner(ctx: Context<ForceUnstakeToOwner>, amount: u64) -> Result<()> {
+        remove_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, amount)?;
+        let hfrt_mint = ctx.accounts.hfrt_mint.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, hfrt_mint.as_ref(), &[ctx.bumps.vault_authority]]];
+        token::transfer(ctx.accounts.into_transfer_from_vault_context().with_signer(vault_seeds), amount)?;
+
+        emit!(ForceUnstaked {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            destination: ctx.accounts.recovery_token_account.key(),
+            staked_amount: ctx.accounts.trader.staked_amount,
+            by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    /// Immediately slashes (burns) a trader's stake. Only allowed up to `governance.slash_confirm_threshold`;
+    /// larger slashes must go through `propose_slash` and `confirm_slash`.
+    pub fn slash_stake(ctx: Context<SlashStake>, amount: u64) -> Result<()> {
+        require!(
+            amount <= ctx.accounts.governance.slash_confirm_threshold,
+            ErrorCode::SlashRequiresConfirmation
+        );
+        remove_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, amount)?;
+        let hfrt_mint = ctx.accounts.hfrt_mint.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, hfrt_mint.as_ref(), &[ctx.bumps.vault_authority]]];
+        token::burn(ctx.accounts.into_burn_from_vault_context().with_signer(vault_seeds), amount)?;
+
+        emit!(StakeSlashed {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    /// First step of a large slash: records the pending slash on a PDA, confirmable after the delay.
+    pub fn propose_slash(ctx: Context<ProposeSlash>, amount: u64) -> Result<()> {
+        let gov = &ctx.accounts.governance;
+        require!(amount > gov.slash_confirm_threshold, ErrorCode::SlashBelowThreshold);
+        require!(ctx.accounts.trader.staked_amount >= amount, ErrorCode::InsufficientStake);
+        let clock = Clock::get()?;
+        let pending = &mut ctx.accounts.pending_slash;
+        pending.trader = ctx.accounts.trader.key();
+        pending.amount = amount;
+        pending.executable_at = clock
+            .unix_timestamp
+            .checked_add(gov.slash_confirm_delay)
+            .ok_or(ErrorCode::Overflow)?;
+        pending.bump = ctx.bumps.pending_slash;
+
+        emit!(SlashProposed {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            executable_at: pending.executable_at,
+        });
+        Ok(())
+    }
+
+    /// Second step of a large slash: executes the pending slash once its delay has elapsed.
+    pub fn confirm_slash(ctx: Context<ConfirmSlash>) -> Result<()> {
+        let clock = Clock::get()?;
+        let amount = ctx.accounts.pending_slash.amount;
+        require!(
+            clock.unix_timestamp >= ctx.accounts.pending_slash.executable_at,
+            ErrorCode::SlashDelayNotElapsed
+        );
+        remove_stake(&mut ctx.accounts.trader, &mut ctx.accounts.global_state, amount)?;
+        let hfrt_mint = ctx.accounts.hfrt_mint.key();
+        let vault_seeds: &[&[&[u8]]] = &[&[VAULT_SEED, hfrt_mint.as_ref(), &[ctx.bumps.vault_authority]]];
+        token::burn(ctx.accounts.into_burn_from_vault_context().with_signer(vault_seeds), amount)?;
+
+        emit!(StakeSlashed {
+            owner: ctx.accounts.trader.owner,
+            amount,
+            by: ctx.accounts.authority.key(),
+        });
+        Ok(())
+    }
+
+    /// Sets whether a proposal with exactly as many votes for as against executes. Off by default, so a tie
+    /// is rejected; `pass_threshold_bps` still applies either way.
+    pub fn set_ties_pass(ctx: Context<UpdateGovernance>, ties_pass: bool) -> Result<()> {
+        ctx.accounts.governance.ties_pass = ties_pass;
+        Ok(())
+    }
+
+    /// Sets the super-majority share of votes cast, in basis points, that "for" votes must reach
+    /// for a proposal to execute. Zero keeps the simple-majority rule alone.
+    pub fn set_pass_threshold(ctx: Context<UpdateGovernance>, pass_threshold_bps: u16) -> Result<()> {
+        require!(pass_threshold_bps <= 10_000, ErrorCode::InvalidPassThreshold);
+        ctx.accounts.governance.pass_threshold_bps = pass_threshold_bps;
+        Ok(())
+    }
+
+    /// Sets where a rejected proposal's bond goes: `BOND_FORFEIT_TREASURY`, `BOND_FORFEIT_BURN` or
+    /// `BOND_FORFEIT_REDISTRIBUTE` (pro-rata to "for" voters).
+    /// Proposals do not post a bond yet, so the policy has nothing to forfeit until one is required.
+    pub fn set_bond_forfeit_policy(ctx: Context<UpdateGovernance>, bond_forfeit_policy: u8) -> Result<()> {
+        require!(bond_forfeit_policy <= BOND_FORFEIT_REDISTRIBUTE, ErrorCode::InvalidBondForfeitPolicy);
+        ctx.accounts.governance.bond_forfeit_policy = bond_forfeit_policy;
+        Ok(())
+    }
+
+    /// Sets the treasury and whether closed executed proposals send their rent there instead of to the
+    /// proposer.
+    pub fn set_proposal_rent_destination(
+        ctx: Context<UpdateGovernance>,
+        treasury: Pubkey,
+        proposal_rent_to_treasury: bool,
+    ) -> Result<()> {
+        require!(!proposal_rent_to_treasury || treasury != Pubkey::default(), ErrorCode::TreasuryMissing);
+        let gov = &mut ctx.accounts.governance;
+        gov.treasury = treasury;
+        gov.proposal_rent_to_treasury = proposal_rent_to_treasury;
+        Ok(())
+    }
+
+    /// Configures the compounding boost for voting streaks: `streak_boost_bps` per consecutive proposal
+    /// voted on, up to `max_streak_boost_bps`.
+    pub fn set_streak_boost(
+        ctx: Context<UpdateGovernance>,
+        streak_boost_bps: u16,
+        max_streak_boost_bps: u16,
+    ) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        gov.streak_boost_bps = streak_boost_bps;
+        gov.max_streak_boost_bps = max_streak_boost_bps;
+        Ok(())
+    }
+
+    /// Sets which proposal kinds may be created: bit `1 << kind` enables `kind`.
+    pub fn set_allowed_proposal_kinds(ctx: Context<UpdateGovernance>, allowed_proposal_kinds: u32) -> Result<()> {
+        ctx.accounts.governance.allowed_proposal_kinds = allowed_proposal_kinds;
+        Ok(())
+    }
+
+    /// Creates a new DAO proposal to update the fee discount. `kind` must be enabled in
+    /// `governance.allowed_proposal_kinds`.
+    pub fn create_dao_proposal(
+        ctx: Context<CreateDAOProposal>,
+        proposal_id: u64,
+        kind: u8,
+        new_fee_discount: u8,
+    ) -> Result<()> {
+        require!(
+            kind == PROPOSAL_KIND_FEE_DISCOUNT && ctx.accounts.governance.allowed_proposal_kinds & (1 << kind) != 0,
+            ErrorCode::ProposalKindNotAllowed
+        );
+        let proposal = &mut ctx.accounts.dao_proposal;
+        proposal.proposal_id = proposal_id;
+        proposal.kind = kind;
+        proposal.proposer = ctx.accounts.proposer.key();
+        proposal.new_fee_discount = new_fee_discount;
+        proposal.votes_for = 0;
+        proposal.votes_against = 0;
+        proposal.voter_count = 0;
+        let now = Clock::get()?.unix_timestamp;
+        proposal.created_at = now;
+        let voting_period = ctx.accounts.governance.voting_period;
+        proposal.voting_ends_at = if voting_period > 0 {
+            now.checked_add(voting_period).ok_or(ErrorCode::Overflow)?
+        } else {
+            0
+        };
+        Ok(())
+    }
+
+    /// Votes on an existing DAO proposal, counting one vote, or the voter's staked HFRT under
+    /// `governance.stake_weighted_voting`. Passing a `VoteRecord` records the vote and its weight.
+    /// While fewer than `governance.early_voter_count` votes have been cast, a voter who passes their
+    /// `VoterState` and the mint accounts earns `governance.early_voter_reward` HFRT. Each wallet is
+    /// rewarded at most once per proposal and once per `early_voter_cooldown`, so early votes cannot be farmed.
+    pub fn vote_dao_proposal(ctx: Context<VoteDAOProposal>, vote_for: bool) -> Result<()> {
+        require!(voting_open(&ctx.accounts.dao_proposal, Clock::get()?.unix_timestamp), ErrorCode::VotingClosed);
+        require!(
+            proposal_aged(&ctx.accounts.dao_proposal, &ctx.accounts.governance, Clock::get()?.unix_timestamp),
+            ErrorCode::ProposalTooNew
+        );
+        let min_stake_age = ctx.accounts.governance.min_stake_age_to_vote;
+        if min_stake_age > 0 {
+            let trader = ctx.accounts.trader.as_ref().ok_or(ErrorCode::VoterStakeMissing)?;
+            let stake_age = safe_elapsed(
+                trader.stake_start_time,
+                Clock::get()?.unix_timestamp,
+                ctx.accounts.governance.reject_negative_elapsed,
+            )?;
+            require!(trader.is_staking && stake_age >= min_stake_age, ErrorCode::StakeTooNew);
+        }
+        let weight = if ctx.accounts.governance.stake_weighted_voting {
+            require!(ctx.accounts.vote_record.is_some(), ErrorCode::VoteRecordMissing);
+            let trader = ctx.accounts.trader.as_ref().ok_or(ErrorCode::VoterStakeMissing)?;
+            require!(
+                trader.staked_amount > 0 || ctx.accounts.governance.allow_zero_weight_votes,
+                ErrorCode::NoVotingPower
+            );
+            trader.staked_amount
+        } else {
+            1
+        };
+        let proposal_id = ctx.accounts.dao_proposal.proposal_id;
+        if let Some(trader) = ctx.accounts.trader.as_mut() {
+            record_vote_streak(trader, proposal_id);
+        }
+        let proposal_key = ctx.accounts.dao_proposal.key();
+        if let Some(vote_record) = ctx.accounts.vote_record.as_mut() {
+            vote_record.proposal = proposal_key;
+            vote_record.voter = ctx.accounts.voter.key();
+            vote_record.vote_for = vote_for;
+            vote_record.weight = weight;
+            vote_record.bump = ctx.bumps.vote_record.ok_or(ErrorCode::VoteRecordMissing)?;
+        }
+        let proposal = &mut ctx.accounts.dao_proposal;
+        let vote_index = proposal.voter_count;
+        proposal.voter_count = proposal.voter_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+        if vote_for {
+            proposal.votes_for = proposal.votes_for.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        } else {
+            proposal.votes_against = proposal.votes_against.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        }
+
+        let gov = &ctx.accounts.governance;
+        let Some(voter_state) = ctx.accounts.voter_state.as_mut() else {
+            return Ok(());
+        };
+        if vote_index >= gov.early_voter_count as u64 || gov.early_voter_reward == 0 {
+            return Ok(());
+        }
+        let clock = Clock::get()?;
+        let since_reward =
+            safe_elapsed(voter_state.last_early_reward_at, clock.unix_timestamp, gov.reject_negative_elapsed)?;
+        if voter_state.last_rewarded_proposal == proposal_key
+            || (voter_state.last_early_reward_at != 0 && since_reward < gov.early_voter_cooldown)
+        {
+            return Ok(());
+        }
+        let (Some(hfrt_mint), Some(voter_token_account), Some(mint_authority), Some(token_program)) = (
+            ctx.accounts.hfrt_mint.as_ref(),
+            ctx.accounts.voter_token_account.as_ref(),
+            ctx.accounts.mint_authority.as_ref(),
+            ctx.accounts.token_program.as_ref(),
+        ) else {
+            return Err(ErrorCode::EarlyVoterAccountsMissing.into());
+        };
+        let reward = gov.early_voter_reward;
+        check_supply_cap(&ctx.accounts.global_state, hfrt_mint.supply, reward)?;
+        record_epoch_mint(&mut ctx.accounts.global_state, reward)?;
+        let cpi_accounts = MintTo {
+            mint: hfrt_mint.to_account_info(),
+            to: voter_token_account.to_account_info(),
+            authority: mint_authority.to_account_info(),
+        };
+        let bump = ctx.accounts.global_state.mint_authority_bump;
+        let signer_seeds: &[&[&[u8]]] = &[&[MINT_AUTHORITY_SEED, &[bump]]];
+        token::mint_to(
+            CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds),
+            reward,
+        )?;
+        voter_state.last_rewarded_proposal = proposal_key;
+        voter_state.last_early_reward_at = clock.unix_timestamp;
+        voter_state.early_rewards_earned =
+            voter_state.early_rewards_earned.checked_add(reward).ok_or(ErrorCode::Overflow)?;
+        emit!(EarlyVoterRewarded {
+            voter: voter_state.voter,
+            proposal: proposal_key,
+            vote_index,
+            reward,
+        });
+        Ok(())
+    }
+
+    /// Switches a recorded vote to `new_choice` while voting is open, moving the vote's weight from the old
+    /// tally to the new one. Choosing the current side again is a no-op.
+    pub fn change_vote(ctx: Context<ChangeVote>, new_choice: bool) -> Result<()> {
+        require!(voting_open(&ctx.accounts.dao_proposal, Clock::get()?.unix_timestamp), ErrorCode::VotingClosed);
+        let vote_record = &mut ctx.accounts.vote_record;
+        if vote_record.vote_for == new_choice {
+            return Ok(());
+        }
+        let proposal = &mut ctx.accounts.dao_proposal;
+        let weight = vote_record.weight;
+        let (from, to) = if new_choice {
+            (&mut proposal.votes_against, &mut proposal.votes_for)
+        } else {
+            (&mut proposal.votes_for, &mut proposal.votes_against)
+        };
+        *from = from.checked_sub(weight).ok_or(ErrorCode::Overflow)?;
+        *to = to.checked_add(weight).ok_or(ErrorCode::Overflow)?;
+        vote_record.vote_for = new_choice;
+        emit!(VoteChanged {
+            proposal: proposal.key(),
+            voter: vote_record.voter,
+            vote_for: new_choice,
+            weight,
+        });
+        Ok(())
+    }
+
+    /// Creates the voter's early-reward PDA, which tracks when they were last rewarded.
+    pub fn initialize_voter_state(ctx: Context<InitializeVoterState>) -> Result<()> {
+        let voter_state = &mut ctx.accounts.voter_state;
+        voter_state.voter = ctx.accounts.voter.key();
+        voter_state.bump = ctx.bumps.voter_state;
+        Ok(())
+    }
+
+    /// Configures the early-voter bonus: the first `early_voter_count` votes on each proposal earn
+    /// `early_voter_reward` HFRT, at most once per wallet every `early_voter_cooldown` seconds.
+    pub fn set_early_voter_reward(
+        ctx: Context<UpdateGovernance>,
+        early_voter_count: u8,
+        early_voter_reward: u64,
+        early_voter_cooldown: i64,
+    ) -> Result<()> {
+        require!(early_voter_cooldown >= 0, ErrorCode::InvalidEarlyVoterParams);
+        let gov = &mut ctx.accounts.governance;
+        gov.early_voter_count = early_voter_count;
+        gov.early_voter_reward = early_voter_reward;
+        gov.early_voter_cooldown = early_voter_cooldown;
+        Ok(())
+    }
+
+    /// Configures vesting of compounded rewards: nothing unlocks for `compound_cliff` seconds, then the rest
+    /// unlocks linearly over `compound_vest_duration`. Zero for both leaves compounded rewards unlocked.
+    pub fn set_compound_vesting(
+        ctx: Context<UpdateGovernance>,
+        compound_cliff: i64,
+        compound_vest_duration: i64,
+    ) -> Result<()> {
+        require!(compound_cliff >= 0 && compound_vest_duration >= 0, ErrorCode::InvalidCompoundVesting);
+        let gov = &mut ctx.accounts.governance;
+        gov.compound_cliff = compound_cliff;
+        gov.compound_vest_duration = compound_vest_duration;
+        Ok(())
+    }
+
+    /// Configures stake-weighted voting, where each vote counts the voter's staked HFRT and must create a
+    /// `VoteRecord`. Zero-stake votes are rejected unless `allow_zero_weight_votes`, in which case they are
+    /// recorded for participation without moving the tally.
+    pub fn set_vote_weighting(
+        ctx: Context<UpdateGovernance>,
+        stake_weighted_voting: bool,
+        allow_zero_weight_votes: bool,
+    ) -> Result<()> {
+        let gov = &mut ctx.accounts.governance;
+        gov.stake_weighted_voting = stake_weighted_voting;
+        gov.allow_zero_weight_votes = allow_zero_weight_votes;
+        Ok(())
+    }
+
+    /// Applies several governance parameters in one call. Each `Some` field is validated as by its own
+    /// setter and all are checked before any is written. Rebate rates are left to their rate-limited setters.
+    pub fn update_governance_params(ctx: Context<UpdateGovernance>, params: GovernanceParams) -> Result<()> {
+        let non_negative = [
+            (params.reinvest_cooldown, ErrorCode::InvalidReinvestCooldown),
+            (params.multiplier_decay_window, ErrorCode::InvalidMultiplierDecayWindow),
+            (params.wash_decay_window, ErrorCode::InvalidWashDecayWindow),
+            (params.voting_period, ErrorCode::InvalidVotingPeriod),
+            (params.min_proposal_age, ErrorCode::InvalidMinProposalAge),
+            (params.min_stake_age_to_vote, ErrorCode::InvalidMinStakeAge),
+        ];
+        for (value, error) in non_negative {
+            require!(value.unwrap_or(0) >= 0, error);
+        }
+        require!(params.referral_bps.unwrap_or(0) <= 10_000, ErrorCode::InvalidReferralBps);
+        require!(params.pass_threshold_bps.unwrap_or(0) <= 10_000, ErrorCode::InvalidPassThreshold);
+
+        let governance = &mut ctx.accounts.governance;
+        if let Some(value) = params.referral_bps {
+            governance.referral_bps = value;
+        }
+        if let Some(value) = params.reinvest_cooldown {
+            governance.reinvest_cooldown = value;
+        }
+        if let Some(value) = params.keeper_reward {
+            governance.keeper_reward = value;
+        }
+        if let Some(value) = params.frequent_trade_allowance {
+            governance.frequent_trade_allowance = value;
+        }
+        if let Some(value) = params.soft_wash_handling {
+            governance.soft_wash_handling = value;
+        }
+        if let Some(value) = params.max_rolling_volume {
+            governance.max_rolling_volume = value;
+        }
+        if let Some(value) = params.multiplier_decay_window {
+            governance.multiplier_decay_window = value;
+        }
+        if let Some(value) = params.wash_decay_window {
+            governance.wash_decay_window = value;
+        }
+        if let Some(value) = params.min_rebate_to_mint {
+            governance.min_rebate_to_mint = value;
+        }
+        if let Some(value) = params.min_stake_for_multiplier {
+            governance.min_stake_for_multiplier = value;
+        }
+        if let Some(value) = params.pass_threshold_bps {
+            governance.pass_threshold_bps = value;
+        }
+        if let Some(value) = params.voting_period {
+            governance.voting_period = value;
+        }
+        if let Some(value) = params.min_proposal_age {
+            governance.min_proposal_age = value;
+        }
+        if let Some(value) = params.min_stake_age_to_vote {
+            governance.min_stake_age_to_vote = value;
+        }
+        emit!(GovernanceParamsUpdated {
+            governance: governance.key(),
+            params,
+        });
+        Ok(())
+    }
+
+    /// Sets how long new proposals stay open for voting; 0 leaves them open until executed.
+    pub fn set_voting_period(ctx: Context<UpdateGovernance>, voting_period: i64) -> Result<()> {
+        require!(voting_period >= 0, ErrorCode::InvalidVotingPeriod);
+        ctx.accounts.governance.voting_period = voting_period;
+        Ok(())
+    }
+
+    /// Sets how long a new proposal must wait before its first vote or execution, so a proposer cannot
+    /// propose and self-vote a trivial proposal through at once. Zero disables the wait.
+    pub fn set_min_proposal_age(ctx: Context<UpdateGovernance>, min_proposal_age: i64) -> Result<()> {
+        require!(min_proposal_age >= 0, ErrorCode::InvalidMinProposalAge);
+        ctx.accounts.governance.min_proposal_age = min_proposal_age;
+        Ok(())
+    }
+
+    /// Sets how long a voter's stake must have been held before they may vote; 0 lets anyone vote.
+    pub fn set_min_stake_age_to_vote(ctx: Context<UpdateGovernance>, min_stake_age_to_vote: i64) -> Result<()> {
+        require!(min_stake_age_to_vote >= 0, ErrorCode::InvalidMinStakeAge);
+        ctx.accounts.governance.min_stake_age_to_vote = min_stake_age_to_vote;
+        Ok(())
+    }
+
+    /// Executes a DAO proposal if it has passed, updating the fee discount. A proposal executes at most once.
+    /// Beyond a simple majority, "for" votes must reach `governance.pass_threshold_bps` of all votes cast.
+    /// A proposal with a voting deadline waits for it, as votes can still be changed until then.
+    pub fn execute_dao_proposal(ctx: Context<ExecuteDAOProposal>) -> Result<()> {
+        let proposal = &ctx.accounts.dao_proposal;
+        let now = Clock::get()?.unix_timestamp;
+        require!(!proposal.executed, ErrorCode::ProposalAlreadyExecuted);
+        require!(proposal_aged(proposal, &ctx.accounts.governance, now), ErrorCode::ProposalTooNew);
+        require!(proposal.voting_ends_at == 0 || now >= proposal.voting_ends_at, ErrorCode::VotingStillOpen);
+        let tie = proposal.votes_for == proposal.votes_against;
+        require!(
+            majority_reached(proposal.votes_for, proposal.votes_against, ctx.accounts.governance.ties_pass),
+            ErrorCode::ProposalRejected
+        );
+        require!(
+            meets_pass_threshold(proposal.votes_for, proposal.votes_against, ctx.accounts.governance.pass_threshold_bps),
+            ErrorCode::ProposalRejected
+        );
+        let global_state = &mut ctx.accounts.global_state;
+        global_state.fee_discount = proposal.new_fee_discount;
+        emit!(DAOProposalExecuted {
+            proposal_id: proposal.proposal_id,
+            votes_for: proposal.votes_for,
+            votes_against: proposal.votes_against,
+            tie,
+        });
+        ctx.accounts.dao_proposal.executed = true;
+        Ok(())
+    }
+
+    /// Closes an executed proposal. Its rent goes to the proposer, or to `governance.treasury` while
+    /// `governance.proposal_rent_to_treasury` is set.
+    pub fn close_dao_proposal(ctx: Context<CloseDAOProposal>) -> Result<()> {
+        require!(ctx.accounts.dao_proposal.executed, ErrorCode::ProposalNotExecuted);
+        let destination = if ctx.accounts.governance.proposal_rent_to_treasury {
+            ctx.accounts.treasury.as_ref().ok_or(ErrorCode::TreasuryMissing)?.to_account_info()
+        } else {
+            ctx.accounts.proposer.to_account_info()
+        };
+        emit!(DAOProposalClosed {
+            proposal_id: ctx.accounts.dao_proposal.proposal_id,
+            rent_destination: destination.key(),
+        });
+        ctx.accounts.dao_proposal.close(destination)
+    }
+}
+
+/// Creates a program-owned PDA the way Anchor's `init` does. `create_account` fails on an address that
+/// already holds lamports, so a pre-funded PDA is instead topped up to `lamports`, allocated and assigned.
+fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    target: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    lamports: u64,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let current_lamports = target.lamports();
+    if current_lamports == 0 {
+        let cpi_accounts = system_program::CreateAccount {
+            from: payer.clone(),
+            to: target.clone(),
+        };
+        return system_program::create_account(
+            CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds),
+            lamports,
+            space as u64,
+            owner,
+        );
+    }
+    let top_up = lamports.saturating_sub(current_lamports);
+    if top_up > 0 {
+        let cpi_accounts = system_program::Transfer {
+            from: payer.clone(),
+            to: target.clone(),
+        };
+        system_program::transfer(CpiContext::new(system_program.clone(), cpi_accounts), top_up)?;
+    }
+    let cpi_accounts = system_program::Allocate {
+        account_to_allocate: target.clone(),
+    };
+    system_program::allocate(
+        CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds),
+        space as u64,
+    )?;
+    let cpi_accounts = system_program::Assign {
+        account_to_assign: target.clone(),
+    };
+    system_program::assign(CpiContext::new_with_signer(system_program.clone(), cpi_accounts, signer_seeds), owner)
+}
+
+/// Whether a proposal is at least `governance.min_proposal_age` old, so it may be voted on or executed.
+fn proposal_aged(proposal: &DAOProposal, governance: &Governance, now: i64) -> bool {
+    now.saturating_sub(proposal.created_at) >= governance.min_proposal_age
+}
+
+/// The key a `VoteRecord` is seeded by besides its proposal: the trader account the vote is cast with, so a
+/// stake votes once per proposal even after `accept_trader_ownership` moves it to another wallet, otherwise
+/// the voter.
+fn vote_record_key(trader: Option<&Account<Trader>>, voter: Pubkey) -> Pubkey {
+    trader.map_or(voter, |trader| trader.key())
+}
+
+/// Whether a proposal still accepts votes: it is unexecuted and before its deadline, if it has one.
+fn voting_open(proposal: &DAOProposal, now: i64) -> bool {
+    !proposal.executed && (proposal.voting_ends_at == 0 || now < proposal.voting_ends_at)
+}
+
+/// Extends a voter's participation streak when `proposal_id` directly follows the last proposal they voted
+/// on, and restarts it at one when they skipped a proposal. Repeat votes on the same proposal do not count.
+fn record_vote_streak(trader: &mut Trader, proposal_id: u64) {
+    let streak = trader.consecutive_proposals_voted;
+    if streak > 0 && proposal_id == trader.last_voted_proposal_id {
+        return;
+    }
+    let follows_last = trader.last_voted_proposal_id.checked_add(1) == Some(proposal_id);
+    trader.consecutive_proposals_voted = if streak > 0 && follows_last {
+        streak.saturating_add(1)
+    } else {
+        1
+    };
+    trader.last_voted_proposal_id = proposal_id;
+}
+
+/// Returns the compounding boost for a trader's voting streak: `governance.streak_boost_bps` per
+/// consecutive proposal, capped at `max_streak_boost_bps`.
+fn streak_boost_bps(trader: &Trader, governance: &Governance) -> u16 {
+    let boost = trader.consecutive_proposals_voted as u64 * governance.streak_boost_bps as u64;
+    boost.min(governance.max_streak_boost_bps as u64) as u16
+}
+
+/// Whether a proposal out-votes its opposition. An exact tie passes only with `ties_pass`, and never
+/// when nobody voted.
+fn majority_reached(votes_for: u64, votes_against: u64, ties_pass: bool) -> bool {
+    votes_for > votes_against || (ties_pass && votes_for > 0 && votes_for == votes_against)
+}
+
+/// Returns true if `votes_for` is at least `threshold_bps` of all votes cast.
+fn meets_pass_threshold(votes_for: u64, votes_against: u64, threshold_bps: u16) -> bool {
+    let total_votes = votes_for as u128 + votes_against as u128;
+    votes_for as u128 * 10_000 >= total_votes * threshold_bps as u128
+}
+
+/// Validates the mint authority PDA for `mint_authority_bump` and writes a fresh global state.
+/// Shared by `initialize` and `ensure_initialized`.
+#[allow(clippy::too_many_arguments)]
+fn write_global_state(
+    state: &mut GlobalState,
+    program_id: &Pubkey,
+    authority: Pubkey,
+    hfrt_mint: &Account<Mint>,
+    mint_authority: Pubkey,
+    fee_discount: u8,
+    mint_authority_bump: u8,
+    bump: u8,
+) -> Result<()> {
+    let expected_mint_authority = Pubkey::create_program_address(&[MINT_AUTHORITY_SEED, &[mint_authority_bump]], program_id)
+        .map_err(|_| ErrorCode::InvalidMintAuthority)?;
+    require!(
+        expected_mint_authority == mint_authority && hfrt_mint.mint_authority == COption::Some(mint_authority),
+        ErrorCode::InvalidMintAuthority
+    );
+    state.mint_authority_bump = mint_authority_bump;
+    state.authority = authority;
+    state.hfrt_mint = hfrt_mint.key();
+    state.fee_discount = fee_discount;
+    state.tier_thresholds = scaled_tier_thresholds(hfrt_mint.decimals)?;
+    state.hfrt_vault = Pubkey::find_program_address(&[VAULT_SEED, hfrt_mint.key().as_ref()], program_id).0;
+    state.bump = bump;
+    Ok(())
+}
+
+/// Returns the staking vault for `stake_mint`: `global_state.hfrt_vault` for HFRT, otherwise the mint's
+/// vault authority PDA, which is its own token account.
+fn vault_address(global_state: &GlobalState, stake_mint: &Pubkey, vault_authority: &Pubkey) -> Pubkey {
+    if *stake_mint == global_state.hfrt_mint {
+        global_state.hfrt_vault
+    } else {
+        *vault_authority
+    }
+}
+
+/// Scales `BASE_TIER_THRESHOLDS` from whole tokens to raw amounts for a mint with `decimals`.
+fn scaled_tier_thresholds(decimals: u8) -> Result<[u64; 3]> {
+    let unit = 10u64.checked_pow(decimals as u32).ok_or(ErrorCode::Overflow)?;
+    let mut thresholds = [0u64; 3];
+    for (threshold, base) in thresholds.iter_mut().zip(BASE_TIER_THRESHOLDS) {
+        *threshold = base.checked_mul(unit).ok_or(ErrorCode::Overflow)?;
+    }
+    Ok(thresholds)
+}
+
+/// Returns the fee discount in force at `now`, honoring a scheduled change once it is due
+/// even before `apply_scheduled_discount` has run.
+pub fn current_fee_discount(global_state: &GlobalState, now: i64) -> u8 {
+    if global_state.fee_discount_effective_at != 0 && now >= global_state.fee_discount_effective_at {
+        global_state.scheduled_fee_discount
+    } else {
+        global_state.fee_discount
+    }
+}
+
+/// Returns a multiplier for the rebate based on the 24‑hour trading volume and the raw tier thresholds.
+/// With `inclusive_boundaries` a volume exactly on a tier threshold earns that tier (`>=`);
+/// otherwise it must exceed the threshold (`>`).
+fn calculate_rebate_multiplier(trade_volume: u64, tier_thresholds: &[u64; 3], inclusive_boundaries: bool) -> u8 {
+    let reaches = |threshold: u64| {
+        if inclusive_boundaries {
+            trade_volume >= threshold
+        } else {
+            trade_volume > threshold
+        }
+    };
+    if reaches(tier_thresholds[2]) {
+        MAX_REBATE_MULTIPLIER
+    } else if reaches(tier_thresholds[1]) {
+        3
+    } else if reaches(tier_thresholds[0]) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Rejects a rebate rate whose worst case (rate at `MAX_REBATE_MULTIPLIER`) would exceed
+/// `governance.max_effective_rebate_bps` of volume.
+fn validate_effective_rebate_rate(governance: &Governance, rebate_rate: u8) -> Result<()> {
+    let effective_bps = rebate_rate as u64 * MAX_REBATE_MULTIPLIER as u64 * 10_000 / rebate_denominator(governance);
+    require!(
+        effective_bps <= governance.max_effective_rebate_bps as u64,
+        ErrorCode::InvalidRebateRate
+    );
+    Ok(())
+}
+
+/// Returns the denominator rebate rates are divided by, falling back to the default when unset.
+fn rebate_denominator(governance: &Governance) -> u64 {
+    if governance.rebate_denominator > 0 {
+        governance.rebate_denominator
+    } else {
+        DEFAULT_REBATE_DENOMINATOR
+    }
+}
+
+/// Divides by a configured denominator. Setters reject zero denominators with `InvalidDenominator`, so a
+/// zero here means corrupt state and fails with `ZeroDenominator` rather than a misleading overflow.
+fn divide_by_denominator(numerator: u128, denominator: u64) -> Result<u128> {
+    require!(denominator > 0, ErrorCode::ZeroDenominator);
+    Ok(numerator / denominator as u128)
+}
+
+/// Rescales a rate from one rebate denominator to another, rounding down.
+fn rescale_rebate_rate(rate: u8, old_denominator: u64, new_denominator: u64) -> Result<u8> {
+    let rescaled = divide_by_denominator(rate as u128 * new_denominator as u128, old_denominator)?;
+    Ok(u8::try_from(rescaled).map_err(|_| ErrorCode::RebateRateOutOfRange)?)
+}
+
+/// Computes the rebate owed for a rolling volume: `volume * rate / denominator`, scaled by `multiplier`
+/// (see `rebate_multiplier`).
+/// The `(spot, perp)` parts of `rolling_volume` earn `governance.spot_rebate_rate` and `perp_rebate_rate`
+/// where those are set.
+fn calculate_rebate(
+    rolling_volume: u64,
+    venue_volumes: (u64, u64),
+    rebate_rate: u8,
+    multiplier: u8,
+    governance: &Governance,
+) -> Result<u64> {
+    let venue_rate = |rate: u8| if rate > 0 { rate } else { rebate_rate };
+    let spot_volume = venue_volumes.0.min(rolling_volume);
+    let perp_volume = venue_volumes.1.min(rolling_volume - spot_volume);
+    let other_volume = rolling_volume - spot_volume - perp_volume;
+    let weighted = spot_volume as u128 * venue_rate(governance.spot_rebate_rate) as u128
+        + perp_volume as u128 * venue_rate(governance.perp_rebate_rate) as u128
+        + other_volume as u128 * rebate_rate as u128;
+    let base_rebate = u64::try_from(divide_by_denominator(weighted, rebate_denominator(governance))?)
+        .map_err(|_| ErrorCode::RebateOverflow)?;
+    Ok(base_rebate.checked_mul(multiplier as u64).ok_or(ErrorCode::RebateOverflow)?)
+}
+
+/// Returns the multiplier every rebate payout (claims, market claims and compounds) and every estimate of
+/// one is scaled by: the volume tier of `rolling_volume`, decayed while the trader's volume stagnates and
+/// gated by their stake.
+fn rebate_multiplier(
+    rolling_volume: u64,
+    trader: &Trader,
+    governance: &Governance,
+    global_state: &GlobalState,
+    now: i64,
+) -> u8 {
+    let multiplier = calculate_rebate_multiplier(
+        rolling_volume,
+        &global_state.tier_thresholds,
+        governance.inclusive_tier_boundaries,
+    );
+    stake_gated_multiplier(decayed_multiplier(multiplier, trader, governance, now), trader, governance)
+}
+
+/// Computes the rebate on a trader's own rolling volume at the active rate, with venue rates applied.
+fn trader_rebate(trader: &Trader, governance: &Governance, global_state: &GlobalState, now: i64) -> Result<u64> {
+    let rebate_rate = effective_rebate_rate(governance, global_state, trader.rolling_volume);
+    calculate_rebate(
+        trader.rolling_volume,
+        (trader.spot_volume, trader.perp_volume),
+        rebate_rate,
+        rebate_multiplier(trader.rolling_volume, trader, governance, global_state, now),
+        governance,
+    )
+}
+
+/// Returns the rebate rate for the current regime: the elevated rate while volatility mode is on,
+/// otherwise the volume-interpolated rate in linear mode, otherwise the flat rate.
+fn effective_rebate_rate(governance: &Governance, global_state: &GlobalState, rolling_volume: u64) -> u8 {
+    if global_state.volatility_mode {
+        governance.volatile_rebate_rate
+    } else if governance.linear_rate_mode {
+        interpolate_rebate_rate(governance, rolling_volume)
+    } else {
+        governance.rebate_rate
+    }
+}
+
+/// Linearly interpolates between `linear_min_rate` and `linear_max_rate` as volume moves across
+/// `[linear_volume_min, linear_volume_max]`, clamping outside the range.
+fn interpolate_rebate_rate(governance: &Governance, rolling_volume: u64) -> u8 {
+    let (min_rate, max_rate) = (governance.linear_min_rate, governance.linear_max_rate);
+    let (volume_min, volume_max) = (governance.linear_volume_min, governance.linear_volume_max);
+    if rolling_volume <= volume_min || volume_max <= volume_min {
+        return min_rate;
+    }
+    if rolling_volume >= volume_max {
+        return max_rate;
+    }
+    let rate_span = (max_rate - min_rate) as u128;
+    let progress = (rolling_volume - volume_min) as u128;
+    let volume_span = (volume_max - volume_min) as u128;
+    min_rate + (rate_span * progress / volume_span) as u8
+}
+
+/// Returns the first condition blocking a rebate claim, or `CLAIM_ELIGIBLE`.
+/// Shared by the claims and `can_claim` so both always agree. `rebate_amount` is the final amount the claim
+/// would mint (see `rebate_payout`); below `governance.min_rebate_to_mint` it is rejected rather than carried
+/// forward, as the mint would cost more than it pays; the volume stays in place to claim later.
+fn claim_block_reason(
+    trader: &Trader,
+    rolling_volume: u64,
+    governance: &Governance,
+    global_state: &GlobalState,
+    rebate_amount: u64,
+) -> u8 {
+    if global_state.paused {
+        CLAIM_BLOCKED_PAUSED
+    } else if global_state.rebate_paused {
+        CLAIM_BLOCKED_REBATES_PAUSED
+    } else if trader.blocked {
+        CLAIM_BLOCKED_ADDRESS
+    } else if rolling_volume == 0 {
+        CLAIM_BLOCKED_NO_VOLUME
+    } else if rebate_amount == 0 {
+        CLAIM_BLOCKED_ZERO_REBATE
+    } else if rebate_amount < governance.min_rebate_to_mint {
+        CLAIM_BLOCKED_BELOW_MINIMUM
+    } else {
+        CLAIM_ELIGIBLE
+    }
+}
+
+/// Outcome of `take_claimable_rebate`: the amount to pay out, the rate and multiplier it was earned at,
+/// and which bonuses applied.
+struct ClaimedRebate {
+    amount: u64,
+    rebate_rate: u8,
+    multiplier: u8,
+    veteran_bonus_applied: bool,
+    low_activity_boost_applied: bool,
+}
+
+/// Runs the claim preconditions and consumes the trader's rolling volume, returning the rebate to pay out
+/// plus the reimbursement for any reported priority-fee spend.
+fn take_claimable_rebate(
+    trader: &mut Trader,
+    governance: &Governance,
+    global_state: &GlobalState,
+) -> Result<ClaimedRebate> {
+    let claimed = claimable_rebate(
+        trader,
+        trader.rolling_volume,
+        (trader.spot_volume, trader.perp_volume),
+        priority_reimbursement(trader, governance)?,
+        governance,
+        global_state,
+    )?;
+    mark_rebate_slot(trader)?;
+    trader.rolling_volume = 0;
+    clamp_venue_volumes(trader);
+    trader.pending_priority_spend = 0;
+    trader.lifetime_rebates = trader.lifetime_rebates.saturating_add(claimed.amount);
+    Ok(claimed)
+}
+
+/// Reduces a rebate multiplier by one for every full `governance.multiplier_decay_window` the trader's volume
+/// has gone without growing, never below 1.
+fn decayed_multiplier(multiplier: u8, trader: &Trader, governance: &Governance, now: i64) -> u8 {
+    if governance.multiplier_decay_window == 0 {
+        return multiplier;
+    }
+    let stagnant_windows = now.saturating_sub(trader.last_volume_increase).max(0) / governance.multiplier_decay_window;
+    let steps = u8::try_from(stagnant_windows).unwrap_or(u8::MAX);
+    multiplier.saturating_sub(steps).max(1)
+}
+
+/// Caps a rebate multiplier at 1 for traders staking less than `governance.min_stake_for_multiplier`.
+fn stake_gated_multiplier(multiplier: u8, trader: &Trader, governance: &Governance) -> u8 {
+    if trader.staked_amount < governance.min_stake_for_multiplier {
+        multiplier.min(1)
+    } else {
+        multiplier
+    }
+}
+
+/// Returns the compute units remaining at instruction entry while profiling is enabled.
+fn compute_checkpoint(governance: &Governance) -> Option<u64> {
+    governance.profiling_enabled.then(sol_remaining_compute_units)
+}
+
+/// Emits the compute units consumed since `checkpoint`, if profiling took one.
+fn emit_compute_used(instruction_id: u8, checkpoint: Option<u64>) {
+    if let Some(start) = checkpoint {
+        emit!(ComputeUsed {
+            instruction_id,
+            units: start.saturating_sub(sol_remaining_compute_units()),
+        });
+    }
+}
+
+/// Allows one rebate action (claim or compound) per trader per slot, recording this one.
+fn mark_rebate_slot(trader: &mut Trader) -> Result<()> {
+    let slot = Clock::get()?.slot;
+    require!(trader.last_rebate_slot != slot, ErrorCode::AlreadyProcessedThisSlot);
+    trader.last_rebate_slot = slot;
+    Ok(())
+}
+
+/// Runs the claim preconditions against the final payout for `rolling_volume`, of which `venue_volumes` is
+/// the `(spot, perp)` part, and returns it. The caller is responsible for zeroing whichever volume was
+/// claimed and the reimbursed priority spend.
+fn claimable_rebate(
+    trader: &Trader,
+    rolling_volume: u64,
+    venue_volumes: (u64, u64),
+    reimbursement: u64,
+    governance: &Governance,
+    global_state: &GlobalState,
+) -> Result<ClaimedRebate> {
+    let now = Clock::get()?.unix_timestamp;
+    let claimed = rebate_payout(trader, rolling_volume, venue_volumes, reimbursement, governance, global_state, now)?;
+    let reason_code = claim_block_reason(trader, rolling_volume, governance, global_state, claimed.amount);
+    if reason_code != CLAIM_ELIGIBLE {
+        return Err(claim_block_error(reason_code).into());
+    }
+    Ok(claimed)
+}
+
+/// Computes what a claim on `rolling_volume` mints: the rebate at the active rate and multiplier, with the
+/// veteran bonus, diminishing returns and low-activity boost applied, plus `reimbursement`. Claims and
+/// `can_claim` both check this final amount, so the minimum to mint always applies to what is minted.
+fn rebate_payout(
+    trader: &Trader,
+    rolling_volume: u64,
+    venue_volumes: (u64, u64),
+    reimbursement: u64,
+    governance: &Governance,
+    global_state: &GlobalState,
+    now: i64,
+) -> Result<ClaimedRebate> {
+    let rebate_rate = effective_rebate_rate(governance, global_state, rolling_volume);
+    let multiplier = rebate_multiplier(rolling_volume, trader, governance, global_state, now);
+    let rebate_amount = calculate_rebate(rolling_volume, venue_volumes, rebate_rate, multiplier, governance)?;
+    let account_age = safe_elapsed(trader.created_at, now, governance.reject_negative_elapsed)?;
+    let veteran_bonus_applied = governance.veteran_age > 0 && account_age >= governance.veteran_age;
+    let amount = if veteran_bonus_applied {
+        apply_bps_bonus(rebate_amount, governance.veteran_bonus_bps)?
+    } else {
+        rebate_amount
+    };
+    let mut amount = apply_diminishing_returns(amount, trader.lifetime_rebates, governance)?;
+    let low_activity_boost_applied = low_activity(global_state, now);
+    if low_activity_boost_applied {
+        amount = apply_bps_bonus(amount, global_state.low_activity_boost_bps)?;
+    }
+    Ok(ClaimedRebate {
+        amount: amount.checked_add(reimbursement).ok_or(ErrorCode::RebateOverflow)?,
+        rebate_rate,
+        multiplier,
+        veteran_bonus_applied,
+        low_activity_boost_applied,
+    })
+}
+
+/// Returns the HFRT reimbursed at the next claim for the trader's reported priority-fee spend: the
+/// `priority_rebate_bps` share of the lamports, converted at `priority_hfrt_per_sol` HFRT base units per SOL.
+fn priority_reimbursement(trader: &Trader, governance: &Governance) -> Result<u64> {
+    let (_, reimbursed_lamports) = split_by_bps(trader.pending_priority_spend, governance.priority_rebate_bps)?;
+    let reimbursement =
+        reimbursed_lamports as u128 * governance.priority_hfrt_per_sol as u128 / LAMPORTS_PER_SOL as u128;
+    Ok(u64::try_from(reimbursement).map_err(|_| ErrorCode::RebateOverflow)?)
+}
+
+/// Scales a rebate down once the trader's lifetime claimed rebates pass `governance.diminishing_threshold`:
+/// the payout shrinks by `threshold / lifetime_rebates`, but never below `diminishing_floor_bps` of it.
+/// A zero threshold disables the curve.
+fn apply_diminishing_returns(amount: u64, lifetime_rebates: u64, governance: &Governance) -> Result<u64> {
+    let threshold = governance.diminishing_threshold;
+    if threshold == 0 || lifetime_rebates <= threshold {
+        return Ok(amount);
+    }
+    let factor_bps =
+        (threshold as u128 * 10_000 / lifetime_rebates as u128).max(governance.diminishing_floor_bps as u128);
+    let scaled = (amount as u128)
+        .checked_mul(factor_bps)
+        .ok_or(ErrorCode::RebateOverflow)?
+        / 10_000;
+    Ok(u64::try_from(scaled).map_err(|_| ErrorCode::RebateOverflow)?)
+}
+
+/// Returns `amount` increased by `bonus_bps` basis points.
+fn apply_bps_bonus(amount: u64, bonus_bps: u16) -> Result<u64> {
+    let bonus = (amount as u128)
+        .checked_mul(bonus_bps as u128)
+        .ok_or(ErrorCode::RebateOverflow)?
+        / 10_000;
+    let bonus = u64::try_from(bonus).map_err(|_| ErrorCode::RebateOverflow)?;
+    Ok(amount.checked_add(bonus).ok_or(ErrorCode::RebateOverflow)?)
+}
+
+/// Splits `amount` into `(rest, portion)` where `portion` is `portion_bps` basis points of it, rounded down.
+fn split_by_bps(amount: u64, portion_bps: u16) -> Result<(u64, u64)> {
+    let portion = (amount as u128)
+        .checked_mul(portion_bps as u128)
+        .ok_or(ErrorCode::RebateOverflow)?
+        / 10_000;
+    let portion = u64::try_from(portion).map_err(|_| ErrorCode::RebateOverflow)?;
+    Ok((amount - portion, portion))
+}
+
+/// Accrues `governance.referral_bps` of a referred trader's `rebate_amount` to the referrer's `ReferrerState`,
+/// which must be passed whenever the trader has a referrer and referrals are enabled.
+fn accrue_referral(
+    trader: &Trader,
+    governance: &Governance,
+    referrer_state: Option<&mut ReferrerState>,
+    rebate_amount: u64,
+) -> Result<()> {
+    if trader.referrer == Pubkey::default() || governance.referral_bps == 0 {
+        return Ok(());
+    }
+    let referrer_state = referrer_state.ok_or(ErrorCode::ReferrerStateMissing)?;
+    let (_, referral_amount) = split_by_bps(rebate_amount, governance.referral_bps)?;
+    referrer_state.pending_referral = referrer_state
+        .pending_referral
+        .checked_add(referral_amount)
+        .ok_or(ErrorCode::RebateOverflow)?;
+    emit!(ReferralAccrued {
+        referrer: trader.referrer,
+        trader: trader.owner,
+        referral_amount,
+        pending_referral: referrer_state.pending_referral,
+    });
+    Ok(())
+}
+
+/// Rescales a token amount between mints with different decimals, rounding down.
+fn scale_to_decimals(amount: u64, from_decimals: u8, to_decimals: u8) -> Result<u64> {
+    if to_decimals >= from_decimals {
+        let factor = 10u64
+            .checked_pow((to_decimals - from_decimals) as u32)
+            .ok_or(ErrorCode::RebateOverflow)?;
+        Ok(amount.checked_mul(factor).ok_or(ErrorCode::RebateOverflow)?)
+    } else {
+        let factor = 10u64
+            .checked_pow((from_decimals - to_decimals) as u32)
+            .ok_or(ErrorCode::RebateOverflow)?;
+        Ok(amount / factor)
+    }
+}
+
+/// Maps a `claim_block_reason` code to the error returned by `claim_rebate`.
+fn claim_block_error(reason_code: u8) -> ErrorCode {
+    match reason_code {
+        CLAIM_BLOCKED_PAUSED => ErrorCode::ProgramPaused,
+        CLAIM_BLOCKED_REBATES_PAUSED => ErrorCode::RebatesPaused,
+        CLAIM_BLOCKED_ADDRESS => ErrorCode::AddressBlocked,
+        CLAIM_BLOCKED_NO_VOLUME => ErrorCode::NoVolume,
+        _ => ErrorCode::RebateTooSmall,
+    }
+}
+
+/// Returns true if `reporter` may record trades for `trader`: its owner or the configured trusted reporter.
+fn is_authorized_reporter(trader: &Trader, global_state: &GlobalState, reporter: &Pubkey) -> bool {
+    *reporter == trader.owner
+        || (global_state.trusted_reporter != Pubkey::default() && *reporter == global_state.trusted_reporter)
+}
+
+/// Adds a trade to a 24-hour rolling volume, restarting the window when it has lapsed.
+fn accumulate_volume(rolling_volume: u64, elapsed: i64, trade_amount: u64) -> Result<u64> {
+    if elapsed >= 24 * 3600 {
+        Ok(trade_amount)
+    } else {
+        Ok(rolling_volume.checked_add(trade_amount).ok_or(ErrorCode::VolumeOverflow)?)
+    }
+}
+
+/// Attributes `trade_amount` of newly recorded trader volume to its venue bucket, if any. The buckets
+/// expire with the rolling volume they are part of.
+fn credit_venue_volume(trader: &mut Trader, venue_type: Option<u8>, elapsed: i64, trade_amount: u64) {
+    if elapsed >= 24 * 3600 {
+        trader.spot_volume = 0;
+        trader.perp_volume = 0;
+    }
+    match venue_type {
+        Some(VENUE_SPOT) => trader.spot_volume = trader.spot_volume.saturating_add(trade_amount),
+        Some(VENUE_PERP) => trader.perp_volume = trader.perp_volume.saturating_add(trade_amount),
+        _ => {}
+    }
+    clamp_venue_volumes(trader);
+}
+
+/// Keeps the spot and perp buckets within the trader's rolling volume after it is clamped or consumed.
+fn clamp_venue_volumes(trader: &mut Trader) {
+    trader.spot_volume = trader.spot_volume.min(trader.rolling_volume);
+    trader.perp_volume = trader.perp_volume.min(trader.rolling_volume - trader.spot_volume);
+}
+
+/// Clamps a rolling volume to `max_rolling_volume`, returning the volume and whether it was clamped.
+/// A zero maximum disables the cap.
+fn clamp_volume(rolling_volume: u64, max_rolling_volume: u64) -> (u64, bool) {
+    if max_rolling_volume > 0 && rolling_volume > max_rolling_volume {
+        (max_rolling_volume, true)
+    } else {
+        (rolling_volume, false)
+    }
+}
+
+/// Returns the seconds elapsed from `then` to `now`, never negative.
+/// A negative delta (clock skew) yields zero, or `ErrorCode::ClockSkew` when `reject_negative` is set.
+fn safe_elapsed(then: i64, now: i64, reject_negative: bool) -> Result<i64> {
+    let elapsed = now.checked_sub(then).ok_or(ErrorCode::Overflow)?;
+    if elapsed < 0 {
+        require!(!reject_negative, ErrorCode::ClockSkew);
+        return Ok(0);
+    }
+    Ok(elapsed)
+}
+
+/// Returns true if trades occur too frequently (less than `MIN_TRADE_INTERVAL` seconds apart).
+fn detect_frequent_trades(elapsed: i64) -> bool {
+    elapsed < MIN_TRADE_INTERVAL
+}
+
+/// Returns true if a trade is considered a wash trade. With `inclusive_threshold`, a trade of exactly
+/// `WASH_TRADE_THRESHOLD` also counts, so trades cannot be sized at the threshold to slip through.
+fn is_wash_trade(elapsed: i64, trade_amount: u64, inclusive_threshold: bool) -> bool {
+    let over_threshold = if inclusive_threshold {
+        trade_amount >= WASH_TRADE_THRESHOLD
+    } else {
+        trade_amount > WASH_TRADE_THRESHOLD
+    };
+    over_threshold && elapsed < WASH_TRADE_INTERVAL
+}
+
+/// Returns true if this fill matches a similar-sized fill on the opposite side within `SELF_MATCH_WINDOW`.
+fn is_self_match(trader: &Trader, is_maker: bool, current_time: i64, trade_amount: u64) -> bool {
+    let (opposite_time, opposite_amount) = if is_maker {
+        (trader.last_taker_time, trader.last_taker_amount)
+    } else {
+        (trader.last_maker_time, trader.last_maker_amount)
+    };
+    if opposite_time == 0 || current_time.saturating_sub(opposite_time) >= SELF_MATCH_WINDOW {
+        return false;
+    }
+    let larger = trade_amount.max(opposite_amount) as u128;
+    let difference = trade_amount.abs_diff(opposite_amount) as u128;
+    difference * 10_000 <= larger * SELF_MATCH_TOLERANCE_BPS as u128
+}
+
+/// Records the latest maker or taker fill for self-match detection. No-op when the side is unknown.
+fn record_trade_side(trader: &mut Trader, is_maker: Option<bool>, current_time: i64, trade_amount: u64) {
+    match is_maker {
+        Some(true) => {
+            trader.last_maker_time = current_time;
+            trader.last_maker_amount = trade_amount;
+        }
+        Some(false) => {
+            trader.last_taker_time = current_time;
+            trader.last_taker_amount = trade_amount;
+        }
+        None => {}
+    }
+}
+
+/// Emits `TradeThrottled` with the time the trader may retry and returns `error` to abort with.
+/// The event survives in the failed transaction's logs, giving clients a precise backoff target.
+fn throttle_trade(trader: &Trader, next_eligible_trade_time: i64, error: ErrorCode) -> Error {
+    emit!(TradeThrottled {
+        owner: trader.owner,
+        next_eligible_trade_time,
+    });
+    error.into()
+}
+
+/// Counts a soft-handled wash trade against the trader.
+fn flag_wash_trade(trader: &mut Trader, current_time: i64) -> Result<()> {
+    trader.flagged_trade_count = trader.flagged_trade_count.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    trader.last_wash_flag_at = current_time;
+    Ok(())
+}
+
+/// Forgives one wash flag per full `governance.wash_decay_window` since `trader.last_wash_flag_at`, carrying
+/// the partial window forward.
+fn decay_wash_flags(trader: &mut Trader, governance: &Governance, now: i64) {
+    let window = governance.wash_decay_window;
+    if window == 0 || trader.flagged_trade_count == 0 {
+        return;
+    }
+    let windows = now.saturating_sub(trader.last_wash_flag_at).max(0) / window;
+    if windows > 0 {
+        trader.flagged_trade_count = trader.flagged_trade_count.saturating_sub(windows as u64);
+        trader.last_wash_flag_at = trader.last_wash_flag_at.saturating_add(windows.saturating_mul(window));
+    }
+}
+
+/// Runs the wash and frequency checks for one fill and credits it to the aggregate rolling volume, or to
+/// `market_volume` when `market` is set, emitting `TradeRecorded`. Returns false when soft wash handling
+/// flagged the fill instead of crediting it.
+#[allow(clippy::too_many_arguments)]
+fn apply_trade(
+    trader: &mut Trader,
+    gov: &Governance,
+    global_state: &mut GlobalState,
+    market_volume: Option<&mut MarketVolume>,
+    trade_amount: u64,
+    market: Option<Pubkey>,
+    is_maker: Option<bool>,
+    venue_type: Option<u8>,
+    current_time: i64,
+) -> Result<bool> {
+    decay_wash_flags(trader, gov, current_time);
+    let elapsed = safe_elapsed(trader.last_update, current_time, gov.reject_negative_elapsed)?;
+
+    // Check for potential wash trading, both against the last trade and across the recent-trade window.
+    // In soft mode the trade is recorded and flagged but earns no rebate volume.
+    let single_wash = match is_maker {
+        Some(is_maker) => is_self_match(trader, is_maker, current_time, trade_amount),
+        None => is_wash_trade(elapsed, trade_amount, gov.wash_threshold_inclusive),
+    };
+    if single_wash
+        || is_window_wash_trade(trader, current_time, trade_amount, gov.wash_window_secs, gov.wash_window_volume)
+    {
+        if !gov.soft_wash_handling {
+            let next_eligible_trade_time = if single_wash {
+                trader.last_update.saturating_add(WASH_TRADE_INTERVAL)
+            } else {
+                current_time.saturating_add(gov.wash_window_secs)
+            };
+            return Err(throttle_trade(trader, next_eligible_trade_time, ErrorCode::WashTrade));
+        }
+        flag_wash_trade(trader, current_time)?;
+        trader.last_update = current_time;
+        push_recent_trade(trader, current_time, trade_amount);
+        record_trade_side(trader, is_maker, current_time, trade_amount);
+        emit!(WashTradeFlagged {
+            owner: trader.owner,
+            trade_amount,
+            flagged_trade_count: trader.flagged_trade_count,
+        });
+        return Ok(false);
+    }
+    // Check if trades occur too frequently, allowing a short burst before blocking.
+    if detect_frequent_trades(elapsed) {
+        if trader.fast_trade_count >= gov.frequent_trade_allowance {
+            let next_eligible_trade_time = trader.last_update.saturating_add(MIN_TRADE_INTERVAL);
+            return Err(throttle_trade(trader, next_eligible_trade_time, ErrorCode::FrequentTrades));
+        }
+        trader.fast_trade_count += 1;
+    } else {
+        trader.fast_trade_count = 0;
+    }
+
+    let (rolling_volume, volume_clamped) = match (market, market_volume) {
+        (None, _) => {
+            let volume = accumulate_volume(trader.rolling_volume, elapsed, trade_amount)?;
+            let (volume, clamped) = clamp_volume(volume, gov.max_rolling_volume);
+            if volume > trader.rolling_volume {
+                trader.last_volume_increase = current_time;
+            }
+            trader.rolling_volume = volume;
+            credit_venue_volume(trader, venue_type, elapsed, trade_amount);
+            (volume, clamped)
+        }
+        (Some(market), Some(market_volume)) => {
+            require!(
+                market_volume.owner == trader.owner && market_volume.market == market,
+                ErrorCode::MarketMismatch
+            );
+            activate_market(trader, gov, market_volume)?;
+            let market_elapsed = safe_elapsed(market_volume.last_update, current_time, gov.reject_negative_elapsed)?;
+            let volume = accumulate_volume(market_volume.rolling_volume, market_elapsed, trade_amount)?;
+            let (volume, clamped) = clamp_volume(volume, gov.max_rolling_volume);
+            if volume > market_volume.rolling_volume {
+                trader.last_volume_increase = current_time;
+            }
+            market_volume.rolling_volume = volume;
+            market_volume.last_update = current_time;
+            (volume, clamped)
+        }
+        (Some(_), None) => return Err(ErrorCode::MarketMismatch.into()),
+    };
+    track_volume_recorded(global_state, trade_amount, current_time);
+    accrue_loyalty_points(trader, gov, trade_amount);
+    trader.last_update = current_time;
+    trader.next_eligible_trade_time = if trader.fast_trade_count >= gov.frequent_trade_allowance {
+        current_time.saturating_add(MIN_TRADE_INTERVAL)
+    } else {
+        current_time
+    };
+    push_recent_trade(trader, current_time, trade_amount);
+    record_trade_side(trader, is_maker, current_time, trade_amount);
+
+    let current_multiplier = calculate_rebate_multiplier(
+        rolling_volume,
+        &global_state.tier_thresholds,
+        gov.inclusive_tier_boundaries,
+    );
+    let new_max_tier = if current_multiplier > trader.max_tier_reached {
+        trader.max_tier_reached = current_multiplier;
+        Some(current_multiplier)
+    } else {
+        None
+    };
+    emit!(TradeRecorded {
+        owner: trader.owner,
+        trade_amount,
+        rolling_volume,
+        current_multiplier,
+        new_max_tier,
+        volume_clamped,
+    });
+    Ok(true)
+}
+
+/// Appends `trader_key` to the registry at the next index, which must fall on `page`.
+fn register_trader_in_page(
+    registry: &mut TraderRegistry,
+    page: &mut TraderRegistryPage,
+    trader: &mut Trader,
+    trader_key: Pubkey,
+) -> Result<()> {
+    require!(!trader.registered, ErrorCode::TraderAlreadyRegistered);
+    let index = registry.trader_count;
+    let per_page = TRADERS_PER_REGISTRY_PAGE as u64;
+    require!(page.page as u64 == index / per_page, ErrorCode::WrongRegistryPage);
+    page.traders[(index % per_page) as usize] = trader_key;
+    registry.trader_count = index.checked_add(1).ok_or(ErrorCode::Overflow)?;
+    trader.registered = true;
+    emit!(TraderRegistered {
+        trader: trader_key,
+        index,
+    });
+    Ok(())
+}
+
+/// Returns the unstake penalty tier for a staking duration and its penalty percentage.
+/// Tier 0: under 7 days (10%), tier 1: under 14 days (5%), tier 2: otherwise (2%).
+fn unstake_penalty_tier(duration: i64) -> (u8, u64) {
+    if duration < 7 * 24 * 3600 {
+        (0, 10)
+    } else if duration < 14 * 24 * 3600 {
+        (1, 5)
+    } else {
+        (2, 2)
+    }
+}
+
+/// Calculates a dynamic unstake penalty based on staking duration (see `stake_duration`).
+/// Penalty: 10% if staked less than 7 days, 5% if less than 14 days, 2% otherwise.
+fn calculate_dynamic_unstake_penalty(duration: i64, amount: u64) -> u64 {
+    let (_, penalty_percentage) = unstake_penalty_tier(duration);
+    // Widened so large stakes cannot overflow; the result never exceeds `amount`.
+    (amount as u128 * penalty_percentage as u128 / 100) as u64
+}
+
+/// Returns how long a stake started at `stake_start_time` has been held at `now`, for penalty tiers.
+/// A start in the future (clock skew) fails with `ClockSkew` under `governance.reject_negative_elapsed`;
+/// otherwise it counts as fully matured (lowest penalty) with `governance.skewed_stake_min_penalty`, or as
+/// brand new (highest penalty) without it.
+fn stake_duration(stake_start_time: i64, now: i64, governance: &Governance) -> Result<i64> {
+    let elapsed = now.checked_sub(stake_start_time).ok_or(ErrorCode::Overflow)?;
+    if elapsed >= 0 {
+        return Ok(elapsed);
+    }
+    require!(!governance.reject_negative_elapsed, ErrorCode::ClockSkew);
+    Ok(if governance.skewed_stake_min_penalty { i64::MAX } else { 0 })
+}
+
+/// Returns the unstake penalty for `amount`, drawn from vested compounded rewards first. The compounded part
+/// is penalized on its own `compounded_since` clock, so compounding cannot ride on a matured principal's lock.
+fn blended_unstake_penalty(trader: &Trader, governance: &Governance, now: i64, amount: u64) -> Result<u64> {
+    let (compounded_part, principal_part) = split_unstake(trader, governance, now, amount)?;
+    let principal_duration = stake_duration(trader.stake_start_time, now, governance)?;
+    let compounded_duration = stake_duration(trader.compounded_since, now, governance)?;
+    calculate_dynamic_unstake_penalty(principal_duration, principal_part)
+        .checked_add(calculate_dynamic_unstake_penalty(compounded_duration, compounded_part))
+        .ok_or(ErrorCode::PenaltyOverflow.into())
+}
+
+/// Splits an unstake of `amount` into `(compounded, principal)` parts, taking vested compounded rewards first.
+/// Principal is always available, but unvested compounded rewards cannot be unstaked.
+fn split_unstake(trader: &Trader, governance: &Governance, now: i64, amount: u64) -> Result<(u64, u64)> {
+    let compounded_part = amount.min(withdrawable_compounded(trader, governance, now)?);
+    let principal_part = amount - compounded_part;
+    let principal = trader.staked_amount.saturating_sub(trader.compounded_amount);
+    require!(principal_part <= principal, ErrorCode::CompoundedRewardsVesting);
+    Ok((compounded_part, principal_part))
+}
+
+/// Returns how much of a trader's compounded stake has vested and not yet been unstaked. Compounded rewards
+/// vest on their `compounded_since` clock: nothing before `governance.compound_cliff`, then linearly over
+/// `compound_vest_duration`. With both unset, all compounded rewards are withdrawable.
+fn withdrawable_compounded(trader: &Trader, governance: &Governance, now: i64) -> Result<u64> {
+    let (cliff, vest_duration) = (governance.compound_cliff, governance.compound_vest_duration);
+    if cliff == 0 && vest_duration == 0 {
+        return Ok(trader.compounded_amount);
+    }
+    let age = safe_elapsed(trader.compounded_since, now, governance.reject_negative_elapsed)?;
+    if age < cliff {
+        return Ok(0);
+    }
+    let pool = trader.compounded_amount.saturating_add(trader.compound_withdrawn);
+    let vested = if vest_duration == 0 || age - cliff >= vest_duration {
+        pool
+    } else {
+        (pool as u128 * (age - cliff) as u128 / vest_duration as u128) as u64
+    };
+    Ok(vested.saturating_sub(trader.compound_withdrawn).min(trader.compounded_amount))
+}
+
+/// Removes `amount` from a trader's stake after the unstake checks and returns the amount after penalty.
+/// Shared by the instant and the minimum-notice unstake paths. A partial unstake keeps the remaining
+/// stake's clock unless `governance.partial_unstake_resets_clock` restarts it.
+fn debit_unstake(trader: &mut Trader, governance: &Governance, global_state: &mut GlobalState, amount: u64) -> Result<u64> {
+    require!(!trader.blocked, ErrorCode::AddressBlocked);
+    require!(trader.staked_amount >= amount, ErrorCode::InsufficientStake);
+    let clock = Clock::get()?;
+    let (compounded_part, _) = split_unstake(trader, governance, clock.unix_timestamp, amount)?;
+    let penalty = blended_unstake_penalty(trader, governance, clock.unix_timestamp, amount)?;
+    let free_amount = take_free_unstake(trader, governance, clock.unix_timestamp, amount)?;
+    let penalty = penalty_on_excess(penalty, amount, free_amount);
+    let amount_after_penalty = amount.checked_sub(penalty).ok_or(ErrorCode::PenaltyOverflow)?;
+    let staked_before = trader.staked_amount;
+    trader.staked_amount = trader
+        .staked_amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::StakeOverflow)?;
+    // Vested compounded rewards are unstaked first.
+    trader.compounded_amount = trader.compounded_amount.saturating_sub(compounded_part);
+    trader.compound_withdrawn = trader.compound_withdrawn.saturating_add(compounded_part);
+    if trader.compounded_amount == 0 {
+        trader.compounded_since = 0;
+        trader.compound_withdrawn = 0;
+    }
+    if trader.staked_amount == 0 {
+        trader.stake_start_time = 0;
+        trader.is_staking = false;
+    } else if governance.partial_unstake_resets_clock {
+        trader.stake_start_time = clock.unix_timestamp;
+        if trader.compounded_amount > 0 {
+            trader.compounded_since = clock.unix_timestamp;
+            trader.compound_withdrawn = 0;
+        }
+    }
+    track_stake_totals(global_state, staked_before, trader.staked_amount)?;
+    Ok(amount_after_penalty)
+}
+
+/// Returns whether the trader's HFRT stake is above `governance.large_unstake_threshold`, so it can only be
+/// unstaked after notice. Gating on the position rather than each call stops a large staker from unstaking
+/// instantly in threshold-sized pieces.
+fn unstake_notice_required(trader: &Trader, governance: &Governance) -> bool {
+    governance.large_unstake_threshold != 0 && trader.staked_amount > governance.large_unstake_threshold
+}
+
+/// Returns how much of `governance.free_unstake_allowance` the trader has left at `now`; the whole
+/// allowance once `free_unstake_period` has passed since their window opened.
+fn free_unstake_remaining(trader: &Trader, governance: &Governance, now: i64) -> Result<u64> {
+    if governance.free_unstake_allowance == 0 {
+        return Ok(0);
+    }
+    let window_age = safe_elapsed(trader.free_unstake_window_start, now, governance.reject_negative_elapsed)?;
+    if window_age >= governance.free_unstake_period {
+        return Ok(governance.free_unstake_allowance);
+    }
+    Ok(governance.free_unstake_allowance.saturating_sub(trader.free_unstake_used))
+}
+
+/// Consumes up to `amount` of the trader's free unstake allowance, opening a new window if the last one
+/// has lapsed, and returns the portion that is penalty-free.
+fn take_free_unstake(trader: &mut Trader, governance: &Governance, now: i64, amount: u64) -> Result<u64> {
+    let free_amount = amount.min(free_unstake_remaining(trader, governance, now)?);
+    if free_amount == 0 {
+        return Ok(0);
+    }
+    let window_age = safe_elapsed(trader.free_unstake_window_start, now, governance.reject_negative_elapsed)?;
+    if window_age >= governance.free_unstake_period {
+        trader.free_unstake_window_start = now;
+        trader.free_unstake_used = 0;
+    }
+    trader.free_unstake_used = trader.free_unstake_used.saturating_add(free_amount);
+    Ok(free_amount)
+}
+
+/// Scales a penalty computed on all of `amount` down to the part not covered by `free_amount`.
+fn penalty_on_excess(penalty: u64, amount: u64, free_amount: u64) -> u64 {
+    if amount == 0 {
+        return 0;
+    }
+    (penalty as u128 * (amount - free_amount) as u128 / amount as u128) as u64
+}
+
+/// Invariant check before an unstake transfer: the payout can exceed neither the amount unstaked (a negative
+/// penalty) nor what the vault actually holds.
+fn check_unstake_payout(staking_vault: &AccountInfo, amount: u64, amount_after_penalty: u64) -> Result<()> {
+    let vault_balance = TokenAccount::try_deserialize(&mut &staking_vault.try_borrow_data()?[..])?.amount;
+    require!(
+        unstake_payout_valid(amount, amount_after_penalty, vault_balance),
+        ErrorCode::InvalidPenaltyComputation
+    );
+    Ok(())
+}
+
+/// The comparison behind `check_unstake_payout`.
+fn unstake_payout_valid(amount: u64, amount_after_penalty: u64, vault_balance: u64) -> bool {
+    amount_after_penalty <= amount && amount_after_penalty <= vault_balance
+}
+
+/// Removes `amount` from a non-HFRT stake position and returns the amount after the usual unstake penalty.
+fn debit_stake_position(position: &mut StakePosition, governance: &Governance, amount: u64) -> Result<u64> {
+    require!(position.amount >= amount, ErrorCode::InsufficientStake);
+    let clock = Clock::get()?;
+    let staked_duration = stake_duration(position.stake_start_time, clock.unix_timestamp, governance)?;
+    let penalty = calculate_dynamic_unstake_penalty(staked_duration, amount);
+    let amount_after_penalty = amount.checked_sub(penalty).ok_or(ErrorCode::PenaltyOverflow)?;
+    position.amount = position.amount.checked_sub(amount).ok_or(ErrorCode::StakeOverflow)?;
+    if position.amount == 0 {
+        position.stake_start_time = 0;
+    } else if governance.partial_unstake_resets_clock {
+        position.stake_start_time = clock.unix_timestamp;
+    }
+    Ok(amount_after_penalty)
+}
+
+/// Computes a trader's compoundable rebate at the active rate, boosted by their voting streak, and consumes
+/// their rolling volume.
+fn take_compound_rebate(trader: &mut Trader, governance: &Governance, global_state: &GlobalState) -> Result<u64> {
+    require!(!global_state.paused, ErrorCode::ProgramPaused);
+    require!(!global_state.rebate_paused, ErrorCode::RebatesPaused);
+    require!(!trader.blocked, ErrorCode::AddressBlocked);
+    let rebate_amount = trader_rebate(trader, governance, global_state, Clock::get()?.unix_timestamp)?;
+    let rebate_amount = apply_bps_bonus(rebate_amount, streak_boost_bps(trader, governance))?;
+    mark_rebate_slot(trader)?;
+    trader.rolling_volume = 0;
+    clamp_venue_volumes(trader);
+    Ok(rebate_amount)
+}
+
+/// Adds compounded rewards to a trader's stake, starting the stake clock if they were not already staking.
+/// Compounded rewards also age on their own amount-weighted `compounded_since` clock.
+fn credit_compounded_stake(trader: &mut Trader, global_state: &mut GlobalState, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let staked_before = trader.staked_amount;
+    trader.staked_amount = trader.staked_amount.checked_add(amount).ok_or(ErrorCode::StakeOverflow)?;
+    track_stake_totals(global_state, staked_before, trader.staked_amount)?;
+    trader.compounded_since = weighted_stake_start_time(
+        trader.compounded_amount,
+        trader.compounded_since,
+        amount,
+        clock.unix_timestamp,
+    )?;
+    trader.compounded_amount = trader.compounded_amount.checked_add(amount).ok_or(ErrorCode::StakeOverflow)?;
+    if !trader.is_staking {
+        trader.stake_start_time = clock.unix_timestamp;
+        trader.is_staking = true;
+    }
+    Ok(())
+}
+
+/// Credits a direct HFRT stake of `amount`, blending it into the trader's stake start time.
+fn credit_stake(trader: &mut Trader, global_state: &mut GlobalState, amount: u64) -> Result<()> {
+    let clock = Clock::get()?;
+    let staked_before = trader.staked_amount;
+    trader.stake_start_time = weighted_stake_start_time(
+        trader.staked_amount,
+        trader.stake_start_time,
+        amount,
+        clock.unix_timestamp,
+    )?;
+    trader.staked_amount = trader.staked_amount.checked_add(amount).ok_or(ErrorCode::StakeOverflow)?;
+    trader.is_staking = trader.staked_amount > 0;
+    track_stake_totals(global_state, staked_before, trader.staked_amount)
+}
+
+/// Keeps `global_state.total_staked` in step with a trader's HFRT stake, and `total_stakers` in step with
+/// that stake moving between zero and nonzero. Growth past `global_state.max_total_staked` is rejected.
+fn track_stake_totals(global_state: &mut GlobalState, staked_before: u64, staked_after: u64) -> Result<()> {
+    if staked_after > staked_before {
+        require!(
+            !exceeds_global_stake_cap(global_state, staked_after - staked_before),
+            ErrorCode::GlobalStakeCapExceeded
+        );
+    }
+    global_state.total_staked = global_state
+        .total_staked
+        .checked_sub(staked_before)
+        .and_then(|total| total.checked_add(staked_after))
+        .ok_or(ErrorCode::StakeOverflow)?;
+    if staked_before == 0 && staked_after > 0 {
+        global_state.total_stakers = global_state.total_stakers.checked_add(1).ok_or(ErrorCode::StakeOverflow)?;
+    } else if staked_before > 0 && staked_after == 0 {
+        global_state.total_stakers = global_state.total_stakers.checked_sub(1).ok_or(ErrorCode::StakeOverflow)?;
+    }
+    Ok(())
+}
+
+/// Returns true if staking `amount` more HFRT would push `total_staked` past `global_state.max_total_staked`.
+/// A zero cap disables the check.
+fn exceeds_global_stake_cap(global_state: &GlobalState, amount: u64) -> bool {
+    let cap = global_state.max_total_staked;
+    cap > 0 && global_state.total_staked.saturating_add(amount) > cap
+}
+
+/// Accrues loyalty points on credited trade volume. Points saturate rather than block a trade, and unlike
+/// rolling volume they never expire.
+fn accrue_loyalty_points(trader: &mut Trader, governance: &Governance, amount: u64) {
+    let points = amount as u128 * governance.points_per_volume as u128 / LOYALTY_VOLUME_UNIT as u128;
+    trader.loyalty_points = trader.loyalty_points.saturating_add(u64::try_from(points).unwrap_or(u64::MAX));
+}
+
+/// Counts a market against `governance.max_markets_per_trader` the first time it records a trade. A
+/// `MarketVolume` that has never been updated is new to the trader.
+fn activate_market(trader: &mut Trader, governance: &Governance, market_volume: &MarketVolume) -> Result<()> {
+    if market_volume.last_update != 0 {
+        return Ok(());
+    }
+    let cap = governance.max_markets_per_trader;
+    require!(cap == 0 || trader.active_market_count < cap, ErrorCode::MarketCapReached);
+    trader.active_market_count = trader.active_market_count.saturating_add(1);
+    Ok(())
+}
+
+/// Adds credited trade volume to the protocol-wide total and the current activity window, rolling the
+/// window once `global_state.activity_window` has passed. Saturates so a statistic never blocks a trade.
+fn track_volume_recorded(global_state: &mut GlobalState, amount: u64, now: i64) {
+    global_state.total_volume = global_state.total_volume.saturating_add(amount);
+    if global_state.activity_window == 0 {
+        return;
+    }
+    if now.saturating_sub(global_state.activity_window_start) >= global_state.activity_window {
+        global_state.previous_window_volume = previous_window_volume(global_state, now);
+        global_state.current_window_volume = 0;
+        global_state.activity_window_start = now;
+    }
+    global_state.current_window_volume = global_state.current_window_volume.saturating_add(amount);
+}
+
+/// Returns the protocol-wide volume of the last complete activity window as of `now`: zero once a whole
+/// window has passed with no trades recorded.
+fn previous_window_volume(global_state: &GlobalState, now: i64) -> u64 {
+    let window = global_state.activity_window;
+    let age = now.saturating_sub(global_state.activity_window_start);
+    if age < window {
+        global_state.previous_window_volume
+    } else if age < window.saturating_mul(2) {
+        global_state.current_window_volume
+    } else {
+        0
+    }
+}
+
+/// Whether the market is quiet enough for `global_state.low_activity_boost_bps`: the last complete activity
+/// window saw less than `low_activity_threshold` volume.
+fn low_activity(global_state: &GlobalState, now: i64) -> bool {
+    global_state.activity_window > 0
+        && global_state.low_activity_boost_bps > 0
+        && previous_window_volume(global_state, now) < global_state.low_activity_threshold
+}
+
+/// Adds a minted rebate to the protocol-wide total. Saturates so a statistic never blocks a claim.
+fn track_rebate_minted(global_state: &mut GlobalState, amount: u64) {
+    global_state.total_rebates_minted = global_state.total_rebates_minted.saturating_add(amount);
+}
+
+/// Counts a mint of `amount` against the current inflation epoch, starting a new epoch once
+/// `global_state.epoch_duration` has passed, and rejects it past `max_mint_per_epoch`. A zero cap disables it.
+fn record_epoch_mint(global_state: &mut GlobalState, amount: u64) -> Result<()> {
+    if global_state.max_mint_per_epoch == 0 {
+        return Ok(());
+    }
+    let now = Clock::get()?.unix_timestamp;
+    if now.saturating_sub(global_state.epoch_start) >= global_state.epoch_duration {
+        global_state.epoch_start = now;
+        global_state.minted_this_epoch = 0;
+    }
+    let minted = global_state.minted_this_epoch.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    require!(minted <= global_state.max_mint_per_epoch, ErrorCode::EpochInflationCapExceeded);
+    global_state.minted_this_epoch = minted;
+    Ok(())
+}
+
+/// Rejects a mint of `amount` that would push the HFRT supply past `global_state.max_total_supply`.
+fn check_supply_cap(global_state: &GlobalState, current_supply: u64, amount: u64) -> Result<()> {
+    if global_state.max_total_supply == 0 {
+        return Ok(());
+    }
+    let new_supply = current_supply.checked_add(amount).ok_or(ErrorCode::Overflow)?;
+    require!(new_supply <= global_state.max_total_supply, ErrorCode::SupplyCapExceeded);
+    Ok(())
+}
+
+/// Returns the amount-weighted average of an existing stake's start time and `now` for newly added stake.
+/// With no existing stake this is simply `now`.
+fn weighted_stake_start_time(existing_amount: u64, existing_start: i64, added_amount: u64, now: i64) -> Result<i64> {
+    let total_amount = existing_amount as i128 + added_amount as i128;
+    if existing_amount == 0 || total_amount == 0 {
+        return Ok(now);
+    }
+    let weighted_sum = (existing_start as i128)
+        .checked_mul(existing_amount as i128)
+        .and_then(|v| v.checked_add((now as i128).checked_mul(added_amount as i128)?))
+        .ok_or(ErrorCode::StakeOverflow)?;
+    Ok((weighted_sum / total_amount) as i64)
+}
+
+/// Removes tokens from a trader's stake without any penalty (slashes and admin recovery),
+/// clearing the stake start time if nothing remains.
+fn remove_stake(trader: &mut Trader, global_state: &mut GlobalState, amount: u64) -> Result<()> {
+    require!(trader.staked_amount >= amount, ErrorCode::InsufficientStake);
+    let staked_before = trader.staked_amount;
+    trader.staked_amount = trader
+        .staked_amount
+        .checked_sub(amount)
+        .ok_or(ErrorCode::StakeOverflow)?;
+    trader.compounded_amount = trader.compounded_amount.min(trader.staked_amount);
+    if trader.compounded_amount == 0 {
+        trader.compounded_since = 0;
+        trader.compound_withdrawn = 0;
+    }
+    if trader.staked_amount == 0 {
+        trader.stake_start_time = 0;
+        trader.is_staking = false;
+    }
+    track_stake_totals(global_state, staked_before, trader.staked_amount)?;
+    Ok(())
+}
+
+/// Returns true if this trade plus the buffered trades inside the window exceed the window volume limit.
+/// A zero `window_volume` disables the check.
+fn is_window_wash_trade(trader: &Trader, current_time: i64, trade_amount: u64, window_secs: i64, window_volume: u64) -> bool {
+    if window_volume == 0 {
+        return false;
+    }
+    let window_total = trader
+        .recent_trade_times
+        .iter()
+        .zip(trader.recent_trade_amounts.iter())
+        .filter(|(time, _)| **time != 0 && current_time.saturating_sub(**time) < window_secs)
+        .fold(trade_amount as u128, |total, (_, amount)| total + *amount as u128);
+    window_total > window_volume as u128
+}
+
+/// Records a trade in the trader's fixed-size recent-trade ring buffer, overwriting the oldest entry.
+fn push_recent_trade(trader: &mut Trader, time: i64, amount: u64) {
+    let index = trader.recent_trade_index as usize % RECENT_TRADE_COUNT;
+    trader.recent_trade_times[index] = time;
+    trader.recent_trade_amounts[index] = amount;
+    trader.recent_trade_index = ((index + 1) % RECENT_TRADE_COUNT) as u8;
+}
+
+/// Calculates execution priority based on HFRT balance (lower number means higher priority).
+fn calculate_execution_priority(hfrt_balance: u64) -> u8 {
+    if hfrt_balance >= 1_000_000 {
+        1
+    } else if hfrt_balance >= 500_000 {
+        2
+    } else if hfrt_balance >= 100_000 {
+        3
+    } else {
+        5
+    }
+}
+
+#[derive(Accounts)]
+pub struct Initialize<'info> {
+    /// Global state PDA (seeded by "global-state")
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"global-state"],
+        bump,
+        space = 8 + GlobalState::LEN,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The mint for HFRT tokens.
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA mint authority (seeded by "mint-authority" with the supplied bump), validated in the handler.
+    /// CHECK: Derivation and mint authority are checked against `mint_authority_bump`.
+    pub mint_authority: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EnsureInitialized<'info> {
+    /// Global state PDA (seeded by "global-state"), created only if it does not exist yet.
+    #[account(
+        init_if_needed,
+        payer = authority,
+        seeds = [b"global-state"],
+        bump,
+        space = 8 + GlobalState::LEN,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The mint for HFRT tokens.
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA mint authority (seeded by "mint-authority" with the supplied bump), validated in the handler.
+    /// CHECK: Derivation and mint authority are checked against `mint_authority_bump`.
+    pub mint_authority: UncheckedAccount<'info>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeGovernance<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Governance::LEN,
+    )]
+    pub governance: Account<'info, Governance>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGovernance<'info> {
+    #[account(mut, has_one = authority)]
+    pub governance: Account<'info, Governance>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateGlobalState<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetGovernance<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Governance account to pin; it must be controlled by the protocol authority.
+    #[account(constraint = governance.authority == authority.key() @ ErrorCode::Unauthorized)]
+    pub governance: Account<'info, Governance>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The HFRT mint.
+    #[account(address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// Current HFRT staking vault PDA (seeded by "staking-vault" and the HFRT mint), which owns both vaults.
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, global_state.hfrt_mint.as_ref()],
+        bump,
+    )]
+    pub legacy_vault: Account<'info, TokenAccount>,
+    /// The legacy vault PDA's associated token account, created here as the new vault.
+    #[account(
+        init,
+        payer = authority,
+        associated_token::mint = hfrt_mint,
+        associated_token::authority = legacy_vault,
+    )]
+    pub new_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DrainLegacyVault<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub authority: Signer<'info>,
+    /// The original HFRT vault PDA (seeded by "staking-vault" alone), which owns itself.
+    #[account(
+        mut,
+        seeds = [VAULT_SEED],
+        bump,
+        constraint = legacy_vault.mint == global_state.hfrt_mint @ ErrorCode::WrongMint,
+    )]
+    pub legacy_vault: Account<'info, TokenAccount>,
+    /// Current HFRT staking vault (`global_state.hfrt_vault`), receiving the balance.
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    pub hfrt_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyScheduledDiscount<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct TransferMintAuthority<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+        has_one = hfrt_mint,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// The HFRT mint whose authority is handed over.
+    #[account(mut)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetMaxTotalSupply<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+        has_one = hfrt_mint,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// The HFRT mint, read for its current supply.
+    pub hfrt_mint: Account<'info, Mint>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetPaused<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Either the authority or the emergency council.
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetVolatilityMode<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Either the authority or the volatility oracle.
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTrader<'info> {
+    /// Trader state PDA (seeded by "trader" and the owner).
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"trader", owner.key().as_ref()],
+        bump,
+        space = 8 + Trader::LEN,
+    )]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// Trader registry, when registering the new trader.
+    #[account(
+        mut,
+        seeds = [TRADER_REGISTRY_SEED],
+        bump = trader_registry.bump,
+    )]
+    pub trader_registry: Option<Account<'info, TraderRegistry>>,
+    /// The registry's current page; required with `trader_registry`.
+    #[account(mut)]
+    pub registry_page: Option<Account<'info, TraderRegistryPage>>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeTraderRegistry<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Trader registry PDA (seeded by "trader-registry").
+    #[account(
+        init,
+        payer = authority,
+        seeds = [TRADER_REGISTRY_SEED],
+        bump,
+        space = 8 + TraderRegistry::LEN,
+    )]
+    pub trader_registry: Account<'info, TraderRegistry>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(page: u32)]
+pub struct OpenTraderRegistryPage<'info> {
+    #[account(
+        seeds = [TRADER_REGISTRY_SEED],
+        bump = trader_registry.bump,
+    )]
+    pub trader_registry: Account<'info, TraderRegistry>,
+    /// Registry page PDA (seeded by "trader-registry-page" and the page number).
+    #[account(
+        init,
+        payer = payer,
+        seeds = [TRADER_REGISTRY_PAGE_SEED, &page.to_le_bytes()],
+        bump,
+        space = 8 + TraderRegistryPage::LEN,
+    )]
+    pub registry_page: Account<'info, TraderRegistryPage>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RegisterTrader<'info> {
+    /// Trader state account not yet in the registry.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    #[account(
+        mut,
+        seeds = [TRADER_REGISTRY_SEED],
+        bump = trader_registry.bump,
+    )]
+    pub trader_registry: Account<'info, TraderRegistry>,
+    /// The registry's current page.
+    #[account(mut)]
+    pub registry_page: Account<'info, TraderRegistryPage>,
+}
+
+#[derive(Accounts)]
+#[instruction(market: Pubkey)]
+pub struct InitializeMarketVolume<'info> {
+    /// Market volume PDA (seeded by "market-vol", the owner and the market).
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"market-vol", owner.key().as_ref(), market.as_ref()],
+        bump,
+        space = 8 + MarketVolume::LEN,
+    )]
+    pub market_volume: Account<'info, MarketVolume>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferTraderOwnership<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetAutoReinvest<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    pub owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeReferrerState<'info> {
+    /// Referrer accrual PDA (seeded by "referrer" and the referrer).
+    #[account(
+        init,
+        payer = referrer,
+        seeds = [REFERRER_SEED, referrer.key().as_ref()],
+        bump,
+        space = 8 + ReferrerState::LEN,
+    )]
+    pub referrer_state: Account<'info, ReferrerState>,
+    #[account(mut)]
+    pub referrer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetReferrer<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    pub owner: Signer<'info>,
+    /// The referrer's accrual PDA; naming a referrer requires it to exist.
+    #[account(
+        seeds = [REFERRER_SEED, referrer_state.referrer.as_ref()],
+        bump = referrer_state.bump,
+    )]
+    pub referrer_state: Account<'info, ReferrerState>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawReferral<'info> {
+    /// The referrer's accrual PDA (seeded by "referrer" and the referrer).
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, referrer.key().as_ref()],
+        bump = referrer_state.bump,
+        has_one = referrer,
+    )]
+    pub referrer_state: Account<'info, ReferrerState>,
+    pub referrer: Signer<'info>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// Referrer’s token account for the payout; must be owned by the referrer.
+    #[account(
+        mut,
+        constraint = referrer_token_account.owner == referrer.key() @ ErrorCode::Unauthorized,
+        constraint = referrer_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub referrer_token_account: Account<'info, TokenAccount>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Global state, read for the pause flag and supply cap and updated with the epoch mint total.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptTraderOwnership<'info> {
+    /// Trader state account with a pending ownership transfer.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// The nominated owner accepting the transfer.
+    pub new_owner: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RecordTrade<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// The trader's owner or the trusted reporter.
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    /// Canonical governance account for trade-check configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the pause flag and updated with volume totals and staker metrics on reinvest.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Per-market volume bucket, required when `market` is set.
+    #[account(mut)]
+    pub market_volume: Option<Account<'info, MarketVolume>>,
+    /// The HFRT mint, required when the trader has `auto_reinvest` set.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Option<Account<'info, Mint>>,
+    /// HFRT staking vault (`global_state.hfrt_vault`), required for auto-reinvest.
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: Option<UncheckedAccount<'info>>,
+    /// PDA mint authority (seeded by "mint-authority"), required for auto-reinvest.
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: Option<UncheckedAccount<'info>>,
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+#[derive(Accounts)]
+pub struct RecordTradeBatch<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// The trader's owner or the trusted reporter.
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    /// Canonical governance account for trade-check configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the pause flag and trusted reporter and updated with volume totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct BatchRecordTradesForMarkets<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// The trader's owner or the trusted reporter.
+    #[account(mut)]
+    pub reporter: Signer<'info>,
+    /// Canonical governance account for trade-check configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the pause flag and trusted reporter and updated with volume totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct RecordPrioritySpend<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// The trusted reporter attesting to the spend.
+    pub reporter: Signer<'info>,
+    /// Canonical governance account for the reporting cap.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the pause flag and trusted reporter.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct ForceResetVolume<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Trader state account being corrected.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CorrectStakeStartTime<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Trader state account being corrected.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ExpireStaleVolume<'info> {
+    /// Trader state account whose volume may be expired.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    pub keeper: Signer<'info>,
+    /// Keeper’s HFRT token account for receiving the reward.
+    #[account(
+        mut,
+        constraint = keeper_token_account.owner == keeper.key() @ ErrorCode::Unauthorized,
+        constraint = keeper_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub keeper_token_account: Account<'info, TokenAccount>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account, read for the keeper reward.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the supply cap and updated with the epoch mint total.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetTraderBlocked<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Trader state account being blocked or unblocked.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRebate<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// Trader’s token account for receiving rebates; must be owned by the trader owner.
+    #[account(
+        mut,
+        constraint = trader_token_account.owner == trader.owner @ ErrorCode::Unauthorized,
+        constraint = trader_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(mut, address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and updated with rebate totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    /// The referrer's accrual PDA (seeded by "referrer" and the referrer), required for referred traders.
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, trader.referrer.as_ref()],
+        bump = referrer_state.bump,
+    )]
+    pub referrer_state: Option<Account<'info, ReferrerState>>,
+    /// Accounting receipt for this claim (seeded by "receipt", the owner and `trader.claim_nonce`), created
+    /// when passed.
+    #[account(
+        init,
+        payer = owner,
+        space = 8 + ClaimReceipt::LEN,
+        seeds = [RECEIPT_SEED, trader.owner.as_ref(), &trader.claim_nonce.to_le_bytes()],
+        bump,
+    )]
+    pub claim_receipt: Option<Account<'info, ClaimReceipt>>,
+    /// Required when a `ClaimReceipt` is created.
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRebateSplit<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// Trader’s token account for the wallet portion; must be owned by the trader owner.
+    #[account(
+        mut,
+        constraint = trader_token_account.owner == trader.owner @ ErrorCode::Unauthorized,
+        constraint = trader_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// HFRT staking vault (`global_state.hfrt_vault`) for the staked portion.
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(mut, address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    /// The referrer's accrual PDA (seeded by "referrer" and the referrer), required for referred traders.
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, trader.referrer.as_ref()],
+        bump = referrer_state.bump,
+    )]
+    pub referrer_state: Option<Account<'info, ReferrerState>>,
+}
+
+#[derive(Accounts)]
+pub struct RedeemPoints<'info> {
+    /// Trader state account spending the points.
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    pub owner: Signer<'info>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// Trader’s token account for the redeemed HFRT; must be owned by the trader owner.
+    #[account(
+        mut,
+        constraint = trader_token_account.owner == trader.owner @ ErrorCode::Unauthorized,
+        constraint = trader_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for the redemption rate.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the pause flag, mint authority bump and supply cap and updated with the epoch mint total.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimMarketRebate<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    /// Market volume bucket being claimed.
+    #[account(
+        mut,
+        seeds = [b"market-vol", owner.key().as_ref(), market_volume.market.as_ref()],
+        bump = market_volume.bump,
+        has_one = owner,
+    )]
+    pub market_volume: Account<'info, MarketVolume>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// Trader’s token account for receiving rebates; must be owned by the trader owner.
+    #[account(
+        mut,
+        constraint = trader_token_account.owner == trader.owner @ ErrorCode::Unauthorized,
+        constraint = trader_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and updated with rebate totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    /// The referrer's accrual PDA (seeded by "referrer" and the referrer), required for referred traders.
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, trader.referrer.as_ref()],
+        bump = referrer_state.bump,
+    )]
+    pub referrer_state: Option<Account<'info, ReferrerState>>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimRebateInRewardToken<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The HFRT mint, read for its decimals.
+    #[account(address = global_state.hfrt_mint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// The configured reward mint.
+    #[account(address = global_state.reward_mint @ ErrorCode::WrongMint)]
+    pub reward_mint: Account<'info, Mint>,
+    /// Funded reward vault, owned by the reward vault authority PDA.
+    #[account(
+        mut,
+        constraint = reward_vault.mint == reward_mint.key() @ ErrorCode::WrongMint,
+        constraint = reward_vault.owner == reward_vault_authority.key() @ ErrorCode::Unauthorized,
+    )]
+    pub reward_vault: Account<'info, TokenAccount>,
+    /// PDA authority over the reward vault (seeded by "reward-vault-authority").
+    #[account(
+        seeds = [REWARD_VAULT_AUTHORITY_SEED],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub reward_vault_authority: UncheckedAccount<'info>,
+    /// Trader’s reward-token account for receiving the payout.
+    #[account(
+        mut,
+        constraint = trader_reward_account.mint == reward_mint.key() @ ErrorCode::WrongMint,
+        constraint = trader_reward_account.owner == trader.owner @ ErrorCode::Unauthorized,
+    )]
+    pub trader_reward_account: Account<'info, TokenAccount>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and reward mint.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    /// The referrer's accrual PDA (seeded by "referrer" and the referrer), required for referred traders.
+    #[account(
+        mut,
+        seeds = [REFERRER_SEED, trader.referrer.as_ref()],
+        bump = referrer_state.bump,
+    )]
+    pub referrer_state: Option<Account<'info, ReferrerState>>,
+}
+
+#[derive(Accounts)]
+pub struct CanClaim<'info> {
+    /// Trader state account being checked.
+    pub trader: Account<'info, Trader>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakePosition<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The non-HFRT mint this position tracks.
+    #[account(constraint = stake_mint.key() != global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub stake_mint: Account<'info, Mint>,
+    /// Stake position PDA (seeded by "stake-position", the trader account and the mint).
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"stake-position", trader.key().as_ref(), stake_mint.key().as_ref()],
+        bump,
+        space = 8 + StakePosition::LEN,
+    )]
+    pub stake_position: Account<'info, StakePosition>,
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeStakeVault<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    /// The mint the vault holds.
+    pub stake_mint: Account<'info, Mint>,
+    /// PDA that owns the staking vault for this mint (seeded by "staking-vault" and the mint).
+    #[account(
+        seeds = [VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// Staking vault for this mint, created at the vault authority PDA with that PDA as its owner.
+    #[account(
+        init,
+        payer = authority,
+        seeds = [VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+        token::mint = stake_mint,
+        token::authority = vault_authority,
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct StakeTokens<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The mint being staked; HFRT or any other mint with an initialized `StakePosition`.
+    pub stake_mint: Account<'info, Mint>,
+    /// Trader’s token account for `stake_mint`.
+    #[account(
+        mut,
+        constraint = trader_token_account.mint == stake_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// PDA that owns the staking vault for this mint (seeded by "staking-vault" and the mint).
+    #[account(
+        seeds = [VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// Staking vault for this mint: the vault authority PDA itself, or `global_state.hfrt_vault` for HFRT.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == vault_address(&global_state, &stake_mint.key(), &vault_authority.key())
+            @ ErrorCode::WrongVault,
+        constraint = staking_vault.mint == trader_token_account.mint @ ErrorCode::WrongMint,
+    )]
+    pub staking_vault: Account<'info, TokenAccount>,
+    /// Per-mint stake position, required when `stake_mint` is not the HFRT mint.
+    #[account(
+        mut,
+        seeds = [b"stake-position", trader.key().as_ref(), stake_mint.key().as_ref()],
+        bump = stake_position.bump,
+    )]
+    pub stake_position: Option<Account<'info, StakePosition>>,
+    /// Global state, read for the pause flag and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeAndRecordTrade<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// Canonical governance account for trade-check configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Trader’s HFRT token account.
+    #[account(
+        mut,
+        constraint = trader_token_account.mint == global_state.hfrt_mint @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// Global state, read for the pause flag and updated with staker metrics and volume totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakeTokens<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// Canonical governance account for penalty configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// The mint being staked; HFRT or any other mint with an initialized `StakePosition`.
+    pub stake_mint: Account<'info, Mint>,
+    /// Trader’s token account for `stake_mint`.
+    #[account(
+        mut,
+        constraint = trader_token_account.mint == stake_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// PDA that owns the staking vault for this mint (seeded by "staking-vault" and the mint).
+    #[account(
+        seeds = [VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// Staking vault for this mint: the vault authority PDA itself, or `global_state.hfrt_vault` for HFRT.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == vault_address(&global_state, &stake_mint.key(), &vault_authority.key())
+            @ ErrorCode::WrongVault,
+    )]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// Per-mint stake position, required when `stake_mint` is not the HFRT mint.
+    #[account(
+        mut,
+        seeds = [b"stake-position", trader.key().as_ref(), stake_mint.key().as_ref()],
+        bump = stake_position.bump,
+    )]
+    pub stake_position: Option<Account<'info, StakePosition>>,
+    /// Global state, read for the pause flag and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct StakeTokensAsPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The mint being staked.
+    pub stake_mint: Account<'info, Mint>,
+    /// Staker’s token account for `stake_mint`.
+    #[account(
+        mut,
+        constraint = owner_token_account.owner == owner.key() @ ErrorCode::Unauthorized,
+        constraint = owner_token_account.mint == stake_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub owner_token_account: Account<'info, TokenAccount>,
+    /// PDA that owns the staking vault for this mint (seeded by "staking-vault" and the mint).
+    #[account(
+        seeds = [VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// Staking vault for this mint: the vault authority PDA itself, or `global_state.hfrt_vault` for HFRT.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == vault_address(&global_state, &stake_mint.key(), &vault_authority.key())
+            @ ErrorCode::WrongVault,
+    )]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// Fresh position mint; its single token represents the position.
+    #[account(
+        init,
+        payer = owner,
+        mint::decimals = 0,
+        mint::authority = mint_authority,
+    )]
+    pub position_mint: Account<'info, Mint>,
+    /// Staker's account for the position token (seeded by "position-token" and the position mint).
+    #[account(
+        init,
+        payer = owner,
+        seeds = [POSITION_TOKEN_SEED, position_mint.key().as_ref()],
+        bump,
+        token::mint = position_mint,
+        token::authority = owner,
+    )]
+    pub position_token_account: Account<'info, TokenAccount>,
+    /// Tokenized position PDA (seeded by "position" and the position mint).
+    #[account(
+        init,
+        payer = owner,
+        seeds = [TOKENIZED_POSITION_SEED, position_mint.key().as_ref()],
+        bump,
+        space = 8 + TokenizedPosition::LEN,
+    )]
+    pub tokenized_position: Account<'info, TokenizedPosition>,
+    /// PDA mint authority (seeded by "mint-authority"), which mints the position token.
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Global state, read for the pause flag and updated with staking totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UnstakePosition<'info> {
+    #[account(mut)]
+    pub holder: Signer<'info>,
+    /// Tokenized position PDA (seeded by "position" and the position mint), closed to the holder.
+    #[account(
+        mut,
+        seeds = [TOKENIZED_POSITION_SEED, position_mint.key().as_ref()],
+        bump = tokenized_position.bump,
+        has_one = position_mint,
+        has_one = stake_mint,
+        close = holder,
+    )]
+    pub tokenized_position: Account<'info, TokenizedPosition>,
+    #[account(mut)]
+    pub position_mint: Account<'info, Mint>,
+    /// Holder's account carrying the position token; holding it authorizes the unstake.
+    #[account(
+        mut,
+        constraint = holder_position_account.owner == holder.key() @ ErrorCode::PositionNotHeld,
+        constraint = holder_position_account.mint == position_mint.key() @ ErrorCode::PositionNotHeld,
+        constraint = holder_position_account.amount == 1 @ ErrorCode::PositionNotHeld,
+    )]
+    pub holder_position_account: Account<'info, TokenAccount>,
+    /// The staked mint.
+    pub stake_mint: Account<'info, Mint>,
+    /// Holder’s token account receiving the staked tokens.
+    #[account(
+        mut,
+        constraint = holder_token_account.owner == holder.key() @ ErrorCode::Unauthorized,
+        constraint = holder_token_account.mint == stake_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub holder_token_account: Account<'info, TokenAccount>,
+    /// PDA that owns the staking vault for this mint (seeded by "staking-vault" and the mint).
+    #[account(
+        seeds = [VAULT_SEED, stake_mint.key().as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// Staking vault for this mint: the vault authority PDA itself, or `global_state.hfrt_vault` for HFRT.
+    #[account(
+        mut,
+        constraint = staking_vault.key() == vault_address(&global_state, &stake_mint.key(), &vault_authority.key())
+            @ ErrorCode::WrongVault,
+    )]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// Canonical governance account for the clock-skew setting.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the pause flag and updated with staking totals.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RequestUnstake<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// Canonical governance account for the notice threshold and period.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Pending unstake PDA (seeded by "pending-unstake" and the trader account).
+    #[account(
+        init,
+        payer = owner,
+        seeds = [b"pending-unstake", trader.key().as_ref()],
+        bump,
+        space = 8 + PendingUnstake::LEN,
+    )]
+    pub pending_unstake: Account<'info, PendingUnstake>,
+    /// Global state, read for the pause flag.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawUnstake<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// Canonical governance account for penalty configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Pending unstake PDA, closed back to the owner once withdrawn.
+    #[account(
+        mut,
+        seeds = [b"pending-unstake", trader.key().as_ref()],
+        bump = pending_unstake.bump,
+        has_one = trader,
+        close = owner,
+    )]
+    pub pending_unstake: Account<'info, PendingUnstake>,
+    /// Trader’s HFRT token account.
+    #[account(mut)]
+    pub trader_token_account: Account<'info, TokenAccount>,
+    /// PDA that owns the HFRT staking vault (seeded by "staking-vault" and the HFRT mint).
+    #[account(
+        seeds = [VAULT_SEED, global_state.hfrt_mint.as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// Global state, read for the pause flag and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GetVaultStats<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    pub staking_vault: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct GetProtocolSummary<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Canonical governance account for the active rebate rate.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+}
+
+#[derive(Accounts)]
+pub struct BulkSnapshot<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// The authority, which pays rent for each new snapshot.
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SimulateUnstake<'info> {
+    /// Trader state account being previewed.
+    pub trader: Account<'info, Trader>,
+    /// Canonical governance account for penalty configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the canonical governance.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct AutoCompound<'info> {
+    /// Trader state account (must be pre-initialized).
+    #[account(mut, has_one = owner)]
+    pub trader: Account<'info, Trader>,
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(mut, address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct EstimateCompoundGrowth<'info> {
+    /// Trader state account being projected.
+    pub trader: Account<'info, Trader>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRelayerAllowlist<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Relayer allowlist PDA (seeded by "relayer-allowlist").
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"relayer-allowlist"],
+        bump,
+        space = 8 + RelayerAllowlist::LEN,
+    )]
+    pub relayer_allowlist: Account<'info, RelayerAllowlist>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRelayerAllowlist<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    #[account(
+        mut,
+        seeds = [b"relayer-allowlist"],
+        bump = relayer_allowlist.bump,
+    )]
+    pub relayer_allowlist: Account<'info, RelayerAllowlist>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RelayAutoCompound<'info> {
+    /// Trader being compounded on behalf of.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// Relayer submitting the instruction; must be on the allowlist.
+    pub relayer: Signer<'info>,
+    #[account(
+        seeds = [b"relayer-allowlist"],
+        bump = relayer_allowlist.bump,
+    )]
+    pub relayer_allowlist: Account<'info, RelayerAllowlist>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for rebate rate configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AutoCompoundAll<'info> {
+    /// Relayer submitting the instruction; must be on the allowlist.
+    pub relayer: Signer<'info>,
+    #[account(
+        seeds = [b"relayer-allowlist"],
+        bump = relayer_allowlist.bump,
+    )]
+    pub relayer_allowlist: Account<'info, RelayerAllowlist>,
+    /// The HFRT mint.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// PDA mint authority (seeded by "mint-authority").
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: UncheckedAccount<'info>,
+    /// Canonical governance account for rebate rate and reinvest cooldown configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the active rebate regime and updated with staker metrics.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SlashStake<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+        has_one = hfrt_mint,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Canonical governance account for the slash confirmation threshold.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Trader being slashed.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// The HFRT mint, whose supply is reduced by the burn.
+    #[account(mut)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA that owns the HFRT staking vault (seeded by "staking-vault" and the HFRT mint).
+    #[account(
+        seeds = [VAULT_SEED, global_state.hfrt_mint.as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ForceUnstakeToOwner<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+        has_one = hfrt_mint,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Trader whose stake is being recovered.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA that owns the HFRT staking vault (seeded by "staking-vault" and the HFRT mint).
+    #[account(
+        seeds = [VAULT_SEED, global_state.hfrt_mint.as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    /// Owner-controlled HFRT token account receiving the recovered stake.
+    #[account(
+        mut,
+        constraint = recovery_token_account.owner == trader.owner @ ErrorCode::Unauthorized,
+        constraint = recovery_token_account.mint == hfrt_mint.key() @ ErrorCode::WrongMint,
+    )]
+    pub recovery_token_account: Account<'info, TokenAccount>,
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeSlash<'info> {
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Canonical governance account for the slash confirmation threshold and delay.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Trader being slashed.
+    pub trader: Account<'info, Trader>,
+    /// Pending slash PDA (seeded by "pending-slash" and the trader account).
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"pending-slash", trader.key().as_ref()],
+        bump,
+        space = 8 + PendingSlash::LEN,
+    )]
+    pub pending_slash: Account<'info, PendingSlash>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ConfirmSlash<'info> {
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+        has_one = authority,
+        has_one = hfrt_mint,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Trader being slashed.
+    #[account(mut)]
+    pub trader: Account<'info, Trader>,
+    /// Pending slash PDA, closed back to the authority once executed.
+    #[account(
+        mut,
+        seeds = [b"pending-slash", trader.key().as_ref()],
+        bump = pending_slash.bump,
+        has_one = trader,
+        close = authority,
+    )]
+    pub pending_slash: Account<'info, PendingSlash>,
+    /// The HFRT mint, whose supply is reduced by the burn.
+    #[account(mut)]
+    pub hfrt_mint: Account<'info, Mint>,
+    /// PDA that owns the HFRT staking vault (seeded by "staking-vault" and the HFRT mint).
+    #[account(
+        seeds = [VAULT_SEED, global_state.hfrt_mint.as_ref()],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub vault_authority: UncheckedAccount<'info>,
+    /// HFRT staking vault (`global_state.hfrt_vault`).
+    #[account(mut, address = global_state.hfrt_vault @ ErrorCode::WrongVault)]
+    /// CHECK: This token account holds staked tokens.
+    pub staking_vault: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateDAOProposal<'info> {
+    #[account(
+        init,
+        payer = proposer,
+        space = 8 + DAOProposal::LEN,
+    )]
+    pub dao_proposal: Account<'info, DAOProposal>,
+    #[account(mut)]
+    pub proposer: Signer<'info>,
+    /// Canonical governance account for the allowed proposal kinds and voting period.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the canonical governance.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct VoteDAOProposal<'info> {
+    #[account(mut)]
+    pub dao_proposal: Account<'info, DAOProposal>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    /// Canonical governance account for the early-voter reward, minimum stake age and vote weighting
+    /// configuration.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// The voter's trader account, required when `governance.min_stake_age_to_vote` is set or voting is
+    /// stake-weighted; passing it also extends the voter's participation streak.
+    #[account(mut, constraint = trader.owner == voter.key() @ ErrorCode::Unauthorized)]
+    pub trader: Option<Account<'info, Trader>>,
+    /// Global state, read for the mint authority bump and supply cap and updated with the epoch mint total.
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// The voter's early-reward PDA (seeded by "voter" and the voter); omit to vote without a reward.
+    #[account(
+        mut,
+        seeds = [VOTER_SEED, voter.key().as_ref()],
+        bump = voter_state.bump,
+    )]
+    pub voter_state: Option<Account<'info, VoterState>>,
+    /// The HFRT mint, required when an early-voter reward is paid.
+    #[account(mut, address = global_state.hfrt_mint @ ErrorCode::WrongMint)]
+    pub hfrt_mint: Option<Account<'info, Mint>>,
+    /// Voter’s HFRT token account for the reward; must be owned by the voter.
+    #[account(
+        mut,
+        constraint = voter_token_account.owner == voter.key() @ ErrorCode::Unauthorized,
+        constraint = voter_token_account.mint == global_state.hfrt_mint @ ErrorCode::WrongMint,
+    )]
+    pub voter_token_account: Option<Account<'info, TokenAccount>>,
+    /// PDA mint authority (seeded by "mint-authority"), required when a reward is paid.
+    #[account(
+        seeds = [MINT_AUTHORITY_SEED],
+        bump = global_state.mint_authority_bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub mint_authority: Option<UncheckedAccount<'info>>,
+    pub token_program: Option<Program<'info, Token>>,
+    /// The voter's record of this vote (seeded by "vote-record", the proposal and `vote_record_key`), created
+    /// here; required under stake-weighted voting, which it limits to one vote per proposal and trader.
+    #[account(
+        init,
+        payer = voter,
+        space = 8 + VoteRecord::LEN,
+        seeds = [
+            VOTE_RECORD_SEED,
+            dao_proposal.key().as_ref(),
+            vote_record_key(trader.as_ref(), voter.key()).as_ref(),
+        ],
+        bump,
+    )]
+    pub vote_record: Option<Account<'info, VoteRecord>>,
+    /// Required when a `VoteRecord` is created.
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct ChangeVote<'info> {
+    #[account(mut)]
+    pub dao_proposal: Account<'info, DAOProposal>,
+    pub voter: Signer<'info>,
+    /// The trader account the vote was cast with, if any; it must still be the voter's.
+    #[account(constraint = trader.owner == voter.key() @ ErrorCode::Unauthorized)]
+    pub trader: Option<Account<'info, Trader>>,
+    /// The voter's record of their vote on this proposal.
+    #[account(
+        mut,
+        has_one = voter,
+        seeds = [
+            VOTE_RECORD_SEED,
+            dao_proposal.key().as_ref(),
+            vote_record_key(trader.as_ref(), voter.key()).as_ref(),
+        ],
+        bump = vote_record.bump,
+    )]
+    pub vote_record: Account<'info, VoteRecord>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeVoterState<'info> {
+    /// Voter early-reward PDA (seeded by "voter" and the voter).
+    #[account(
+        init,
+        payer = voter,
+        seeds = [VOTER_SEED, voter.key().as_ref()],
+        bump,
+        space = 8 + VoterState::LEN,
+    )]
+    pub voter_state: Account<'info, VoterState>,
+    #[account(mut)]
+    pub voter: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteDAOProposal<'info> {
+    #[account(mut)]
+    pub dao_proposal: Account<'info, DAOProposal>,
+    #[account(
+        mut,
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// Canonical governance account for the pass threshold.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseDAOProposal<'info> {
+    #[account(mut, has_one = proposer)]
+    pub dao_proposal: Account<'info, DAOProposal>,
+    /// Canonical governance account for the rent destination setting.
+    #[account(address = global_state.governance @ ErrorCode::WrongGovernance)]
+    pub governance: Account<'info, Governance>,
+    /// Global state, read for the canonical governance.
+    #[account(
+        seeds = [b"global-state"],
+        bump = global_state.bump,
+    )]
+    pub global_state: Account<'info, GlobalState>,
+    /// CHECK: The proposal's creator, receiving the rent unless it goes to the treasury.
+    #[account(mut)]
+    pub proposer: UncheckedAccount<'info>,
+    /// CHECK: Must match `governance.treasury`; required when rent goes to the treasury.
+    #[account(mut, address = governance.treasury @ ErrorCode::TreasuryMissing)]
+    pub treasury: Option<UncheckedAccount<'info>>,
+    pub closer: Signer<'info>,
+}
+
+impl<'info> TransferMintAuthority<'info> {
+    /// Prepares the context for reassigning the mint authority.
+    fn into_set_authority_context(&self) -> CpiContext<'_, '_, '_, 'info, SetAuthority<'info>> {
+        let cpi_accounts = SetAuthority {
+            current_authority: self.mint_authority.to_account_info().clone(),
+            account_or_mint: self.hfrt_mint.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ClaimRebate<'info> {
+    /// Prepares the context for minting tokens to the trader.
+    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.trader_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> RedeemPoints<'info> {
+    /// Prepares the context for minting redeemed HFRT to the trader.
+    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.trader_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> MigrateVault<'info> {
+    /// Prepares the context for moving the legacy vault's balance to the new vault.
+    fn into_transfer_to_new_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.legacy_vault.to_account_info().clone(),
+            to: self.new_vault.to_account_info().clone(),
+            authority: self.legacy_vault.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> DrainLegacyVault<'info> {
+    /// Prepares the context for moving the legacy vault's balance to the current HFRT vault.
+    fn into_transfer_to_hfrt_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.legacy_vault.to_account_info().clone(),
+            to: self.hfrt_vault.to_account_info().clone(),
+            authority: self.legacy_vault.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ClaimRebateSplit<'info> {
+    /// Prepares the context for minting the wallet portion to the trader.
+    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.trader_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+
+    /// Prepares the context for minting the staked portion directly to the staking vault.
+    fn into_mint_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> WithdrawReferral<'info> {
+    /// Prepares the context for minting accrued referral rewards to the referrer.
+    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.referrer_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ExpireStaleVolume<'info> {
+    /// Prepares the context for minting the keeper reward.
+    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.keeper_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ClaimMarketRebate<'info> {
+    /// Prepares the context for minting tokens to the trader.
+    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.trader_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ClaimRebateInRewardToken<'info> {
+    /// Prepares the context for transferring the payout from the reward vault to the trader.
+    fn into_transfer_from_reward_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.reward_vault.to_account_info().clone(),
+            to: self.trader_reward_account.to_account_info().clone(),
+            authority: self.reward_vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> StakeTokens<'info> {
+    /// Prepares the context for transferring tokens from the trader to the staking vault.
+    fn into_transfer_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.trader_token_account.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.owner.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> StakeAndRecordTrade<'info> {
+    /// Prepares the context for transferring tokens from the trader to the staking vault.
+    fn into_transfer_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.trader_token_account.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.owner.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> StakeTokensAsPosition<'info> {
+    /// Prepares the context for transferring tokens from the staker to the staking vault.
+    fn into_transfer_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.owner_token_account.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.owner.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+
+    /// Prepares the context for minting the single position token to the staker.
+    fn into_mint_position_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.position_mint.to_account_info().clone(),
+            to: self.position_token_account.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+
+    /// Prepares the context for dropping the position mint's authority so its supply stays at one.
+    fn into_set_authority_context(&self) -> CpiContext<'_, '_, '_, 'info, SetAuthority<'info>> {
+        let cpi_accounts = SetAuthority {
+            current_authority: self.mint_authority.to_account_info().clone(),
+            account_or_mint: self.position_mint.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> UnstakePosition<'info> {
+    /// Prepares the context for burning the holder's position token.
+    fn into_burn_position_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.position_mint.to_account_info().clone(),
+            from: self.holder_position_account.to_account_info().clone(),
+            authority: self.holder.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+
+    /// Prepares the context for transferring the staked tokens from the vault to the holder.
+    fn into_transfer_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info().clone(),
+            to: self.holder_token_account.to_account_info().clone(),
+            authority: self.vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> UnstakeTokens<'info> {
+    /// Prepares the context for transferring tokens from the staking vault back to the trader.
+    fn into_transfer_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info().clone(),
+            to: self.trader_token_account.to_account_info().clone(),
+            authority: self.vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> WithdrawUnstake<'info> {
+    /// Prepares the context for transferring tokens from the staking vault back to the trader.
+    fn into_transfer_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info().clone(),
+            to: self.trader_token_account.to_account_info().clone(),
+            authority: self.vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> AutoCompound<'info> {
+    /// Prepares the context for minting tokens directly to the staking vault.
+    fn into_mint_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> RelayAutoCompound<'info> {
+    /// Prepares the context for minting tokens directly to the staking vault.
+    fn into_mint_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
 }
 
-#[derive(Accounts)]
-pub struct UnstakeTokens<'info> {
-    /// Trader state account (must be pre-initialized).
-    #[account(mut, has_one = owner)]
-    pub trader: Account<'info, Trader>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// Trader’s HFRT token account.
-    #[account(mut)]
-    pub trader_token_account: Account<'info, TokenAccount>,
-    /// Staking vault PDA (seeded by "staking-vault").
-    #[account(
-        mut,
-        seeds = [VAULT_SEED],
-        bump,
-    )]
-    /// CHECK: This PDA holds staked tokens.
-    pub staking_vault: UncheckedAccount<'info>,
-    pub token_program: Program<'info, Token>,
+impl<'info> AutoCompoundAll<'info> {
+    /// Prepares the context for minting the batch's compounded rebates to the staking vault.
+    fn into_mint_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
+        let cpi_accounts = MintTo {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            to: self.staking_vault.to_account_info().clone(),
+            authority: self.mint_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
 }
 
-#[derive(Accounts)]
-pub struct AutoCompound<'info> {
-    /// Trader state account (must be pre-initialized).
-    #[account(mut, has_one = owner)]
-    pub trader: Account<'info, Trader>,
-    #[account(mut)]
-    pub owner: Signer<'info>,
-    /// The HFRT mint.
-    #[account(mut)]
-    pub hfrt_mint: Account<'info, Mint>,
-    /// Staking vault PDA (seeded by "staking-vault").
-    #[account(
-        mut,
-        seeds = [VAULT_SEED],
-        bump,
-    )]
-    /// CHECK: This PDA holds staked tokens.
-    pub staking_vault: UncheckedAccount<'info>,
-    /// PDA mint authority (seeded by "mint-authority").
-    #[account(
-        seeds = [b"mint-authority"],
-        bump,
-    )]
-    /// CHECK: This PDA is derived deterministically.
-    pub mint_authority: UncheckedAccount<'info>,
-    /// Governance account for rebate rate configuration.
-    #[account(mut)]
-    pub governance: Account<'info, Governance>,
-    pub token_program: Program<'info, Token>,
+impl<'info> SlashStake<'info> {
+    /// Prepares the context for burning slashed tokens out of the staking vault.
+    fn into_burn_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            from: self.staking_vault.to_account_info().clone(),
+            authority: self.vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ForceUnstakeToOwner<'info> {
+    /// Prepares the context for transferring recovered stake from the vault to the owner's account.
+    fn into_transfer_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
+        let cpi_accounts = Transfer {
+            from: self.staking_vault.to_account_info().clone(),
+            to: self.recovery_token_account.to_account_info().clone(),
+            authority: self.vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+impl<'info> ConfirmSlash<'info> {
+    /// Prepares the context for burning slashed tokens out of the staking vault.
+    fn into_burn_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Burn<'info>> {
+        let cpi_accounts = Burn {
+            mint: self.hfrt_mint.to_account_info().clone(),
+            from: self.staking_vault.to_account_info().clone(),
+            authority: self.vault_authority.to_account_info().clone(),
+        };
+        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
+    }
+}
+
+/// Governance parameters for `update_governance_params`; only the `Some` fields are applied.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct GovernanceParams {
+    pub referral_bps: Option<u16>,
+    pub reinvest_cooldown: Option<i64>,
+    pub keeper_reward: Option<u64>,
+    pub frequent_trade_allowance: Option<u8>,
+    pub soft_wash_handling: Option<bool>,
+    pub max_rolling_volume: Option<u64>,
+    pub multiplier_decay_window: Option<i64>,
+    pub wash_decay_window: Option<i64>,
+    pub min_rebate_to_mint: Option<u64>,
+    pub min_stake_for_multiplier: Option<u64>,
+    pub pass_threshold_bps: Option<u16>,
+    pub voting_period: Option<i64>,
+    pub min_proposal_age: Option<i64>,
+    pub min_stake_age_to_vote: Option<i64>,
+}
+
+/// One fill reported to `batch_record_trades_for_markets`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone)]
+pub struct MarketFill {
+    pub market_index: u8, // Index into the instruction's remaining `MarketVolume` accounts.
+    pub amount: u64,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct GlobalState {
+    pub authority: Pubkey,
+    pub hfrt_mint: Pubkey,
+    pub fee_discount: u8,
+    pub bump: u8,
+    pub volatility_mode: bool,     // When set, rebates use the volatile rebate rate.
+    pub volatility_oracle: Pubkey, // Keeper/oracle allowed to toggle volatility mode.
+    pub reward_mint: Pubkey,       // SPL token paid by claim_rebate_in_reward_token.
+    pub paused: bool,              // Halts trading, claims, staking and compounding.
+    pub emergency_council: Pubkey, // May pause, but not unpause.
+    pub trusted_reporter: Pubkey,  // May record trades on behalf of any trader.
+    pub mint_authority_bump: u8,   // Bump of the mint-authority PDA that owns the HFRT mint.
+    pub total_stakers: u64,        // Number of traders with a nonzero stake.
+    pub max_total_supply: u64,     // HFRT supply cap for program mints; 0 means uncapped.
+    pub total_staked: u64,         // Sum of all traders' HFRT staked_amount plus tokenized HFRT positions.
+    pub scheduled_fee_discount: u8, // Fee discount taking effect at fee_discount_effective_at.
+    pub fee_discount_effective_at: i64, // When the scheduled discount applies; 0 means none scheduled.
+    pub pause_reason: u8,          // Integrator-defined reason code for the current pause.
+    pub paused_at: i64,            // When the current pause began; 0 while unpaused.
+    pub tier_thresholds: [u64; 3], // Raw rolling-volume tier thresholds for the HFRT mint's decimals.
+    pub total_volume: u64,         // Lifetime trade volume credited to rolling volumes (saturating).
+    pub total_rebates_minted: u64, // Lifetime HFRT minted as rebates, to wallets or as stake (saturating).
+    pub max_total_staked: u64,     // Cap on total_staked; 0 means uncapped.
+    pub renounced: bool,           // Authority permanently renounced; it stays the default pubkey.
+    pub hfrt_vault: Pubkey,        // HFRT staking vault: the vault authority PDA, or its ATA once migrated.
+    pub vault_migrated: bool,      // migrate_vault has moved the HFRT vault to the associated token account.
+    pub max_mint_per_epoch: u64,   // Cap on HFRT minted by the program per epoch; 0 means uncapped.
+    pub epoch_duration: i64,       // Length of an inflation epoch in seconds.
+    pub epoch_start: i64,          // When the current inflation epoch began.
+    pub minted_this_epoch: u64,    // HFRT minted by the program since epoch_start.
+    pub rebate_paused: bool,       // Blocks rebate claims and compounds only; see set_rebate_paused.
+    pub activity_window: i64,      // Length of a protocol-wide volume window in seconds; 0 stops tracking.
+    pub activity_window_start: i64,
+    pub current_window_volume: u64, // Volume recorded since activity_window_start.
+    pub previous_window_volume: u64, // Volume of the last complete window.
+    pub low_activity_threshold: u64, // Window volume below which claims earn the low-activity boost.
+    pub low_activity_boost_bps: u16, // Claim bonus in basis points while activity is low; 0 disables.
+    pub governance: Pubkey,        // Canonical governance account; see set_governance.
+}
+impl GlobalState {
+    /// Space: Pubkey (32) + Pubkey (32) + u8 (1) + u8 (1) + bool (1) + Pubkey (32) + Pubkey (32) + bool (1)
+    /// + Pubkey (32) + Pubkey (32) + u8 (1) + u64 (8) + u64 (8) + u64 (8) + u8 (1) + i64 (8) + u8 (1) + i64 (8)
+    /// + [u64; 3] (24) + u64 (8) + u64 (8) + u64 (8) + bool (1) + Pubkey (32) + bool (1) + u64 (8) + i64 (8)
+    /// + i64 (8) + u64 (8) + bool (1) + i64 (8) + i64 (8) + u64 (8) + u64 (8) + u64 (8) + u16 (2) + Pubkey (32)
+    pub const LEN: usize = 32 + 32 + 1 + 1 + 1 + 32 + 32 + 1 + 32 + 32 + 1 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 8 * 3
+        + 8 + 8 + 8 + 1 + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 2 + 32;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Governance {
+    pub authority: Pubkey,
+    pub rebate_rate: u8,      // For example: 10 means a 1% rebate.
+    pub max_fee_discount: u8, // Maximum fee discount allowed.
+    pub volatile_rebate_rate: u8, // Rebate rate applied while volatility mode is on.
+    pub slash_confirm_threshold: u64, // Slashes above this need propose + confirm.
+    pub slash_confirm_delay: i64,     // Seconds between propose_slash and confirm_slash.
+    pub reject_negative_elapsed: bool, // Error on clock skew instead of treating it as zero elapsed.
+    pub veteran_age: i64,              // Account age (seconds) that earns the veteran bonus; 0 disables.
+    pub veteran_bonus_bps: u16,        // Veteran rebate bonus in basis points.
+    pub max_effective_rebate_bps: u16, // Ceiling on rate * max multiplier, in bps of volume.
+    pub wash_window_secs: i64,         // Sliding window for multi-trade wash detection.
+    pub wash_window_volume: u64,       // Max volume inside the window; 0 disables the check.
+    pub large_unstake_threshold: u64,  // HFRT stake above this unstakes only via request + withdraw; 0 disables.
+    pub unstake_notice_period: i64,    // Seconds between request_unstake and withdraw_unstake.
+    pub linear_rate_mode: bool,        // Interpolate the rebate rate by volume instead of a flat rate.
+    pub linear_min_rate: u8,           // Rate at or below linear_volume_min.
+    pub linear_max_rate: u8,           // Rate at or above linear_volume_max.
+    pub linear_volume_min: u64,
+    pub linear_volume_max: u64,
+    pub keeper_reward: u64,            // HFRT minted per productive maintenance call.
+    pub frequent_trade_allowance: u8,  // Fast trades tolerated before FrequentTrades trips.
+    pub reinvest_cooldown: i64,        // Minimum seconds between inline auto-reinvests.
+    pub pass_threshold_bps: u16,       // Super-majority of votes cast required to execute a proposal.
+    pub soft_wash_handling: bool,      // Flag wash trades without crediting volume instead of rejecting.
+    pub max_rolling_volume: u64,       // Cap on rolling volume per trader or market; 0 disables.
+    pub inclusive_tier_boundaries: bool, // Tier thresholds compare with >= when set, > otherwise.
+    pub bond_forfeit_policy: u8,       // Destination of forfeited proposal bonds (BOND_FORFEIT_*).
+    pub last_rate_change: i64,         // When update_rebate_rate last changed the rate.
+    pub rate_change_cooldown: i64,     // Minimum seconds between rate changes; 0 disables.
+    pub max_rate_change: u8,           // Largest rate step per update; 0 leaves it unbounded.
+    pub referral_bps: u16,             // Share of a referred trader's rebate accrued to the referrer.
+    pub partial_unstake_resets_clock: bool, // Restart the remaining stake's clock on a partial unstake.
+    pub diminishing_threshold: u64,    // Lifetime rebates after which payouts diminish; 0 disables.
+    pub diminishing_floor_bps: u16,    // Smallest share of a rebate still paid past the threshold.
+    pub early_voter_count: u8,         // Votes per proposal that earn the early-voter reward.
+    pub early_voter_reward: u64,       // HFRT minted per early vote; 0 disables.
+    pub early_voter_cooldown: i64,     // Minimum seconds between early-voter rewards for one wallet.
+    pub min_stake_age_to_vote: i64,    // Seconds a voter's stake must be held before voting; 0 disables.
+    pub spot_rebate_rate: u8,          // Rate for spot-attributed volume; 0 uses the active rate.
+    pub perp_rebate_rate: u8,          // Rate for perp-attributed volume; 0 uses the active rate.
+    pub compound_cliff: i64,           // Seconds before any compounded reward vests.
+    pub compound_vest_duration: i64,   // Seconds over which compounded rewards vest after the cliff.
+    pub treasury: Pubkey,              // Protocol treasury wallet.
+    pub proposal_rent_to_treasury: bool, // Send closed executed proposals' rent to the treasury.
+    pub priority_rebate_bps: u16,      // Share of reported priority-fee spend reimbursed in HFRT at claim.
+    pub priority_spend_cap: u64,       // Priority spend a trader may report per 24 hours; 0 leaves it uncapped.
+    pub wash_threshold_inclusive: bool, // A trade of exactly WASH_TRADE_THRESHOLD is wash-checked.
+    pub allowed_proposal_kinds: u32,   // Bitmask of proposal kinds that may be created.
+    pub streak_boost_bps: u16,         // Compounding boost per consecutive proposal voted on.
+    pub max_streak_boost_bps: u16,     // Cap on the voting-streak compounding boost.
+    pub points_per_volume: u64,        // Loyalty points accrued per LOYALTY_VOLUME_UNIT of volume.
+    pub point_redemption_rate: u64,    // HFRT minted per redeemed point; 0 disables redemption.
+    pub rebate_denominator: u64,       // Divisor applied to rebate rates; 0 means DEFAULT_REBATE_DENOMINATOR.
+    pub stake_weighted_voting: bool,   // Votes count the voter's staked HFRT instead of one each.
+    pub allow_zero_weight_votes: bool, // Record zero-stake votes under stake weighting instead of rejecting them.
+    pub voting_period: i64,            // Seconds new proposals stay open for voting; 0 means no deadline.
+    pub multiplier_decay_window: i64,  // Stagnant-volume window per claim multiplier step lost; 0 disables.
+    pub profiling_enabled: bool,       // Emit ComputeUsed events from the profiled instructions.
+    pub min_rebate_to_mint: u64,       // Claims paying out less are rejected; 0 disables.
+    pub free_unstake_allowance: u64,   // HFRT unstakeable penalty-free per free_unstake_period; 0 disables.
+    pub free_unstake_period: i64,      // Seconds before a trader's free unstake allowance resets.
+    pub min_stake_for_multiplier: u64, // HFRT stake needed for a claim multiplier above 1; 0 disables.
+    pub wash_decay_window: i64,        // Seconds per wash flag forgiven on a trader; 0 disables decay.
+    pub min_proposal_age: i64,         // Seconds a proposal must exist before its first vote or execution.
+    pub skewed_stake_min_penalty: bool, // A stake start in the future gets the lowest, not highest, penalty.
+    pub max_markets_per_trader: u16,   // Markets a trader may start trading in; 0 disables the cap.
+    pub ties_pass: bool,               // An exact vote tie executes instead of being rejected.
+    pub priority_hfrt_per_sol: u64,    // HFRT base units reimbursed per SOL of priority spend; 0 disables.
+}
+impl Governance {
+    /// Space: Pubkey (32) + u8 (1) + u8 (1) + u8 (1) + u64 (8) + i64 (8) + bool (1) + i64 (8) + u16 (2) + u16 (2)
+    /// + i64 (8) + u64 (8) + u64 (8) + i64 (8) + bool (1) + u8 (1) + u8 (1) + u64 (8) + u64 (8)
+    /// + u64 (8) + u8 (1) + i64 (8) + u16 (2) + bool (1) + u64 (8) + bool (1) + u8 (1) + i64 (8) + i64 (8) + u8 (1)
+    /// + u16 (2) + bool (1) + u64 (8) + u16 (2) + u8 (1) + u64 (8) + i64 (8) + i64 (8) + u8 (1) + u8 (1)
+    /// + i64 (8) + i64 (8) + Pubkey (32) + bool (1) + u16 (2) + u64 (8) + bool (1) + u32 (4) + u16 (2) + u16 (2)
+    /// + u64 (8) + u64 (8) + u64 (8) + bool (1) + bool (1) + i64 (8) + i64 (8) + bool (1) + u64 (8)
+    /// + u64 (8) + i64 (8) + u64 (8) + i64 (8) + i64 (8) + bool (1) + u16 (2) + bool (1) + u64 (8)
+    pub const LEN: usize = 32 + 1 + 1 + 1 + 8 + 8 + 1 + 8 + 2 + 2 + 8 + 8 + 8 + 8 + 1 + 1 + 1 + 8 + 8 + 8 + 1 + 8 + 2 + 1
+        + 8 + 1 + 1 + 8 + 8 + 1 + 2 + 1 + 8 + 2 + 1 + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 32 + 1 + 2 + 8 + 1 + 4 + 2 + 2
+        + 8 + 8 + 8 + 1 + 1 + 8 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 2 + 1 + 8;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Trader {
+    pub owner: Pubkey,
+    pub rolling_volume: u64,
+    pub last_update: i64,
+    pub staked_amount: u64,
+    pub stake_start_time: i64, // Unix timestamp for when staking began.
+    pub created_at: i64,       // Unix timestamp for when the trader account was initialized.
+    pub blocked: bool,         // Compliance block on claims, compounding and unstaking.
+    pub pending_owner: Pubkey, // Nominee of an in-progress ownership transfer, or default.
+    pub recent_trade_times: [i64; RECENT_TRADE_COUNT],   // Ring buffer of recent trade timestamps.
+    pub recent_trade_amounts: [u64; RECENT_TRADE_COUNT], // Ring buffer of recent trade amounts.
+    pub recent_trade_index: u8,                          // Next ring buffer slot to overwrite.
+    pub fast_trade_count: u8,                            // Consecutive trades inside the frequency floor.
+    pub auto_reinvest: bool,                             // Compound the rebate inline on every record_trade.
+    pub last_reinvest_at: i64,                           // Unix timestamp of the last inline reinvestment.
+    pub max_tier_reached: u8,                            // Highest rebate multiplier ever reached; never resets.
+    pub flagged_trade_count: u64,                        // Trades flagged as wash trades under soft handling.
+    pub next_eligible_trade_time: i64,                   // Earliest time the next trade clears the frequency check.
+    pub compounded_amount: u64,                          // Portion of staked_amount that came from compounding.
+    pub compounded_since: i64,                           // Amount-weighted start of the compounded portion.
+    pub last_maker_time: i64,                            // Latest maker fill, for self-match detection.
+    pub last_maker_amount: u64,
+    pub last_taker_time: i64,                            // Latest taker fill, for self-match detection.
+    pub last_taker_amount: u64,
+    pub referrer: Pubkey,                                // Earns referral_bps of each claim; default if none.
+    pub is_staking: bool,                                // Has HFRT stake; stake_start_time is only meaningful when set.
+    pub last_rebate_slot: u64,                           // Slot of the last claim or compound.
+    pub lifetime_rebates: u64,                           // Total rebates ever claimed, for diminishing returns.
+    pub spot_volume: u64,                                // Part of rolling_volume attributed to spot venues.
+    pub perp_volume: u64,                                // Part of rolling_volume attributed to perp venues.
+    pub compound_withdrawn: u64,                         // Vested compounded rewards unstaked on this clock.
+    pub pending_priority_spend: u64,                     // Reported priority fees awaiting reimbursement.
+    pub priority_window_spend: u64,                      // Priority fees reported in the current window.
+    pub priority_window_start: i64,                      // Start of the 24-hour priority reporting window.
+    pub consecutive_proposals_voted: u32,                // Voting streak over sequential proposal ids.
+    pub last_voted_proposal_id: u64,                     // Proposal id of the last vote counted in the streak.
+    pub loyalty_points: u64,                             // Non-transferable points from volume; never expire.
+    pub last_volume_increase: i64,                       // When a trade last grew rolling_volume or a market bucket.
+    pub free_unstake_used: u64,                          // Penalty-free HFRT unstaked in the current window.
+    pub free_unstake_window_start: i64,                  // When the current free unstake window began.
+    pub registered: bool,                                // Listed in the trader registry.
+    pub last_wash_flag_at: i64,                          // Last wash flag, advanced as flags decay.
+    pub claim_nonce: u64,                                // claim_rebate calls so far; seeds the next ClaimReceipt.
+    pub active_market_count: u16,                        // MarketVolume accounts that have recorded a trade.
+}
+impl Trader {
+    /// Space: Pubkey (32) + u64 (8) + i64 (8) + u64 (8) + i64 (8) + i64 (8) + bool (1) + Pubkey (32)
+    /// + [i64; 4] (32) + [u64; 4] (32) + u8 (1) + u8 (1) + bool (1) + i64 (8) + u8 (1) + u64 (8) + i64 (8)
+    /// + u64 (8) + i64 (8) + i64 (8) + u64 (8) + i64 (8) + u64 (8) + Pubkey (32) + bool (1) + u64 (8)
+    /// + u64 (8) + u64 (8) + u64 (8) + u64 (8) + u64 (8) + u64 (8) + i64 (8) + u32 (4) + u64 (8) + u64 (8)
+    /// + i64 (8) + u64 (8) + i64 (8) + bool (1) + i64 (8) + u64 (8) + u16 (2)
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 8 + 8 + 1 + 32
+        + 8 * RECENT_TRADE_COUNT + 8 * RECENT_TRADE_COUNT
+        + 1 + 1 + 1 + 8 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 8 + 4 + 8 + 8
+        + 8 + 8 + 8 + 1 + 8 + 8 + 2;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct DAOProposal {
+    pub proposal_id: u64,
+    pub proposer: Pubkey,
+    pub new_fee_discount: u8,
+    pub votes_for: u64,
+    pub votes_against: u64,
+    pub executed: bool,      // Set once execute_dao_proposal succeeds; only executed proposals can be closed.
+    pub kind: u8,            // One of the PROPOSAL_KIND_* constants.
+    pub voter_count: u64,    // Votes cast, including zero-weight ones, independent of their weight.
+    pub voting_ends_at: i64, // Unix timestamp when voting closes; 0 if it stays open until execution.
+    pub created_at: i64,
+}
+impl DAOProposal {
+    pub const LEN: usize = 8 + 32 + 1 + 8 + 8 + 1 + 1 + 8 + 8 + 8;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct RelayerAllowlist {
+    #[max_len(MAX_RELAYERS)]
+    pub relayers: Vec<Pubkey>, // At most MAX_RELAYERS entries.
+    pub bump: u8,
+}
+impl RelayerAllowlist {
+    /// Space: Vec prefix (4) + MAX_RELAYERS * Pubkey (32) + u8 (1)
+    pub const LEN: usize = 4 + MAX_RELAYERS * 32 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PendingSlash {
+    pub trader: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64, // Earliest time confirm_slash may run.
+    pub bump: u8,
+}
+impl PendingSlash {
+    /// Space: Pubkey (32) + u64 (8) + i64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct PendingUnstake {
+    pub trader: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64, // Earliest time withdraw_unstake may run.
+    pub bump: u8,
+}
+impl PendingUnstake {
+    /// Space: Pubkey (32) + u64 (8) + i64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct MarketVolume {
+    pub owner: Pubkey,
+    pub market: Pubkey,       // Venue identifier this bucket tracks.
+    pub rolling_volume: u64,  // 24-hour volume on this market only.
+    pub last_update: i64,
+    pub bump: u8,
+}
+impl MarketVolume {
+    /// Space: Pubkey (32) + Pubkey (32) + u64 (8) + i64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct StakePosition {
+    pub trader: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub stake_start_time: i64, // Amount-weighted start of this position.
+    pub bump: u8,
+}
+impl StakePosition {
+    /// Space: Pubkey (32) + Pubkey (32) + u64 (8) + i64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TokenizedPosition {
+    pub position_mint: Pubkey, // Supply-one mint whose holder owns this position.
+    pub stake_mint: Pubkey,
+    pub amount: u64,
+    pub stake_start_time: i64,
+    pub bump: u8,
+}
+impl TokenizedPosition {
+    /// Space: Pubkey (32) + Pubkey (32) + u64 (8) + i64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct Snapshot {
+    pub trader: Pubkey,        // Trader account captured.
+    pub owner: Pubkey,
+    pub snapshot_id: u64,
+    pub rolling_volume: u64,
+    pub staked_amount: u64,
+    pub lifetime_rebates: u64,
+    pub taken_at: i64,         // Unix timestamp at which the values were frozen.
+    pub bump: u8,
+}
+impl Snapshot {
+    /// Space: Pubkey (32) + Pubkey (32) + u64 (8) + u64 (8) + u64 (8) + u64 (8) + i64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ReferrerState {
+    pub referrer: Pubkey,
+    pub pending_referral: u64, // Referral rewards accrued by claims, awaiting withdraw_referral.
+    pub total_withdrawn: u64,
+    pub bump: u8,
+}
+impl ReferrerState {
+    /// Space: Pubkey (32) + u64 (8) + u64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VoterState {
+    pub voter: Pubkey,
+    pub last_rewarded_proposal: Pubkey, // Proposal of the most recent early-voter reward.
+    pub last_early_reward_at: i64,      // When that reward was paid; 0 if never.
+    pub early_rewards_earned: u64,
+    pub bump: u8,
+}
+impl VoterState {
+    /// Space: Pubkey (32) + Pubkey (32) + i64 (8) + u64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 32 + 8 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VoteRecord {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub vote_for: bool,
+    pub weight: u64, // Weight added to the tally; 0 for a participation-only vote.
+    pub bump: u8,
+}
+impl VoteRecord {
+    /// Space: Pubkey (32) + Pubkey (32) + bool (1) + u64 (8) + u8 (1)
+    pub const LEN: usize = 32 + 32 + 1 + 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TraderRegistry {
+    pub trader_count: u64, // Traders registered so far; the next one takes this index.
+    pub bump: u8,
+}
+impl TraderRegistry {
+    /// Space: u64 (8) + u8 (1)
+    pub const LEN: usize = 8 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct TraderRegistryPage {
+    pub page: u32,
+    pub traders: [Pubkey; TRADERS_PER_REGISTRY_PAGE], // Trader PDAs at indices page * TRADERS_PER_REGISTRY_PAGE onward.
+    pub bump: u8,
+}
+impl TraderRegistryPage {
+    /// Space: u32 (4) + TRADERS_PER_REGISTRY_PAGE * Pubkey (32) + u8 (1)
+    pub const LEN: usize = 4 + TRADERS_PER_REGISTRY_PAGE * 32 + 1;
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct ClaimReceipt {
+    pub owner: Pubkey,
+    pub claim_nonce: u64,
+    pub amount: u64,      // HFRT minted by the claim.
+    pub claimed_at: i64,
+    pub rebate_rate: u8,  // Effective rebate rate the claim was priced at.
+    pub multiplier: u8,   // Volume multiplier after decay and the stake gate.
+    pub bump: u8,
+}
+impl ClaimReceipt {
+    /// Space: Pubkey (32) + u64 (8) + u64 (8) + i64 (8) + u8 (1) + u8 (1) + u8 (1)
+    pub const LEN: usize = 32 + 8 + 8 + 8 + 1 + 1 + 1;
+}
+
+// Compile-time guard that every hand-computed `LEN` matches the derived `InitSpace` size,
+// so adding a field without updating its `LEN` fails the build.
+const _: () = assert!(GlobalState::LEN == GlobalState::INIT_SPACE);
+const _: () = assert!(Governance::LEN == Governance::INIT_SPACE);
+const _: () = assert!(Trader::LEN == Trader::INIT_SPACE);
+const _: () = assert!(DAOProposal::LEN == DAOProposal::INIT_SPACE);
+const _: () = assert!(RelayerAllowlist::LEN == RelayerAllowlist::INIT_SPACE);
+const _: () = assert!(PendingSlash::LEN == PendingSlash::INIT_SPACE);
+const _: () = assert!(PendingUnstake::LEN == PendingUnstake::INIT_SPACE);
+const _: () = assert!(MarketVolume::LEN == MarketVolume::INIT_SPACE);
+const _: () = assert!(StakePosition::LEN == StakePosition::INIT_SPACE);
+const _: () = assert!(TokenizedPosition::LEN == TokenizedPosition::INIT_SPACE);
+const _: () = assert!(Snapshot::LEN == Snapshot::INIT_SPACE);
+const _: () = assert!(ReferrerState::LEN == ReferrerState::INIT_SPACE);
+const _: () = assert!(VoterState::LEN == VoterState::INIT_SPACE);
+const _: () = assert!(VoteRecord::LEN == VoteRecord::INIT_SPACE);
+const _: () = assert!(TraderRegistry::LEN == TraderRegistry::INIT_SPACE);
+const _: () = assert!(TraderRegistryPage::LEN == TraderRegistryPage::INIT_SPACE);
+const _: () = assert!(ClaimReceipt::LEN == ClaimReceipt::INIT_SPACE);
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Arithmetic overflow occurred.")]
+    Overflow,
+    #[msg("Insufficient staked tokens for unstaking.")]
+    InsufficientStake,
+    #[msg("Invalid rebate rate.")]
+    InvalidRebateRate,
+    #[msg("Potential wash trading detected.")]
+    WashTrade,
+    #[msg("Trades are occurring too frequently.")]
+    FrequentTrades,
+    #[msg("DAO proposal rejected due to insufficient votes.")]
+    ProposalRejected,
+    #[msg("No trading volume to claim a rebate on.")]
+    NoVolume,
+    #[msg("Computed rebate is too small to claim.")]
+    RebateTooSmall,
+    #[msg("Signer is not authorized for this action.")]
+    Unauthorized,
+    #[msg("Slash exceeds the immediate threshold and must be proposed and confirmed.")]
+    SlashRequiresConfirmation,
+    #[msg("Slash is within the immediate threshold; use slash_stake instead.")]
+    SlashBelowThreshold,
+    #[msg("Pending slash delay has not elapsed yet.")]
+    SlashDelayNotElapsed,
+    #[msg("Invalid slash parameters.")]
+    InvalidSlashParams,
+    #[msg("Clock moved backwards relative to a stored timestamp.")]
+    ClockSkew,
+    #[msg("Signer is not an approved relayer.")]
+    UnauthorizedRelayer,
+    #[msg("Relayer allowlist is full.")]
+    RelayerAllowlistFull,
+    #[msg("Token account or mint does not match the expected mint.")]
+    WrongMint,
+    #[msg("Reward vault balance is insufficient for the payout.")]
+    InsufficientRewardVault,
+    #[msg("Invalid veteran bonus parameters.")]
+    InvalidVeteranParams,
+    #[msg("The program is paused.")]
+    ProgramPaused,
+    #[msg("Signer is neither the trader's owner nor the trusted reporter.")]
+    UnauthorizedReporter,
+    #[msg("Mint authority PDA does not match the supplied bump or the HFRT mint.")]
+    InvalidMintAuthority,
+    #[msg("This address is blocked.")]
+    AddressBlocked,
+    #[msg("Signer is not the pending owner of this trader.")]
+    NotPendingOwner,
+    #[msg("Invalid wash-trade window parameters.")]
+    InvalidWashWindow,
+    #[msg("Supply cap cannot be set below the current HFRT supply.")]
+    InvalidSupplyCap,
+    #[msg("Minting would exceed the HFRT supply cap.")]
+    SupplyCapExceeded,
+    #[msg("Unstake exceeds the instant threshold; use request_unstake.")]
+    UnstakeNoticeRequired,
+    #[msg("Unstake is within the instant threshold; use unstake_tokens.")]
+    UnstakeNoticeNotRequired,
+    #[msg("Unstake notice period has not elapsed yet.")]
+    UnstakeNoticeNotElapsed,
+    #[msg("Invalid unstake notice parameters.")]
+    InvalidUnstakeNotice,
+    #[msg("Invalid linear rebate rate parameters.")]
+    InvalidLinearRate,
+    #[msg("Market volume account does not match the trader and market.")]
+    MarketMismatch,
+    #[msg("Auto-reinvest requires the mint, staking vault, mint authority and token program.")]
+    ReinvestAccountsMissing,
+    #[msg("Reinvest cooldown must not be negative.")]
+    InvalidReinvestCooldown,
+    #[msg("Arithmetic overflow while computing a rebate.")]
+    RebateOverflow,
+    #[msg("Arithmetic overflow while accumulating trading volume.")]
+    VolumeOverflow,
+    #[msg("Arithmetic overflow while updating a stake.")]
+    StakeOverflow,
+    #[msg("Arithmetic overflow while applying an unstake penalty.")]
+    PenaltyOverflow,
+    #[msg("Staking a non-HFRT mint requires its stake position account.")]
+    StakePositionRequired,
+    #[msg("Pass threshold must not exceed 10000 basis points.")]
+    InvalidPassThreshold,
+    #[msg("Global state already exists with different parameters.")]
+    InitializationConflict,
+    #[msg("Stake start time cannot be in the future.")]
+    InvalidStakeStartTime,
+    #[msg("Scheduled fee discount must take effect in the future.")]
+    InvalidFeeSchedule,
+    #[msg("No scheduled fee discount is due yet.")]
+    FeeScheduleNotDue,
+    #[msg("Stake share must be at most 10000 basis points.")]
+    InvalidStakeBps,
+    #[msg("Trade batch must contain between 1 and MAX_BATCH_TRADES fills.")]
+    InvalidBatchSize,
+    #[msg("Too many market accounts passed for one batch.")]
+    TooManyMarkets,
+    #[msg("Unknown bond forfeit policy.")]
+    InvalidBondForfeitPolicy,
+    #[msg("The rebate rate was changed too recently.")]
+    RateChangeCooldown,
+    #[msg("The rebate rate change exceeds the per-update limit.")]
+    RateChangeTooLarge,
+    #[msg("Rate change cooldown cannot be negative.")]
+    InvalidRateChangeLimits,
+    #[msg("The referrer state account is required for a referred trader.")]
+    ReferrerStateMissing,
+    #[msg("The trader already has a referrer.")]
+    ReferrerAlreadySet,
+    #[msg("A trader cannot refer themselves.")]
+    InvalidReferrer,
+    #[msg("No referral rewards are pending.")]
+    NoPendingReferral,
+    #[msg("Referral share must be at most 10000 basis points.")]
+    InvalidReferralBps,
+    #[msg("A rebate was already claimed or compounded for this trader in this slot.")]
+    AlreadyProcessedThisSlot,
+    #[msg("Diminishing-returns floor must be at most 10000 basis points.")]
+    InvalidDiminishingParams,
+    #[msg("Mint accounts are required to pay the early-voter reward.")]
+    EarlyVoterAccountsMissing,
+    #[msg("Early-voter cooldown cannot be negative.")]
+    InvalidEarlyVoterParams,
+    #[msg("A tokenized position must stake a nonzero amount.")]
+    InvalidPositionAmount,
+    #[msg("The signer does not hold this position token.")]
+    PositionNotHeld,
+    #[msg("Voting requires the voter's trader account while a minimum stake age is set.")]
+    VoterStakeMissing,
+    #[msg("The voter's stake has not been held long enough to vote.")]
+    StakeTooNew,
+    #[msg("The minimum stake age to vote cannot be negative.")]
+    InvalidMinStakeAge,
+    #[msg("Venue type must be spot (0) or perp (1) and cannot be combined with a market.")]
+    InvalidVenueType,
+    #[msg("This stake would push total staked HFRT past the global cap.")]
+    GlobalStakeCapExceeded,
+    #[msg("A snapshot account is not the PDA for this snapshot id and trader.")]
+    SnapshotMismatch,
+    #[msg("Unstake exceeds the principal plus vested compounded rewards.")]
+    CompoundedRewardsVesting,
+    #[msg("Compound cliff and vesting duration cannot be negative.")]
+    InvalidCompoundVesting,
+    #[msg("Only an executed proposal can be closed.")]
+    ProposalNotExecuted,
+    #[msg("The governance treasury is unset or the treasury account does not match it.")]
+    TreasuryMissing,
+    #[msg("Reported priority spend exceeds the per-window cap.")]
+    PrioritySpendCapExceeded,
+    #[msg("Priority rebate must be at most 10,000 basis points.")]
+    InvalidPriorityRebateBps,
+    #[msg("This proposal kind is unknown or disabled by governance.")]
+    ProposalKindNotAllowed,
+    #[msg("Loyalty point redemption is disabled.")]
+    PointsRedemptionDisabled,
+    #[msg("Not enough loyalty points.")]
+    InsufficientPoints,
+    #[msg("Unstake payout exceeds the amount unstaked or the vault balance.")]
+    InvalidPenaltyComputation,
+    #[msg("Denominators must be non-zero.")]
+    InvalidDenominator,
+    #[msg("A rescaled rebate rate does not fit the rate range.")]
+    RebateRateOutOfRange,
+    #[msg("Voter has no staked HFRT to vote with.")]
+    NoVotingPower,
+    #[msg("Stake-weighted voting requires a vote record.")]
+    VoteRecordMissing,
+    #[msg("Voting on this proposal has closed.")]
+    VotingClosed,
+    #[msg("Voting period must not be negative.")]
+    InvalidVotingPeriod,
+    #[msg("Multiplier decay window must not be negative.")]
+    InvalidMultiplierDecayWindow,
+    #[msg("Staking vault does not match the global state.")]
+    WrongVault,
+    #[msg("The HFRT staking vault has already been migrated.")]
+    VaultAlreadyMigrated,
+    #[msg("Vault migration did not move the full balance.")]
+    VaultMigrationIncomplete,
+    #[msg("Mint would exceed the per-epoch inflation cap.")]
+    EpochInflationCapExceeded,
+    #[msg("Epoch duration must be positive while the inflation cap is set.")]
+    InvalidEpochDuration,
+    #[msg("Free unstake period must be positive while an allowance is set.")]
+    InvalidFreeUnstakePeriod,
+    #[msg("Registry page is not the page for the next trader index.")]
+    WrongRegistryPage,
+    #[msg("Trader is already in the registry.")]
+    TraderAlreadyRegistered,
+    #[msg("Rebate claims and compounds are paused.")]
+    RebatesPaused,
+    #[msg("Wash decay window must not be negative.")]
+    InvalidWashDecayWindow,
+    #[msg("Claim receipt bump is missing.")]
+    ClaimReceiptMissing,
+    #[msg("Proposal is younger than the minimum proposal age.")]
+    ProposalTooNew,
+    #[msg("Minimum proposal age must not be negative.")]
+    InvalidMinProposalAge,
+    #[msg("A configured denominator is zero.")]
+    ZeroDenominator,
+    #[msg("Low-activity window must not be negative and the boost at most 10,000 bps.")]
+    InvalidLowActivityBoost,
+    #[msg("Trader is already active in the maximum number of markets.")]
+    MarketCapReached,
+    #[msg("Governance account is not the canonical governance pinned in the global state.")]
+    WrongGovernance,
+    #[msg("Proposal has already been executed.")]
+    ProposalAlreadyExecuted,
+    #[msg("Voting on this proposal is still open.")]
+    VotingStillOpen,
+}
+
+#[event]
+pub struct TradeRecorded {
+    pub owner: Pubkey,
+    pub trade_amount: u64,
+    pub rolling_volume: u64,
+    pub current_multiplier: u8,
+    pub new_max_tier: Option<u8>,
+    pub volume_clamped: bool,
+}
+
+#[event]
+pub struct RebateClaimed {
+    pub owner: Pubkey,
+    pub rebate_amount: u64,
+    pub volatility_mode: bool,
+    pub veteran_bonus_applied: bool,
+    pub low_activity_boost_applied: bool,
+}
+
+#[event]
+pub struct ClaimEligibility {
+    pub owner: Pubkey,
+    pub eligible: bool,
+    pub reason_code: u8,
+}
+
+#[event]
+pub struct VolatilityModeChanged {
+    pub enabled: bool,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct VolumeForceReset {
+    pub owner: Pubkey,
+    pub old: u64,
+    pub new: u64,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct StakeSlashed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct SlashProposed {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub executable_at: i64,
+}
+
+#[event]
+pub struct RewardTokenRebateClaimed {
+    pub owner: Pubkey,
+    pub rebate_amount: u64,
+    pub payout_amount: u64,
+    pub reward_mint: Pubkey,
+}
+
+#[event]
+pub struct TokensStaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub staked_amount: u64,
+    pub total_stakers: u64,
+}
+
+#[event]
+pub struct TokensUnstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub staked_amount: u64,
+    pub total_stakers: u64,
+    pub new_stake_start_time: i64, // Zero after a full unstake; otherwise the (possibly reset) lock start.
+    pub fully_unstaked: bool,
+}
+
+#[event]
+pub struct UnstakeSimulation {
+    pub amount: u64,
+    pub penalty: u64,
+    pub net: u64,
+    pub penalty_tier: u8,
 }
 
-#[derive(Accounts)]
-pub struct CreateDAOProposal<'info> {
-    #[account(
-        init,
-        payer = proposer,
-        space = 8 + DAOProposal::LEN,
-    )]
-    pub dao_proposal: Account<'info, DAOProposal>,
-    #[account(mut)]
-    pub proposer: Signer<'info>,
-    pub system_program: Program<'info, System>,
+#[event]
+pub struct TraderOwnershipTransferred {
+    pub previous_owner: Pubkey,
+    pub new_owner: Pubkey,
 }
 
-#[derive(Accounts)]
-pub struct VoteDAOProposal<'info> {
-    #[account(mut)]
-    pub dao_proposal: Account<'info, DAOProposal>,
-    pub voter: Signer<'info>,
+#[event]
+pub struct SupplyCapChanged {
+    pub old_cap: u64,
+    pub new_cap: u64,
 }
 
-#[derive(Accounts)]
-pub struct ExecuteDAOProposal<'info> {
-    #[account(mut)]
-    pub dao_proposal: Account<'info, DAOProposal>,
-    #[account(mut)]
-    pub global_state: Account<'info, GlobalState>,
-    pub authority: Signer<'info>,
+#[event]
+pub struct UnstakeRequested {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub unlock_at: i64,
 }
 
-impl<'info> ClaimRebate<'info> {
-    /// Prepares the context for minting tokens to the trader.
-    fn into_mint_to_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
-        let cpi_accounts = MintTo {
-            mint: self.hfrt_mint.to_account_info().clone(),
-            to: self.trader_token_account.to_account_info().clone(),
-            authority: self.mint_authority.to_account_info().clone(),
-        };
-        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
-    }
+#[event]
+pub struct KeeperRewarded {
+    pub keeper: Pubkey,
+    pub amount: u64,
 }
 
-impl<'info> StakeTokens<'info> {
-    /// Prepares the context for transferring tokens from the trader to the staking vault.
-    fn into_transfer_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.trader_token_account.to_account_info().clone(),
-            to: self.staking_vault.to_account_info().clone(),
-            authority: self.owner.to_account_info().clone(),
-        };
-        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
-    }
+#[event]
+pub struct MarketRebateClaimed {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub rebate_amount: u64,
+    pub veteran_bonus_applied: bool,
 }
 
-impl<'info> UnstakeTokens<'info> {
-    /// Prepares the context for transferring tokens from the staking vault back to the trader.
-    fn into_transfer_from_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, Transfer<'info>> {
-        let cpi_accounts = Transfer {
-            from: self.staking_vault.to_account_info().clone(),
-            to: self.trader_token_account.to_account_info().clone(),
-            authority: self.staking_vault.to_account_info().clone(),
-        };
-        CpiContext::new_with_signer(
-            self.token_program.to_account_info().clone(),
-            cpi_accounts,
-            STAKING_VAULT_SIGNER,
-        )
-    }
+#[event]
+pub struct PositionStaked {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub staked_amount: u64,
 }
 
-impl<'info> AutoCompound<'info> {
-    /// Prepares the context for minting tokens directly to the staking vault.
-    fn into_mint_to_vault_context(&self) -> CpiContext<'_, '_, '_, 'info, MintTo<'info>> {
-        let cpi_accounts = MintTo {
-            mint: self.hfrt_mint.to_account_info().clone(),
-            to: self.staking_vault.to_account_info().clone(),
-            authority: self.mint_authority.to_account_info().clone(),
-        };
-        CpiContext::new(self.token_program.to_account_info().clone(), cpi_accounts)
-    }
+#[event]
+pub struct PositionUnstaked {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+    pub staked_amount: u64,
 }
 
-#[account]
-pub struct GlobalState {
-    pub authority: Pubkey,
+#[event]
+pub struct WashTradeFlagged {
+    pub owner: Pubkey,
+    pub trade_amount: u64,
+    pub flagged_trade_count: u64,
+}
+
+#[event]
+pub struct MintAuthorityTransferred {
     pub hfrt_mint: Pubkey,
-    pub fee_discount: u8,
-    pub bump: u8,
+    pub new_authority: Pubkey,
 }
-impl GlobalState {
-    /// Space: Pubkey (32) + Pubkey (32) + u8 (1) + u8 (1)
-    pub const LEN: usize = 32 + 32 + 1 + 1;
+
+#[event]
+pub struct TradeThrottled {
+    pub owner: Pubkey,
+    pub next_eligible_trade_time: i64,
 }
 
-#[account]
-pub struct Governance {
-    pub authority: Pubkey,
-    pub rebate_rate: u8,      // For example: 10 means a 1% rebate.
-    pub max_fee_discount: u8, // Maximum fee discount allowed.
+#[event]
+pub struct StakeStartCorrected {
+    pub owner: Pubkey,
+    pub old: i64,
+    pub new: i64,
+    pub by: Pubkey,
 }
-impl Governance {
-    /// Space: Pubkey (32) + u8 (1) + u8 (1)
-    pub const LEN: usize = 32 + 1 + 1;
+
+#[event]
+pub struct VaultStats {
+    pub vault_balance: u64,
+    pub total_staked: u64,
+    pub drift: i64,
+    pub total_stakers: u64,
 }
 
-#[account]
-pub struct Trader {
+#[event]
+pub struct FeeDiscountScheduled {
+    pub new_discount: u8,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct ProgramPaused {
+    pub reason: u8,
+    pub at: i64,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct ProgramUnpaused {
+    pub at: i64,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct RebateSplitClaimed {
+    pub owner: Pubkey,
+    pub wallet_amount: u64,
+    pub staked_amount: u64,
+    pub stake_bps: u16,
+    pub veteran_bonus_applied: bool,
+}
+
+#[event]
+pub struct TradeBatchRecorded {
     pub owner: Pubkey,
+    pub trade_count: u8,
+    pub flagged_count: u8,
+    pub total_amount: u64,
     pub rolling_volume: u64,
-    pub last_update: i64,
+    pub current_multiplier: u8,
+    pub volume_clamped: bool,
+}
+
+#[event]
+pub struct MarketTradesRecorded {
+    pub owner: Pubkey,
+    pub market: Pubkey,
+    pub fill_count: u8,
+    pub total_amount: u64,
+    pub rolling_volume: u64,
+    pub volume_clamped: bool,
+}
+
+#[event]
+pub struct ProtocolSummary {
+    pub total_volume: u64,
+    pub total_rebates_minted: u64,
+    pub total_staked: u64,
+    pub total_stakers: u64,
+    pub fee_discount: u8,
+    pub rebate_rate: u8,
+    pub paused: bool,
+}
+
+#[event]
+pub struct ReferralAccrued {
+    pub referrer: Pubkey,
+    pub trader: Pubkey,
+    pub referral_amount: u64,
+    pub pending_referral: u64,
+}
+
+#[event]
+pub struct ReferralWithdrawn {
+    pub referrer: Pubkey,
+    pub amount: u64,
+    pub total_withdrawn: u64,
+}
+
+#[event]
+pub struct ForceUnstaked {
+    pub owner: Pubkey,
+    pub amount: u64,
+    pub destination: Pubkey,
     pub staked_amount: u64,
-    pub stake_start_time: i64, // Unix timestamp for when staking began.
+    pub by: Pubkey,
 }
-impl Trader {
-    /// Space: Pubkey (32) + u64 (8) + i64 (8) + u64 (8) + i64 (8)
-    pub const LEN: usize = 32 + 8 + 8 + 8 + 8;
+
+#[event]
+pub struct EarlyVoterRewarded {
+    pub voter: Pubkey,
+    pub proposal: Pubkey,
+    pub vote_index: u64,
+    pub reward: u64,
 }
 
-#[account]
-pub struct DAOProposal {
+#[event]
+pub struct PositionTokenized {
+    pub owner: Pubkey,
+    pub position_mint: Pubkey,
+    pub stake_mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PositionRedeemed {
+    pub holder: Pubkey,
+    pub position_mint: Pubkey,
+    pub amount: u64,
+    pub penalty: u64,
+}
+
+#[event]
+pub struct AuthorityRenounced {
+    pub previous_authority: Pubkey,
+    pub at: i64,
+}
+
+#[event]
+pub struct BulkSnapshotTaken {
+    pub snapshot_id: u64,
+    pub recorded: u8,
+    pub skipped: u8,
+}
+
+#[event]
+pub struct DAOProposalExecuted {
     pub proposal_id: u64,
-    pub proposer: Pubkey,
-    pub new_fee_discount: u8,
     pub votes_for: u64,
     pub votes_against: u64,
+    pub tie: bool, // Executed on an exact tie under governance.ties_pass.
 }
-impl DAOProposal {
-    pub const LEN: usize = 8 + 32 + 1 + 8 + 8;
+
+#[event]
+pub struct DAOProposalClosed {
+    pub proposal_id: u64,
+    pub rent_destination: Pubkey,
 }
 
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Arithmetic overflow occurred.")]
-    Overflow,
-    #[msg("Insufficient staked tokens for unstaking.")]
-    InsufficientStake,
-    #[msg("Invalid rebate rate.")]
-    InvalidRebateRate,
-    #[msg("Potential wash trading detected.")]
-    WashTrade,
-    #[msg("Trades are occurring too frequently.")]
-    FrequentTrades,
-    #[msg("DAO proposal rejected due to insufficient votes.")]
-    ProposalRejected,
+#[event]
+pub struct PrioritySpendRecorded {
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub pending_priority_spend: u64,
 }
 
 #[event]
-pub struct TradeRecorded {
+pub struct PointsRedeemed {
     pub owner: Pubkey,
-    pub trade_amount: u64,
-    pub rolling_volume: u64,
+    pub points: u64,
+    pub hfrt_amount: u64,
+    pub remaining_points: u64,
 }
 
 #[event]
-pub struct RebateClaimed {
+pub struct RebateDenominatorChanged {
+    pub old_denominator: u64,
+    pub new_denominator: u64,
+    pub old_rate: u8,
+    pub new_rate: u8,
+}
+
+#[event]
+pub struct GrowthProjection {
+    pub periods: u32,
+    pub projected_balance: u64,
+}
+
+#[event]
+pub struct VoteChanged {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub vote_for: bool,
+    pub weight: u64,
+}
+
+#[event]
+pub struct VaultMigrated {
+    pub old_vault: Pubkey,
+    pub new_vault: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct StakeVaultInitialized {
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct LegacyVaultDrained {
+    pub legacy_vault: Pubkey,
+    pub hfrt_vault: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct ComputeUsed {
+    pub instruction_id: u8,
+    pub units: u64,
+}
+
+#[event]
+pub struct AutoCompoundBatch {
+    pub relayer: Pubkey,
+    pub compounded_count: u8,
+    pub skipped_count: u8,
+    pub total_amount: u64,
+}
+
+#[event]
+pub struct TradeRejectedPaused {
     pub owner: Pubkey,
-    pub rebate_amount: u64,
+}
+
+#[event]
+pub struct TraderRegistered {
+    pub trader: Pubkey,
+    pub index: u64,
+}
+
+#[event]
+pub struct RebatePauseChanged {
+    pub rebate_paused: bool,
+    pub by: Pubkey,
+}
+
+#[event]
+pub struct GovernanceParamsUpdated {
+    pub governance: Pubkey,
+    pub params: GovernanceParams, // The fields that were set; `None` fields were left unchanged.
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unstake_payout_valid_bounds_the_payout_by_amount_and_vault() {
+        assert!(unstake_payout_valid(100, 90, 1_000));
+        assert!(unstake_payout_valid(100, 100, 100));
+        assert!(unstake_payout_valid(0, 0, 0));
+        // A negative penalty would pay out more than was unstaked.
+        assert!(!unstake_payout_valid(100, 101, 1_000));
+        // The vault cannot cover the payout.
+        assert!(!unstake_payout_valid(100, 90, 89));
+    }
+
+    #[test]
+    fn safe_elapsed_clamps_or_rejects_a_clock_going_backwards() {
+        assert_eq!(safe_elapsed(100, 100, true).unwrap(), 0);
+        assert_eq!(safe_elapsed(100, 90, false).unwrap(), 0);
+        assert_eq!(safe_elapsed(100, 90, true).unwrap_err(), ErrorCode::ClockSkew.into());
+    }
+
+    #[test]
+    fn trade_checks_treat_a_skewed_clock_as_no_time_elapsed() {
+        // `safe_elapsed` turns now <= then into zero elapsed seconds.
+        let elapsed = safe_elapsed(100, 90, false).unwrap();
+        assert!(detect_frequent_trades(elapsed));
+        assert!(is_wash_trade(elapsed, WASH_TRADE_THRESHOLD + 1, false));
+        assert!(is_wash_trade(elapsed, WASH_TRADE_THRESHOLD, true));
+        assert!(!is_wash_trade(elapsed, WASH_TRADE_THRESHOLD, false));
+    }
+
+    #[test]
+    fn unstake_penalty_is_the_highest_tier_with_no_time_elapsed() {
+        assert_eq!(calculate_dynamic_unstake_penalty(0, 1_000), 100);
+        assert_eq!(calculate_dynamic_unstake_penalty(14 * 24 * 3600, 1_000), 20);
+        assert_eq!(calculate_dynamic_unstake_penalty(0, u64::MAX), u64::MAX / 10);
+    }
+
+    /// A zeroed governance: every flag off.
+    fn governance() -> Governance {
+        Governance::deserialize(&mut &[0u8; Governance::INIT_SPACE][..]).unwrap()
+    }
+
+    /// A zeroed trader: nothing staked or compounded.
+    fn trader() -> Trader {
+        Trader::deserialize(&mut &[0u8; Trader::INIT_SPACE][..]).unwrap()
+    }
+
+    /// A zeroed global state: no caps, nothing staked.
+    fn global_state() -> GlobalState {
+        GlobalState::deserialize(&mut &[0u8; GlobalState::INIT_SPACE][..]).unwrap()
+    }
+
+    #[test]
+    fn stake_duration_with_a_future_start_follows_skewed_stake_min_penalty() {
+        let mut governance = governance();
+        assert_eq!(stake_duration(100, 100, &governance).unwrap(), 0);
+        assert_eq!(stake_duration(100, 90, &governance).unwrap(), 0);
+        assert_eq!(calculate_dynamic_unstake_penalty(stake_duration(100, 90, &governance).unwrap(), 1_000), 100);
+
+        governance.skewed_stake_min_penalty = true;
+        assert_eq!(stake_duration(100, 90, &governance).unwrap(), i64::MAX);
+        assert_eq!(calculate_dynamic_unstake_penalty(stake_duration(100, 90, &governance).unwrap(), 1_000), 20);
+
+        governance.reject_negative_elapsed = true;
+        assert_eq!(stake_duration(100, 90, &governance).unwrap_err(), ErrorCode::ClockSkew.into());
+    }
+
+    #[test]
+    fn linear_rate_interpolates_between_the_range_endpoints() {
+        let mut governance = governance();
+        governance.linear_min_rate = 4;
+        governance.linear_max_rate = 12;
+        governance.linear_volume_min = 1_000_000;
+        governance.linear_volume_max = 9_000_000;
+        assert_eq!(interpolate_rebate_rate(&governance, 0), 4);
+        assert_eq!(interpolate_rebate_rate(&governance, 1_000_000), 4);
+        assert_eq!(interpolate_rebate_rate(&governance, 5_000_000), 8);
+        assert_eq!(interpolate_rebate_rate(&governance, 8_999_999), 11);
+        assert_eq!(interpolate_rebate_rate(&governance, 9_000_000), 12);
+        assert_eq!(interpolate_rebate_rate(&governance, u64::MAX), 12);
+    }
+
+    #[test]
+    fn overflows_report_the_quantity_that_overflowed() {
+        assert_eq!(accumulate_volume(u64::MAX, 0, 1).unwrap_err(), ErrorCode::VolumeOverflow.into());
+        assert_eq!(accumulate_volume(u64::MAX, 24 * 3600, 1).unwrap(), 1);
+
+        assert_eq!(
+            calculate_rebate(u64::MAX, (0, 0), u8::MAX, 5, &governance()).unwrap_err(),
+            ErrorCode::RebateOverflow.into()
+        );
+
+        let mut global_state = global_state();
+        global_state.total_staked = u64::MAX;
+        assert_eq!(track_stake_totals(&mut global_state, 0, 1).unwrap_err(), ErrorCode::StakeOverflow.into());
+        global_state.total_staked = 0;
+        assert_eq!(track_stake_totals(&mut global_state, 1, 0).unwrap_err(), ErrorCode::StakeOverflow.into());
+    }
+
+    #[test]
+    fn fresh_compounded_rewards_keep_the_full_penalty_on_a_matured_principal() {
+        let now = 100 * 24 * 3600;
+        let mut trader = trader();
+        trader.staked_amount = 1_000;
+        trader.compounded_amount = 200;
+        trader.stake_start_time = now - 30 * 24 * 3600;
+        trader.compounded_since = now;
+        let governance = governance();
+
+        // Compounded rewards are drawn first and pay 10%; the matured principal pays 2%
+        assert_eq!(blended_unstake_penalty(&trader, &governance, now, 200).unwrap(), 20);
+        assert_eq!(blended_unstake_penalty(&trader, &governance, now, 1_000).unwrap(), 20 + 16);
+
+        // Once the compounded rewards mature too, the whole stake pays the lowest tier
+        trader.compounded_since = trader.stake_start_time;
+        assert_eq!(blended_unstake_penalty(&trader, &governance, now, 1_000).unwrap(), 20);
+    }
+
+    #[test]
+    fn tier_boundaries_land_on_the_raw_amounts_for_the_mint_decimals() {
+        let six = scaled_tier_thresholds(6).unwrap();
+        assert_eq!(six, [10_000_000, 50_000_000, 100_000_000]);
+        let nine = scaled_tier_thresholds(9).unwrap();
+        assert_eq!(nine, [10_000_000_000, 50_000_000_000, 100_000_000_000]);
+
+        // Ten whole tokens reach the first tier on either mint
+        assert_eq!(calculate_rebate_multiplier(10_000_000, &six, true), 2);
+        assert_eq!(calculate_rebate_multiplier(10_000_000, &nine, true), 1);
+        assert_eq!(calculate_rebate_multiplier(10_000_000_000 - 1, &nine, true), 1);
+        assert_eq!(calculate_rebate_multiplier(10_000_000_000, &nine, true), 2);
+        assert_eq!(calculate_rebate_multiplier(100_000_000_000, &nine, true), MAX_REBATE_MULTIPLIER);
+
+        assert_eq!(scaled_tier_thresholds(19).unwrap_err(), ErrorCode::Overflow.into());
+    }
+
+    #[test]
+    fn a_stake_started_at_the_unix_epoch_keeps_its_lock() {
+        // A start time of zero is a real stake, so adding to it blends rather than restarting the clock
+        assert_eq!(weighted_stake_start_time(100, 0, 100, 10).unwrap(), 5);
+        assert_eq!(weighted_stake_start_time(0, 0, 100, 10).unwrap(), 10);
+
+        let mut trader = trader();
+        trader.staked_amount = 1_000;
+        trader.stake_start_time = 0;
+        trader.is_staking = true;
+        let governance = governance();
+        assert_eq!(blended_unstake_penalty(&trader, &governance, 3600, 1_000).unwrap(), 100);
+        assert_eq!(blended_unstake_penalty(&trader, &governance, 7 * 24 * 3600, 1_000).unwrap(), 50);
+        assert_eq!(blended_unstake_penalty(&trader, &governance, 14 * 24 * 3600, 1_000).unwrap(), 20);
+    }
 }
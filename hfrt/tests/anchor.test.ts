@@ -1,45 +1,358 @@
 // No imports needed: web3, anchor, pg and more are globally available
 
 describe("HFRT Tests", () => {
-  it("initialize", async () => {
-    // Derive the global state PDA using the seed "global-state"
-    const [globalStatePda] = await web3.PublicKey.findProgramAddress(
-      [Buffer.from("global-state")],
+  // Derive the global state PDA using the seed "global-state"; every test shares it
+  const [globalStatePda] = web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("global-state")],
+    pg.program.programId
+  );
+
+  // Funds a fresh keypair from the wallet so it can pay for its own accounts
+  const fundedKeypair = async () => {
+    const keypair = new web3.Keypair();
+    await pg.program.provider.sendAndConfirm(
+      new web3.Transaction().add(
+        web3.SystemProgram.transfer({
+          fromPubkey: pg.wallet.publicKey,
+          toPubkey: keypair.publicKey,
+          lamports: web3.LAMPORTS_PER_SOL / 10,
+        })
+      )
+    );
+    return keypair;
+  };
+
+  // A fresh owner with its own trader account, so the trader has no history
+  const createOwnerWithTrader = async () => {
+    const owner = await fundedKeypair();
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), owner.publicKey.toBuffer()],
       pg.program.programId
     );
+    await pg.program.methods
+      .initializeTrader()
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+        traderRegistry: null,
+        registryPage: null,
+      })
+      .signers([owner])
+      .rpc();
+    return { owner, traderPda };
+  };
+
+  // A governance account controlled by the wallet; governance is a plain keypair account
+  const createGovernance = async (rebateRate = 10, maxFeeDiscount = 20) => {
+    const governance = new web3.Keypair();
+    await pg.program.methods
+      .initializeGovernance(rebateRate, maxFeeDiscount)
+      .accounts({
+        governance: governance.publicKey,
+        authority: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([governance])
+      .rpc();
+    return governance;
+  };
+
+  // A fresh governance pinned as the canonical one, so a test's settings don't leak from earlier tests
+  const pinFreshGovernance = async (rebateRate = 10, maxFeeDiscount = 20) => {
+    const governance = await createGovernance(rebateRate, maxFeeDiscount);
+    await pg.program.methods
+      .setGovernance()
+      .accounts({ globalState: globalStatePda, governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    return governance;
+  };
+
+  // SPL Token Program ID (manually set since TOKEN_PROGRAM_ID isn't available)
+  const TOKEN_PROGRAM_ID = new web3.PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
 
-    // SPL Token Program ID (manually set since TOKEN_PROGRAM_ID isn't available)
-    const TOKEN_PROGRAM_ID = new web3.PublicKey("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA");
+  // Derive the mint authority PDA and its bump using the seed "mint-authority"
+  const [mintAuthorityPda, mintAuthorityBump] = web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("mint-authority")],
+    pg.program.programId
+  );
 
-    // Generate a new keypair for HFRT mint
-    const hfrtMint = new web3.Keypair();
+  // The HFRT mint, created by the "initialize" test with the mint authority PDA as its authority
+  let hfrtMint: web3.PublicKey;
 
-    // Create the HFRT mint account
-    const txHashMint = await pg.program.provider.sendAndConfirm(
+  // SPL token instructions are built by hand for the same reason
+  const tokenInstruction = (keys: web3.AccountMeta[], data: Buffer) =>
+    new web3.TransactionInstruction({ programId: TOKEN_PROGRAM_ID, keys, data });
+
+  // Creates and initializes a mint (InitializeMint2) with no freeze authority
+  const createMint = async (mintAuthority: web3.PublicKey, decimals = 6) => {
+    const mint = new web3.Keypair();
+    await pg.program.provider.sendAndConfirm(
       new web3.Transaction().add(
         web3.SystemProgram.createAccount({
           fromPubkey: pg.wallet.publicKey,
-          newAccountPubkey: hfrtMint.publicKey,
+          newAccountPubkey: mint.publicKey,
           space: 82, // Standard space for SPL token mints
           lamports: await pg.connection.getMinimumBalanceForRentExemption(82),
           programId: TOKEN_PROGRAM_ID,
-        })
+        }),
+        tokenInstruction(
+          [{ pubkey: mint.publicKey, isSigner: false, isWritable: true }],
+          Buffer.concat([Buffer.from([20, decimals]), mintAuthority.toBuffer(), Buffer.from([0])])
+        )
+      ),
+      [mint]
+    );
+    return mint.publicKey;
+  };
+
+  // Creates and initializes a token account (InitializeAccount3) for `mint` owned by `owner`
+  const createTokenAccount = async (mint: web3.PublicKey, owner: web3.PublicKey) => {
+    const account = new web3.Keypair();
+    await pg.program.provider.sendAndConfirm(
+      new web3.Transaction().add(
+        web3.SystemProgram.createAccount({
+          fromPubkey: pg.wallet.publicKey,
+          newAccountPubkey: account.publicKey,
+          space: 165, // Standard space for SPL token accounts
+          lamports: await pg.connection.getMinimumBalanceForRentExemption(165),
+          programId: TOKEN_PROGRAM_ID,
+        }),
+        tokenInstruction(
+          [
+            { pubkey: account.publicKey, isSigner: false, isWritable: true },
+            { pubkey: mint, isSigner: false, isWritable: false },
+          ],
+          Buffer.concat([Buffer.from([18]), owner.toBuffer()])
+        )
+      ),
+      [account]
+    );
+    return account.publicKey;
+  };
+
+  // Mints `amount` of a wallet-controlled test mint (MintTo) into `to`
+  const mintTestTokens = async (mint: web3.PublicKey, to: web3.PublicKey, amount: number) => {
+    await pg.program.provider.sendAndConfirm(
+      new web3.Transaction().add(
+        tokenInstruction(
+          [
+            { pubkey: mint, isSigner: false, isWritable: true },
+            { pubkey: to, isSigner: false, isWritable: true },
+            { pubkey: pg.wallet.publicKey, isSigner: true, isWritable: false },
+          ],
+          Buffer.concat([Buffer.from([7]), new anchor.BN(amount).toArrayLike(Buffer, "le", 8)])
+        )
+      )
+    );
+  };
+
+  // Transfers `amount` between two token accounts of the same mint, signed by the source's owner
+  const transferTokens = async (from: web3.PublicKey, to: web3.PublicKey, owner: web3.Keypair, amount: number) => {
+    await pg.program.provider.sendAndConfirm(
+      new web3.Transaction().add(
+        tokenInstruction(
+          [
+            { pubkey: from, isSigner: false, isWritable: true },
+            { pubkey: to, isSigner: false, isWritable: true },
+            { pubkey: owner.publicKey, isSigner: true, isWritable: false },
+          ],
+          Buffer.concat([Buffer.from([3]), new anchor.BN(amount).toArrayLike(Buffer, "le", 8)])
+        )
       ),
-      [hfrtMint]
+      [owner]
     );
+  };
+
+  // Reads a token account's balance in base units
+  const tokenBalance = async (account: web3.PublicKey) =>
+    Number((await pg.connection.getTokenAccountBalance(account)).value.amount);
+
+  // Records an aggregate trade of `volume`, reported by the trader's owner
+  const recordVolume = (owner: web3.Keypair, traderPda: web3.PublicKey, governance: web3.Keypair, volume: number) =>
+    pg.program.methods
+      .recordTrade(new anchor.BN(volume), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+  // Claims the trader's rebate against `governance` into `tokenAccount`, without a referrer or receipt
+  const claimRebate = (
+    owner: web3.Keypair,
+    traderPda: web3.PublicKey,
+    governance: web3.Keypair,
+    tokenAccount: web3.PublicKey
+  ) =>
+    pg.program.methods
+      .claimRebate()
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        hfrtMint,
+        traderTokenAccount: tokenAccount,
+        mintAuthority: mintAuthorityPda,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        referrerState: null,
+        claimReceipt: null,
+        systemProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+  // Earns HFRT the only way it is minted to traders: records `volume` against the canonical `governance` and
+  // claims the rebate into a fresh HFRT token account owned by `owner`, which is returned
+  const claimHfrt = async (
+    owner: web3.Keypair,
+    traderPda: web3.PublicKey,
+    governance: web3.Keypair,
+    volume = 1_000_000_000
+  ) => {
+    const tokenAccount = await createTokenAccount(hfrtMint, owner.publicKey);
+    await recordVolume(owner, traderPda, governance, volume);
+    await claimRebate(owner, traderPda, governance, tokenAccount);
+    return tokenAccount;
+  };
+
+  // The staking vault PDA for a mint (seeded by "staking-vault" and the mint), which owns itself
+  const stakeVaultPda = (mint: web3.PublicKey) =>
+    web3.PublicKey.findProgramAddressSync([Buffer.from("staking-vault"), mint.toBuffer()], pg.program.programId)[0];
+
+  // Creates the staking vault for `mint` at its PDA (authority-only)
+  const createStakeVault = (mint: web3.PublicKey) =>
+    pg.program.methods
+      .initializeStakeVault()
+      .accounts({
+        globalState: globalStatePda,
+        authority: pg.wallet.publicKey,
+        stakeMint: mint,
+        vaultAuthority: stakeVaultPda(mint),
+        stakingVault: stakeVaultPda(mint),
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+        rent: web3.SYSVAR_RENT_PUBKEY,
+      })
+      .rpc();
+
+  // Stakes `amount` HFRT from `from` into the current HFRT vault
+  const stakeHfrt = async (owner: web3.Keypair, traderPda: web3.PublicKey, from: web3.PublicKey, amount: number) => {
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    await pg.program.methods
+      .stakeTokens(new anchor.BN(amount))
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        stakeMint: hfrtMint,
+        traderTokenAccount: from,
+        vaultAuthority: stakeVaultPda(hfrtMint),
+        stakingVault: hfrtVault,
+        stakePosition: null,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+  };
+
+  // Builds an HFRT unstake of `amount` into `to`, checked against `governance`
+  const unstakeHfrt = async (
+    owner: web3.Keypair,
+    traderPda: web3.PublicKey,
+    to: web3.PublicKey,
+    governance: web3.PublicKey,
+    amount: number
+  ) => {
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    return pg.program.methods
+      .unstakeTokens(new anchor.BN(amount))
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        governance,
+        stakeMint: hfrtMint,
+        traderTokenAccount: to,
+        vaultAuthority: stakeVaultPda(hfrtMint),
+        stakingVault: hfrtVault,
+        stakePosition: null,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner]);
+  };
+
+  // Auto-compounds the trader's rolling volume into the current HFRT vault as stake
+  const autoCompound = async (owner: web3.Keypair, traderPda: web3.PublicKey, governance: web3.Keypair) => {
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    await pg.program.methods
+      .autoCompound()
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        hfrtMint,
+        stakingVault: hfrtVault,
+        mintAuthority: mintAuthorityPda,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+  };
+
+  // The relayer allowlist PDA (seeded by "relayer-allowlist")
+  const [relayerAllowlistPda] = web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("relayer-allowlist")],
+    pg.program.programId
+  );
+
+  // Adds `relayer` to the allowlist, creating the allowlist first if no earlier test has
+  const approveRelayer = async (relayer: web3.PublicKey) => {
+    if ((await pg.connection.getAccountInfo(relayerAllowlistPda)) === null) {
+      await pg.program.methods
+        .initializeRelayerAllowlist()
+        .accounts({
+          globalState: globalStatePda,
+          relayerAllowlist: relayerAllowlistPda,
+          authority: pg.wallet.publicKey,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .rpc();
+    }
+    await pg.program.methods
+      .approveRelayer(relayer)
+      .accounts({ globalState: globalStatePda, relayerAllowlist: relayerAllowlistPda, authority: pg.wallet.publicKey })
+      .rpc();
+  };
 
-    console.log(`HFRT Mint Created - TX: ${txHashMint}`);
+  it("initialize", async () => {
+    // Create the HFRT mint with the mint authority PDA as its authority, as initialize requires
+    hfrtMint = await createMint(mintAuthorityPda);
+
+    const mint = await pg.connection.getParsedAccountInfo(hfrtMint);
+    assert.equal(mint.value.data.parsed.info.mintAuthority, mintAuthorityPda.toBase58());
 
     // Define the fee discount value (example: 10)
     const feeDiscount = 10;
 
     // Initialize HFRT global state
     const txHash = await pg.program.methods
-      .initialize(feeDiscount)
+      .initialize(feeDiscount, mintAuthorityBump)
       .accounts({
         globalState: globalStatePda,
         authority: pg.wallet.publicKey,
-        hfrtMint: hfrtMint.publicKey,
+        hfrtMint,
+        mintAuthority: mintAuthorityPda,
         systemProgram: web3.SystemProgram.programId,
         rent: web3.SYSVAR_RENT_PUBKEY,
         tokenProgram: TOKEN_PROGRAM_ID,
@@ -56,7 +369,4596 @@ describe("HFRT Tests", () => {
     // Verify global state matches expected values
     assert.equal(globalState.feeDiscount, feeDiscount);
     assert(globalState.authority.equals(pg.wallet.publicKey));
-    assert(globalState.hfrtMint.equals(hfrtMint.publicKey));
+    assert(globalState.hfrtMint.equals(hfrtMint));
+    assert.equal(globalState.mintAuthorityBump, mintAuthorityBump);
   });
-});
 
+  it("initialize_trader", async () => {
+    // Derive the trader PDA using the seed "trader" and the owner
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+
+    const txHash = await pg.program.methods
+      .initializeTrader()
+      .accounts({
+        trader: traderPda,
+        owner: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+        traderRegistry: null,
+        registryPage: null,
+      })
+      .rpc();
+
+    console.log(`Trader Initialized - TX: ${txHash}`);
+
+    // Fetch the on-chain trader account
+    const trader = await pg.program.account.trader.fetch(traderPda);
+
+    // Verify the trader starts empty and records its creation time
+    assert(trader.owner.equals(pg.wallet.publicKey));
+    assert.equal(trader.rollingVolume.toNumber(), 0);
+    assert(trader.createdAt.toNumber() > 0);
+  });
+
+  it("rejects a rebate rate above the effective ceiling", async () => {
+    // Allow any rate under max_fee_discount so only the ceiling check applies
+    const governance = await createGovernance(10, 255);
+
+    // 255 / 1000 at the top 5x multiplier is over 100% of volume
+    try {
+      await pg.program.methods
+        .updateRebateRate(255)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("expected InvalidRebateRate");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidRebateRate");
+    }
+
+    const gov = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(gov.rebateRate, 10);
+  });
+
+  it("routes market trades to the market volume bucket", async () => {
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+
+    // Any pubkey can identify a venue
+    const market = new web3.Keypair().publicKey;
+    const [marketVolumePda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("market-vol"), pg.wallet.publicKey.toBuffer(), market.toBuffer()],
+      pg.program.programId
+    );
+
+    await pg.program.methods
+      .initializeMarketVolume(market)
+      .accounts({
+        marketVolume: marketVolumePda,
+        owner: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const governance = await pinFreshGovernance();
+
+    const tradeAmount = new anchor.BN(5000);
+    await pg.program.methods
+      .recordTrade(tradeAmount, market, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: marketVolumePda,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .rpc();
+
+    // The bucket receives the volume and the aggregate is untouched
+    const marketVolume = await pg.program.account.marketVolume.fetch(marketVolumePda);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(marketVolume.rollingVolume.toNumber(), 5000);
+    assert.equal(trader.rollingVolume.toNumber(), 0);
+  });
+
+  it("allows a burst of fast trades up to the frequent-trade allowance", async () => {
+    // A fresh owner so this trader has no trade history
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    const allowance = 2;
+    await pg.program.methods
+      .setFrequentTradeAllowance(allowance)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    // Distinct amounts keep each transaction unique within the same blockhash
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    // The first trade is not fast; the next `allowance` trades are
+    for (let i = 0; i <= allowance; i++) {
+      await recordTrade(1000 + i);
+    }
+    try {
+      await recordTrade(2000);
+      assert.fail("expected FrequentTrades");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "FrequentTrades");
+    }
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.fastTradeCount, allowance);
+  });
+
+  it("requires the super-majority pass threshold to execute a proposal", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setPassThreshold(7000)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const proposal = new web3.Keypair();
+    const newFeeDiscount = 12;
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(1), 0, newFeeDiscount)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+
+    // 3 for and 2 against: a simple majority, but only 60% of votes cast
+    for (const voteFor of [true, true, true, false, false]) {
+      const voter = new web3.Keypair();
+      await pg.program.methods
+        .voteDaoProposal(voteFor)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader: null,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: null,
+          systemProgram: null,
+        })
+        .signers([voter])
+        .rpc();
+    }
+
+    const execute = () =>
+      pg.program.methods
+        .executeDaoProposal()
+        .accounts({
+          daoProposal: proposal.publicKey,
+          globalState: globalStatePda,
+          governance: governance.publicKey,
+          authority: pg.wallet.publicKey,
+        })
+        .rpc();
+
+    try {
+      await execute();
+      assert.fail("expected ProposalRejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalRejected");
+    }
+
+    // At a 60% threshold the same tally passes
+    await pg.program.methods
+      .setPassThreshold(6000)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await execute();
+
+    const globalState = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(globalState.feeDiscount, newFeeDiscount);
+
+    // An executed proposal cannot be replayed
+    try {
+      await execute();
+      assert.fail("expected ProposalAlreadyExecuted");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalAlreadyExecuted");
+    }
+  });
+
+  it("ensure_initialized is a no-op on matching parameters and rejects conflicts", async () => {
+    // Re-run against the global state created by the earlier initialize
+    const existing = await pg.program.account.globalState.fetch(globalStatePda);
+    const ensure = (feeDiscount: number) =>
+      pg.program.methods
+        .ensureInitialized(feeDiscount, mintAuthorityBump)
+        .accounts({
+          globalState: globalStatePda,
+          authority: pg.wallet.publicKey,
+          hfrtMint: existing.hfrtMint,
+          mintAuthority: mintAuthorityPda,
+          systemProgram: web3.SystemProgram.programId,
+          rent: web3.SYSVAR_RENT_PUBKEY,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    await ensure(existing.feeDiscount);
+
+    try {
+      await ensure(existing.feeDiscount + 1);
+      assert.fail("expected InitializationConflict");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InitializationConflict");
+    }
+
+    const globalState = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(globalState.feeDiscount, existing.feeDiscount);
+  });
+
+  it("keeps max_tier_reached after the rolling volume is reset", async () => {
+    // A fresh owner so the first trade is not subject to wash or frequency checks
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    // 100M volume reaches the top 5x tier
+    await pg.program.methods
+      .recordTrade(new anchor.BN(100_000_000), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+    await pg.program.methods
+      .forceResetVolume(new anchor.BN(0))
+      .accounts({
+        globalState: globalStatePda,
+        trader: traderPda,
+        authority: pg.wallet.publicKey,
+      })
+      .rpc();
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 0);
+    assert.equal(trader.maxTierReached, 5);
+  });
+
+  it("rejects wash trades in strict mode and flags them in soft mode", async () => {
+    // A fresh owner so the first trade is not subject to wash or frequency checks
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(1000);
+
+    // A large trade within 10 seconds of the last one is a wash trade
+    try {
+      await recordTrade(2_000_000);
+      assert.fail("expected WashTrade");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WashTrade");
+    }
+
+    await pg.program.methods
+      .setSoftWashHandling(true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await recordTrade(2_000_000);
+
+    // The flagged trade is recorded but earns no rebate volume
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.flaggedTradeCount.toNumber(), 1);
+    assert.equal(trader.rollingVolume.toNumber(), 1000);
+  });
+
+  it("emits the retry time when a trade is throttled", async () => {
+    // A fresh owner so the first trade is not subject to wash or frequency checks
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(1000);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+
+    try {
+      await recordTrade(1001);
+      assert.fail("expected FrequentTrades");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "FrequentTrades");
+      // The event is still readable from the failed transaction's logs
+      const prefix = "Program data: ";
+      const event = err.logs
+        .filter((line) => line.startsWith(prefix))
+        .map((line) => pg.program.coder.events.decode(line.slice(prefix.length)))
+        .find((e) => e && e.name.toLowerCase() === "tradethrottled");
+      assert.equal(
+        event.data.nextEligibleTradeTime.toNumber(),
+        trader.lastUpdate.toNumber() + 5
+      );
+    }
+  });
+
+  it("clamps the rolling volume to max_rolling_volume", async () => {
+    // A fresh owner so the first trade is not subject to wash or frequency checks
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setMaxRollingVolume(new anchor.BN(1500))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    // Let the second trade through the frequency check
+    await pg.program.methods
+      .setFrequentTradeAllowance(1)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(1000);
+    await recordTrade(1001);
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 1500);
+  });
+
+  it("applies the configured tier boundary semantics at an exact threshold", async () => {
+    // 10M is exactly the 2x threshold
+    for (const [inclusive, expectedTier] of [[true, 2], [false, 1]] as [boolean, number][]) {
+      // A fresh owner so the trade is not subject to wash or frequency checks
+      const { owner, traderPda } = await createOwnerWithTrader();
+
+      const governance = await pinFreshGovernance();
+      await pg.program.methods
+        .setInclusiveTierBoundaries(inclusive)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+
+      await pg.program.methods
+        .recordTrade(new anchor.BN(10_000_000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      assert.equal(trader.maxTierReached, expectedTier);
+    }
+  });
+
+  it("correct_stake_start_time repairs a zeroed start time and rejects the future", async () => {
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+
+    const correct = (newTime: number) =>
+      pg.program.methods
+        .correctStakeStartTime(new anchor.BN(newTime))
+        .accounts({
+          globalState: globalStatePda,
+          trader: traderPda,
+          authority: pg.wallet.publicKey,
+        })
+        .rpc();
+
+    // The trader has never staked, so its start time is zero
+    const now = Math.floor(Date.now() / 1000);
+    const pastTime = now - 3600;
+    await correct(pastTime);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakeStartTime.toNumber(), pastTime);
+
+    try {
+      await correct(now + 86400);
+      assert.fail("expected InvalidStakeStartTime");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidStakeStartTime");
+    }
+  });
+
+  it("allows one-sided fast flow but blocks maker/taker self-matching", async () => {
+    // A fresh owner so the first trade is not subject to wash or frequency checks
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    // Let the burst through the frequency check so only wash detection applies
+    await pg.program.methods
+      .setFrequentTradeAllowance(5)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = (amount: number, isMaker: boolean) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, isMaker, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    // Large maker fills in quick succession are fine
+    await recordTrade(2_000_000, true);
+    await recordTrade(2_000_001, true);
+
+    // A similar-sized taker fill right after a maker fill is self-matching
+    try {
+      await recordTrade(2_000_002, false);
+      assert.fail("expected WashTrade");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WashTrade");
+    }
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 4_000_001);
+  });
+
+  it("keeps the old fee discount until a scheduled change is due", async () => {
+    const before = await pg.program.account.globalState.fetch(globalStatePda);
+
+    const effectiveAt = Math.floor(Date.now() / 1000) + 86400;
+    await pg.program.methods
+      .scheduleFeeDiscount(before.feeDiscount + 1, new anchor.BN(effectiveAt))
+      .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+      .rpc();
+
+    try {
+      await pg.program.methods
+        .applyScheduledDiscount()
+        .accounts({ globalState: globalStatePda })
+        .rpc();
+      assert.fail("expected FeeScheduleNotDue");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "FeeScheduleNotDue");
+    }
+
+    const after = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(after.feeDiscount, before.feeDiscount);
+    assert.equal(after.scheduledFeeDiscount, before.feeDiscount + 1);
+    assert.equal(after.feeDiscountEffectiveAt.toNumber(), effectiveAt);
+  });
+
+  it("records a pause reason and clears it on unpause", async () => {
+    await pg.program.methods
+      .setPaused(true, 3)
+      .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+      .rpc();
+    const paused = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(paused.paused, true);
+    assert.equal(paused.pauseReason, 3);
+    assert(paused.pausedAt.toNumber() > 0);
+
+    await pg.program.methods
+      .setPaused(false, 0)
+      .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+      .rpc();
+    const resumed = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(resumed.paused, false);
+    assert.equal(resumed.pauseReason, 0);
+    assert.equal(resumed.pausedAt.toNumber(), 0);
+  });
+
+  it("wash-checks each batch element rather than the aggregate", async () => {
+    // A fresh owner so the batch is not subject to an earlier trade
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    const recordBatch = (amounts: number[]) =>
+      pg.program.methods
+        .recordTradeBatch(amounts.map((amount) => new anchor.BN(amount)))
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+        })
+        .signers([owner])
+        .rpc();
+
+    // The first fill is long after the last trade, but the second follows it immediately
+    try {
+      await recordBatch([2_000_000, 2_000_000]);
+      assert.fail("expected WashTrade");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WashTrade");
+    }
+
+    // Small fills are never single-trade washes, so the batch goes through
+    await recordBatch([500_000, 500_000, 500_000]);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 1_500_000);
+  });
+
+  it("records fills across three markets atomically and caps the market count", async () => {
+    // A fresh owner so the batch is not subject to an earlier trade
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    // Five buckets: three for the batch and enough to exceed MAX_BATCH_MARKETS
+    const marketVolumePdas = [];
+    for (let i = 0; i < 5; i++) {
+      const market = new web3.Keypair().publicKey;
+      const [marketVolumePda] = await web3.PublicKey.findProgramAddress(
+        [Buffer.from("market-vol"), owner.publicKey.toBuffer(), market.toBuffer()],
+        pg.program.programId
+      );
+      await pg.program.methods
+        .initializeMarketVolume(market)
+        .accounts({
+          marketVolume: marketVolumePda,
+          owner: owner.publicKey,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      marketVolumePdas.push(marketVolumePda);
+    }
+
+    const batch = (fills: { marketIndex: number; amount: number }[], buckets: web3.PublicKey[]) =>
+      pg.program.methods
+        .batchRecordTradesForMarkets(
+          fills.map((fill) => ({ marketIndex: fill.marketIndex, amount: new anchor.BN(fill.amount) }))
+        )
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+        })
+        .remainingAccounts(buckets.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+        .signers([owner])
+        .rpc();
+
+    try {
+      await batch([{ marketIndex: 0, amount: 1000 }], marketVolumePdas);
+      assert.fail("expected TooManyMarkets");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "TooManyMarkets");
+    }
+
+    await batch(
+      [
+        { marketIndex: 0, amount: 1000 },
+        { marketIndex: 1, amount: 2000 },
+        { marketIndex: 2, amount: 3000 },
+      ],
+      marketVolumePdas.slice(0, 3)
+    );
+    for (let i = 0; i < 3; i++) {
+      const bucket = await pg.program.account.marketVolume.fetch(marketVolumePdas[i]);
+      assert.equal(bucket.rollingVolume.toNumber(), (i + 1) * 1000);
+    }
+  });
+
+  it("protocol_summary matches the underlying accounts after a trade", async () => {
+    // A fresh owner so the trade is not subject to an earlier one
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    const before = await pg.program.account.globalState.fetch(globalStatePda);
+    await pg.program.methods
+      .recordTrade(new anchor.BN(5000), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+    const { events } = await pg.program.methods
+      .protocolSummary()
+      .accounts({ globalState: globalStatePda, governance: governance.publicKey })
+      .simulate();
+    const summary = events.find((e) => e.name.toLowerCase() === "protocolsummary").data;
+    const state = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(state.totalVolume.toNumber(), before.totalVolume.toNumber() + 5000);
+    assert.equal(summary.totalVolume.toNumber(), state.totalVolume.toNumber());
+    assert.equal(summary.totalRebatesMinted.toNumber(), state.totalRebatesMinted.toNumber());
+    assert.equal(summary.totalStaked.toNumber(), state.totalStaked.toNumber());
+    assert.equal(summary.totalStakers.toNumber(), state.totalStakers.toNumber());
+    assert.equal(summary.feeDiscount, state.feeDiscount);
+    assert.equal(summary.rebateRate, 10);
+    assert.equal(summary.paused, state.paused);
+
+    // The reported rebate rate is the canonical governance's, not a caller's own
+    const spoofed = await createGovernance(50, 60);
+    try {
+      await pg.program.methods
+        .protocolSummary()
+        .accounts({ globalState: globalStatePda, governance: spoofed.publicKey })
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("rate-limits rebate rate changes", async () => {
+    const governance = await createGovernance();
+    await pg.program.methods
+      .setRateChangeLimits(new anchor.BN(2), 5)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const updateRate = (rate: number) =>
+      pg.program.methods
+        .updateRebateRate(rate)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+
+    await updateRate(12);
+
+    // A second change inside the cooldown is rejected
+    try {
+      await updateRate(14);
+      assert.fail("expected RateChangeCooldown");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "RateChangeCooldown");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+
+    // After the cooldown the step size still applies
+    try {
+      await updateRate(20);
+      assert.fail("expected RateChangeTooLarge");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "RateChangeTooLarge");
+    }
+    await updateRate(14);
+
+    const gov = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(gov.rebateRate, 14);
+  });
+
+  it("sets a referrer once and rejects self-referral", async () => {
+    // The wallet acts as the referrer
+    const [referrerStatePda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("referrer"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .initializeReferrerState()
+      .accounts({
+        referrerState: referrerStatePda,
+        referrer: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // The wallet's own trader cannot name the wallet
+    const [walletTraderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+    try {
+      await pg.program.methods
+        .setReferrer()
+        .accounts({ trader: walletTraderPda, owner: pg.wallet.publicKey, referrerState: referrerStatePda })
+        .rpc();
+      assert.fail("expected InvalidReferrer");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidReferrer");
+    }
+
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const setReferrer = () =>
+      pg.program.methods
+        .setReferrer()
+        .accounts({ trader: traderPda, owner: owner.publicKey, referrerState: referrerStatePda })
+        .signers([owner])
+        .rpc();
+
+    await setReferrer();
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert(trader.referrer.equals(pg.wallet.publicKey));
+
+    try {
+      await setReferrer();
+      assert.fail("expected ReferrerAlreadySet");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ReferrerAlreadySet");
+    }
+  });
+
+  it("requires a held stake to vote once a minimum stake age is set", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setMinStakeAgeToVote(new anchor.BN(3600))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const proposal = new web3.Keypair();
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(2), 0, 12)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+
+    const { owner: voter, traderPda } = await createOwnerWithTrader();
+
+    const vote = (trader: web3.PublicKey | null) =>
+      pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: null,
+          systemProgram: null,
+        })
+        .signers([voter])
+        .rpc();
+
+    try {
+      await vote(null);
+      assert.fail("expected VoterStakeMissing");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "VoterStakeMissing");
+    }
+
+    // A trader with no stake has nothing aged enough to count
+    try {
+      await vote(traderPda);
+      assert.fail("expected StakeTooNew");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "StakeTooNew");
+    }
+
+    // Lifting the requirement lets the same voter through
+    await pg.program.methods
+      .setMinStakeAgeToVote(new anchor.BN(0))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await vote(null);
+    const state = await pg.program.account.daoProposal.fetch(proposal.publicKey);
+    assert.equal(state.votesFor.toNumber(), 1);
+  });
+
+  it("attributes volume to spot and perp buckets", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(3)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setVenueRebateRates(5, 15)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = (amount: number, market: web3.PublicKey | null, venueType: number | null) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), market, null, venueType)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(1000, null, 0);
+    await recordTrade(3000, null, 1);
+    await recordTrade(500, null, null);
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 4500);
+    assert.equal(trader.spotVolume.toNumber(), 1000);
+    assert.equal(trader.perpVolume.toNumber(), 3000);
+
+    for (const [market, venueType] of [[null, 2], [web3.Keypair.generate().publicKey, 0]] as const) {
+      try {
+        await recordTrade(100, market, venueType);
+        assert.fail("expected InvalidVenueType");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "InvalidVenueType");
+      }
+    }
+  });
+
+  it("sets and clears the global stake cap", async () => {
+    const setCap = (cap: number) =>
+      pg.program.methods
+        .setMaxTotalStaked(new anchor.BN(cap))
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+
+    await setCap(1_000_000);
+    let state = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(state.maxTotalStaked.toNumber(), 1_000_000);
+
+    // Only the authority may change it
+    const stranger = new web3.Keypair();
+    try {
+      await pg.program.methods
+        .setMaxTotalStaked(new anchor.BN(0))
+        .accounts({ globalState: globalStatePda, authority: stranger.publicKey })
+        .signers([stranger])
+        .rpc();
+      assert.fail("expected has_one to reject a non-authority");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ConstraintHasOne");
+    }
+
+    await setCap(0);
+    state = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(state.maxTotalStaked.toNumber(), 0);
+  });
+
+  it("bulk-snapshots several traders and skips ones already captured", async () => {
+    const snapshotId = new anchor.BN(Date.now());
+    const pairs = [];
+    for (let i = 0; i < 3; i++) {
+      const { owner, traderPda } = await createOwnerWithTrader();
+      const [snapshotPda] = await web3.PublicKey.findProgramAddress(
+        [Buffer.from("snapshot"), snapshotId.toArrayLike(Buffer, "le", 8), traderPda.toBuffer()],
+        pg.program.programId
+      );
+      pairs.push({ owner, traderPda, snapshotPda });
+    }
+
+    const bulkSnapshot = (entries: typeof pairs) =>
+      pg.program.methods
+        .bulkSnapshot(snapshotId)
+        .accounts({
+          globalState: globalStatePda,
+          authority: pg.wallet.publicKey,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .remainingAccounts(
+          entries.flatMap(({ traderPda, snapshotPda }) => [
+            { pubkey: traderPda, isWritable: false, isSigner: false },
+            { pubkey: snapshotPda, isWritable: true, isSigner: false },
+          ])
+        )
+        .rpc();
+
+    // Lamports sent to a snapshot PDA in advance must not block its creation
+    await pg.program.provider.sendAndConfirm(
+      new web3.Transaction().add(
+        web3.SystemProgram.transfer({
+          fromPubkey: pg.wallet.publicKey,
+          toPubkey: pairs[2].snapshotPda,
+          lamports: 1_000_000,
+        })
+      )
+    );
+
+    await bulkSnapshot(pairs.slice(0, 2));
+    // The first two are skipped the second time around
+    await bulkSnapshot(pairs);
+
+    for (const { owner, traderPda, snapshotPda } of pairs) {
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      const snapshot = await pg.program.account.snapshot.fetch(snapshotPda);
+      assert(snapshot.trader.equals(traderPda));
+      assert(snapshot.owner.equals(owner.publicKey));
+      assert.equal(snapshot.snapshotId.toString(), snapshotId.toString());
+      assert.equal(snapshot.rollingVolume.toNumber(), trader.rollingVolume.toNumber());
+      assert.equal(snapshot.stakedAmount.toNumber(), trader.stakedAmount.toNumber());
+    }
+  });
+
+  it("sends closed proposal rent to the proposer or the treasury", async () => {
+    const governance = await pinFreshGovernance();
+    const treasury = new web3.Keypair();
+    const { feeDiscount } = await pg.program.account.globalState.fetch(globalStatePda);
+
+    // Creates, passes and executes a proposal that leaves the fee discount unchanged
+    const executedProposal = async () => {
+      const proposal = new web3.Keypair();
+      await pg.program.methods
+        .createDaoProposal(new anchor.BN(3), 0, feeDiscount)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          proposer: pg.wallet.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([proposal])
+        .rpc();
+      const voter = new web3.Keypair();
+      await pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader: null,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: null,
+          systemProgram: null,
+        })
+        .signers([voter])
+        .rpc();
+      await pg.program.methods
+        .executeDaoProposal()
+        .accounts({
+          daoProposal: proposal.publicKey,
+          globalState: globalStatePda,
+          governance: governance.publicKey,
+          authority: pg.wallet.publicKey,
+        })
+        .rpc();
+      return proposal.publicKey;
+    };
+    const close = (proposal: web3.PublicKey, withTreasury: boolean) =>
+      pg.program.methods
+        .closeDaoProposal()
+        .accounts({
+          daoProposal: proposal,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          proposer: pg.wallet.publicKey,
+          treasury: withTreasury ? treasury.publicKey : null,
+          closer: pg.wallet.publicKey,
+        })
+        .rpc();
+
+    // Default: the proposer gets the rent back
+    const first = await executedProposal();
+    const rent = await pg.connection.getBalance(first);
+    await close(first, false);
+    assert.equal(await pg.connection.getAccountInfo(first), null);
+    assert.equal(await pg.connection.getBalance(treasury.publicKey), 0);
+
+    await pg.program.methods
+      .setProposalRentDestination(treasury.publicKey, true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const second = await executedProposal();
+    try {
+      await close(second, false);
+      assert.fail("expected TreasuryMissing");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "TreasuryMissing");
+    }
+    await close(second, true);
+    assert.equal(await pg.connection.getBalance(treasury.publicKey), rent);
+
+    // A self-made governance cannot redirect the rent to its own treasury
+    const third = await executedProposal();
+    const spoofed = await createGovernance();
+    const attacker = new web3.Keypair();
+    await pg.program.methods
+      .setProposalRentDestination(attacker.publicKey, true)
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await pg.program.methods
+        .closeDaoProposal()
+        .accounts({
+          daoProposal: third,
+          governance: spoofed.publicKey,
+          globalState: globalStatePda,
+          proposer: pg.wallet.publicKey,
+          treasury: attacker.publicKey,
+          closer: pg.wallet.publicKey,
+        })
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("accumulates priority spend up to the per-window cap", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setPriorityRebate(5000, new anchor.BN(10_000), new anchor.BN(1_000_000))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    // A trader cannot self-report spend; only the trusted reporter attests to it.
+    try {
+      await pg.program.methods
+        .recordPrioritySpend(new anchor.BN(4000))
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("expected UnauthorizedReporter");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "UnauthorizedReporter");
+    }
+
+    const setTrustedReporter = (reporter: web3.PublicKey) =>
+      pg.program.methods
+        .setTrustedReporter(reporter)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+    await setTrustedReporter(pg.wallet.publicKey);
+    const recordSpend = (lamports: number) =>
+      pg.program.methods
+        .recordPrioritySpend(new anchor.BN(lamports))
+        .accounts({
+          trader: traderPda,
+          reporter: pg.wallet.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+        })
+        .rpc();
+
+    try {
+      await recordSpend(4000);
+      await recordSpend(6000);
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      assert.equal(trader.pendingPrioritySpend.toNumber(), 10_000);
+      assert.equal(trader.priorityWindowSpend.toNumber(), 10_000);
+
+      try {
+        await recordSpend(1);
+        assert.fail("expected PrioritySpendCapExceeded");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "PrioritySpendCapExceeded");
+      }
+    } finally {
+      await setTrustedReporter(web3.PublicKey.default);
+    }
+  });
+
+  it("wash-checks a trade exactly at the threshold only when inclusive", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(3)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = () =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(1_000_000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    // Exclusive (the default): back-to-back trades at the threshold pass
+    await recordTrade();
+    await recordTrade();
+
+    await pg.program.methods
+      .setWashThresholdInclusive(true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await recordTrade();
+      assert.fail("expected WashTrade");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WashTrade");
+    }
+  });
+
+  it("only creates proposals of allowed kinds", async () => {
+    const governance = await pinFreshGovernance();
+
+    const createProposal = (kind: number, governanceKey = governance.publicKey) => {
+      const proposal = new web3.Keypair();
+      return pg.program.methods
+        .createDaoProposal(new anchor.BN(4), kind, 12)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          proposer: pg.wallet.publicKey,
+          governance: governanceKey,
+          globalState: globalStatePda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([proposal])
+        .rpc();
+    };
+
+    // Every kind is allowed by default
+    await createProposal(0);
+
+    // An unknown kind is never allowed
+    try {
+      await createProposal(5);
+      assert.fail("expected ProposalKindNotAllowed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalKindNotAllowed");
+    }
+
+    await pg.program.methods
+      .setAllowedProposalKinds(0)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await createProposal(0);
+      assert.fail("expected ProposalKindNotAllowed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalKindNotAllowed");
+    }
+
+    // A self-made governance allowing every kind cannot get around the restriction
+    const spoofed = await createGovernance();
+    await pg.program.methods
+      .setAllowedProposalKinds(0xffffffff)
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await createProposal(0, spoofed.publicKey);
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("builds a voting streak over sequential proposals and resets it on a skip", async () => {
+    const governance = await pinFreshGovernance();
+
+    const { owner: voter, traderPda } = await createOwnerWithTrader();
+
+    const voteOn = async (proposalId: number) => {
+      const proposal = new web3.Keypair();
+      await pg.program.methods
+        .createDaoProposal(new anchor.BN(proposalId), 0, 12)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          proposer: pg.wallet.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([proposal])
+        .rpc();
+      await pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader: traderPda,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: null,
+          systemProgram: null,
+        })
+        .signers([voter])
+        .rpc();
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      return trader.consecutiveProposalsVoted;
+    };
+
+    assert.equal(await voteOn(1), 1);
+    assert.equal(await voteOn(2), 2);
+    assert.equal(await voteOn(3), 3);
+    // Proposal 4 is skipped
+    assert.equal(await voteOn(5), 1);
+  });
+
+  it("accrues loyalty points from volume without the 24-hour reset", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(3)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    // 5 points per 1,000,000 of volume
+    await pg.program.methods
+      .setLoyaltyPoints(new anchor.BN(5), new anchor.BN(0))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(1_000_000);
+    await recordTrade(600_000);
+    await recordTrade(400_000);
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 2_000_000);
+    // Each trade accrues on its own, rounding down: 5 + 3 + 2
+    assert.equal(trader.loyaltyPoints.toNumber(), 10);
+  });
+
+  it("Rescales rebate rates when the denominator changes", async () => {
+    const governance = await createGovernance();
+
+    const volume = 2_000_000;
+    const before = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(before.rebateDenominator.toNumber(), 1000);
+    const rebateBefore = Math.floor((volume * before.rebateRate) / before.rebateDenominator.toNumber());
+
+    await pg.program.methods
+      .setRebateDenominatorAndMigrate(new anchor.BN(10_000), true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const after = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(after.rebateDenominator.toNumber(), 10_000);
+    assert.equal(after.rebateRate, 100);
+    assert.equal(after.maxFeeDiscount, 200);
+    const rebateAfter = Math.floor((volume * after.rebateRate) / after.rebateDenominator.toNumber());
+    assert.equal(rebateAfter, rebateBefore);
+
+    // Opting out keeps the raw rate, repricing rebates
+    await pg.program.methods
+      .setRebateDenominatorAndMigrate(new anchor.BN(20_000), false)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const optedOut = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(optedOut.rebateDenominator.toNumber(), 20_000);
+    assert.equal(optedOut.rebateRate, 100);
+  });
+
+  it("Projects compound growth linearly in the number of periods", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .recordTrade(new anchor.BN(2_000_000), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+    const project = async (periods: number) => {
+      const { events } = await pg.program.methods
+        .estimateCompoundGrowth(periods)
+        .accounts({
+          trader: traderPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+        })
+        .simulate();
+      const projection = events.find((e) => e.name === "GrowthProjection");
+      assert.equal(projection.data.periods, periods);
+      return projection.data.projectedBalance.toNumber();
+    };
+
+    assert.equal(await project(0), 0);
+    const onePeriod = await project(1);
+    assert.isAbove(onePeriod, 0);
+    assert.equal(await project(3), 3 * onePeriod);
+  });
+
+  it("Rejects or records zero-stake votes under stake-weighted voting", async () => {
+    const { owner: voter, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    const setVoteWeighting = (allowZeroWeightVotes: boolean) =>
+      pg.program.methods
+        .setVoteWeighting(true, allowZeroWeightVotes)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+
+    const proposal = new web3.Keypair();
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(1), 0, 12)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+    // Votes cast with a trader are recorded against the trader account
+    const [voteRecordPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote-record"), proposal.publicKey.toBuffer(), traderPda.toBuffer()],
+      pg.program.programId
+    );
+    const vote = () =>
+      pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader: traderPda,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: voteRecordPda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+
+    await setVoteWeighting(false);
+    try {
+      await vote();
+      assert.fail("A zero-stake vote should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "NoVotingPower");
+    }
+
+    await setVoteWeighting(true);
+    await vote();
+    const daoProposal = await pg.program.account.daoProposal.fetch(proposal.publicKey);
+    assert.equal(daoProposal.votesFor.toNumber(), 0);
+    assert.equal(daoProposal.votesAgainst.toNumber(), 0);
+    assert.equal(daoProposal.voterCount.toNumber(), 1);
+    const voteRecord = await pg.program.account.voteRecord.fetch(voteRecordPda);
+    assert.equal(voteRecord.weight.toNumber(), 0);
+    assert.isTrue(voteRecord.voteFor);
+  });
+
+  it("Changes a recorded vote until voting closes", async () => {
+    const voter = await fundedKeypair();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setVotingPeriod(new anchor.BN(5))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const proposal = new web3.Keypair();
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(1), 0, 12)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+    const [voteRecordPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote-record"), proposal.publicKey.toBuffer(), voter.publicKey.toBuffer()],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .voteDaoProposal(true)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        voter: voter.publicKey,
+        governance: governance.publicKey,
+        trader: null,
+        globalState: globalStatePda,
+        voterState: null,
+        hfrtMint: null,
+        voterTokenAccount: null,
+        mintAuthority: null,
+        tokenProgram: null,
+        voteRecord: voteRecordPda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([voter])
+      .rpc();
+
+    const changeVote = (newChoice: boolean) =>
+      pg.program.methods
+        .changeVote(newChoice)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          trader: null,
+          voteRecord: voteRecordPda,
+        })
+        .signers([voter])
+        .rpc();
+
+    await changeVote(false);
+    const daoProposal = await pg.program.account.daoProposal.fetch(proposal.publicKey);
+    assert.equal(daoProposal.votesFor.toNumber(), 0);
+    assert.equal(daoProposal.votesAgainst.toNumber(), 1);
+    const voteRecord = await pg.program.account.voteRecord.fetch(voteRecordPda);
+    assert.isFalse(voteRecord.voteFor);
+
+    // The tally can still move, so the proposal cannot execute yet
+    const execute = () =>
+      pg.program.methods
+        .executeDaoProposal()
+        .accounts({
+          daoProposal: proposal.publicKey,
+          globalState: globalStatePda,
+          governance: governance.publicKey,
+          authority: pg.wallet.publicKey,
+        })
+        .rpc();
+    try {
+      await execute();
+      assert.fail("execution before the deadline should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "VotingStillOpen");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 6000));
+
+    try {
+      await changeVote(true);
+      assert.fail("A vote change after the deadline should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "VotingClosed");
+    }
+
+    // After the deadline the final tally decides
+    try {
+      await execute();
+      assert.fail("expected ProposalRejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalRejected");
+    }
+  });
+
+  it("Tracks when rolling volume last grew for multiplier decay", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(3)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setMultiplierDecayWindow(new anchor.BN(3600))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    // Cap volume so a later trade leaves it unchanged
+    await pg.program.methods
+      .setMaxRollingVolume(new anchor.BN(500_000))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(600_000);
+    const grown = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(grown.rollingVolume.toNumber(), 500_000);
+    assert.isAbove(grown.lastVolumeIncrease.toNumber(), 0);
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+
+    // A trade that leaves volume at the cap does not count as growth
+    await recordTrade(400_000);
+    const stagnant = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(stagnant.rollingVolume.toNumber(), 500_000);
+    assert.equal(stagnant.lastVolumeIncrease.toNumber(), grown.lastVolumeIncrease.toNumber());
+    assert.isAbove(stagnant.lastUpdate.toNumber(), grown.lastVolumeIncrease.toNumber());
+  });
+
+  it("Emits compute usage from record_trade while profiling is enabled", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setProfilingEnabled(true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const { events } = await pg.program.methods
+      .recordTrade(new anchor.BN(500_000), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .simulate();
+    const computeUsed = events.find((e) => e.name === "ComputeUsed");
+    assert.isDefined(computeUsed);
+    assert.equal(computeUsed.data.instructionId, 0); // PROFILE_RECORD_TRADE
+    const units = computeUsed.data.units.toNumber();
+    assert.isAbove(units, 0);
+    assert.isBelow(units, 200_000);
+  });
+
+  it("Blocks claims below the minimum rebate to mint", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .recordTrade(new anchor.BN(500_000), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+
+    // With no stake, a one-period projection is exactly the pending rebate
+    const { events: projection } = await pg.program.methods
+      .estimateCompoundGrowth(1)
+      .accounts({ trader: traderPda, governance: governance.publicKey, globalState: globalStatePda })
+      .simulate();
+    const rebate = projection.find((e) => e.name === "GrowthProjection").data.projectedBalance.toNumber();
+    assert.isAbove(rebate, 0);
+
+    const reasonWithMinimum = async (minimum: number) => {
+      await pg.program.methods
+        .setMinRebateToMint(new anchor.BN(minimum))
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      const { events } = await pg.program.methods
+        .canClaim()
+        .accounts({ trader: traderPda, governance: governance.publicKey, globalState: globalStatePda })
+        .simulate();
+      return events.find((e) => e.name === "ClaimEligibility").data.reasonCode;
+    };
+
+    assert.equal(await reasonWithMinimum(rebate), 0); // CLAIM_ELIGIBLE
+    assert.equal(await reasonWithMinimum(rebate + 1), 5); // CLAIM_BLOCKED_BELOW_MINIMUM
+  });
+
+  it("rejects trades while paused and emits TradeRejectedPaused", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    await pg.program.methods
+      .setPaused(true, 1)
+      .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await pg.program.methods
+        .recordTrade(new anchor.BN(1000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("expected ProgramPaused");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProgramPaused");
+      const prefix = "Program data: ";
+      const event = err.logs
+        .filter((line) => line.startsWith(prefix))
+        .map((line) => pg.program.coder.events.decode(line.slice(prefix.length)))
+        .find((e) => e && e.name.toLowerCase() === "traderejectedpaused");
+      assert(event.data.owner.equals(owner.publicKey));
+    } finally {
+      await pg.program.methods
+        .setPaused(false, 0)
+        .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+        .rpc();
+    }
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 0);
+  });
+
+  it("sets the free unstake allowance and rejects a zero period", async () => {
+    const governance = await createGovernance();
+
+    await pg.program.methods
+      .setFreeUnstakeAllowance(new anchor.BN(500), new anchor.BN(86400))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const gov = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(gov.freeUnstakeAllowance.toNumber(), 500);
+    assert.equal(gov.freeUnstakePeriod.toNumber(), 86400);
+
+    try {
+      await pg.program.methods
+        .setFreeUnstakeAllowance(new anchor.BN(500), new anchor.BN(0))
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("expected InvalidFreeUnstakePeriod");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidFreeUnstakePeriod");
+    }
+  });
+
+  it("lists initialized traders on the trader registry pages", async () => {
+    const [registryPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader-registry")],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .initializeTraderRegistry()
+      .accounts({
+        globalState: globalStatePda,
+        traderRegistry: registryPda,
+        authority: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const pageBytes = Buffer.alloc(4);
+    pageBytes.writeUInt32LE(0);
+    const [pagePda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader-registry-page"), pageBytes],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .openTraderRegistryPage(0)
+      .accounts({
+        traderRegistry: registryPda,
+        registryPage: pagePda,
+        payer: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const traderPdas = [];
+    for (let i = 0; i < 3; i++) {
+      const owner = await fundedKeypair();
+      const [traderPda] = await web3.PublicKey.findProgramAddress(
+        [Buffer.from("trader"), owner.publicKey.toBuffer()],
+        pg.program.programId
+      );
+      await pg.program.methods
+        .initializeTrader()
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          traderRegistry: registryPda,
+          registryPage: pagePda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      traderPdas.push(traderPda);
+    }
+
+    const registry = await pg.program.account.traderRegistry.fetch(registryPda);
+    assert.equal(registry.traderCount.toNumber(), 3);
+    const page = await pg.program.account.traderRegistryPage.fetch(pagePda);
+    traderPdas.forEach((traderPda, i) => assert(page.traders[i].equals(traderPda)));
+    assert(page.traders[3].equals(web3.PublicKey.default));
+    const trader = await pg.program.account.trader.fetch(traderPdas[0]);
+    assert.equal(trader.registered, true);
+  });
+
+  it("sets the minimum stake for the claim multiplier", async () => {
+    const governance = await createGovernance();
+    const before = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(before.minStakeForMultiplier.toNumber(), 0);
+
+    await pg.program.methods
+      .setMinStakeForMultiplier(new anchor.BN(1000))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const after = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(after.minStakeForMultiplier.toNumber(), 1000);
+  });
+
+  it("blocks rebates but keeps trading while rebate_paused is set", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    await pg.program.methods
+      .setRebatePaused(true)
+      .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await pg.program.methods
+        .recordTrade(new anchor.BN(1000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      assert.equal(trader.rollingVolume.toNumber(), 1000);
+
+      const { events } = await pg.program.methods
+        .canClaim()
+        .accounts({ trader: traderPda, governance: governance.publicKey, globalState: globalStatePda })
+        .simulate();
+      const eligibility = events.find((e) => e.name === "ClaimEligibility");
+      assert.equal(eligibility.data.reasonCode, 6); // CLAIM_BLOCKED_REBATES_PAUSED
+    } finally {
+      await pg.program.methods
+        .setRebatePaused(false)
+        .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+        .rpc();
+    }
+  });
+
+  it("decays wash flags after the wash decay window", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setSoftWashHandling(true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setWashDecayWindow(new anchor.BN(5))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const recordTrade = (amount: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    await recordTrade(1000);
+    // A large trade within 10 seconds of the last one is flagged under soft handling
+    await recordTrade(2_000_000);
+    const flagged = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(flagged.flaggedTradeCount.toNumber(), 1);
+
+    await new Promise((resolve) => setTimeout(resolve, 11000));
+    await recordTrade(1000);
+    const decayed = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(decayed.flaggedTradeCount.toNumber(), 0);
+  });
+
+  it("rejects votes and execution on a proposal younger than min_proposal_age", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setMinProposalAge(new anchor.BN(5))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { feeDiscount } = await pg.program.account.globalState.fetch(globalStatePda);
+
+    const proposal = new web3.Keypair();
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(4), 0, feeDiscount)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+    const created = await pg.program.account.daoProposal.fetch(proposal.publicKey);
+    assert(created.createdAt.toNumber() > 0);
+
+    const voter = new web3.Keypair();
+    const vote = () =>
+      pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader: null,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: null,
+          systemProgram: null,
+        })
+        .signers([voter])
+        .rpc();
+    const execute = () =>
+      pg.program.methods
+        .executeDaoProposal()
+        .accounts({
+          daoProposal: proposal.publicKey,
+          globalState: globalStatePda,
+          governance: governance.publicKey,
+          authority: pg.wallet.publicKey,
+        })
+        .rpc();
+
+    for (const attempt of [vote, execute]) {
+      try {
+        await attempt();
+        assert.fail("expected ProposalTooNew");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "ProposalTooNew");
+      }
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 6000));
+    await vote();
+    await execute();
+    const executed = await pg.program.account.daoProposal.fetch(proposal.publicKey);
+    assert.equal(executed.executed, true);
+  });
+
+  it("updates only the given governance params in one call", async () => {
+    const governance = await createGovernance();
+    const before = await pg.program.account.governance.fetch(governance.publicKey);
+
+    const params = {
+      referralBps: 250,
+      reinvestCooldown: null,
+      keeperReward: new anchor.BN(7),
+      frequentTradeAllowance: null,
+      softWashHandling: true,
+      maxRollingVolume: null,
+      multiplierDecayWindow: null,
+      washDecayWindow: null,
+      minRebateToMint: null,
+      minStakeForMultiplier: null,
+      passThresholdBps: null,
+      votingPeriod: new anchor.BN(3600),
+      minProposalAge: null,
+      minStakeAgeToVote: null,
+    };
+    const { events } = await pg.program.methods
+      .updateGovernanceParams(params)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .simulate();
+    const updated = events.find((e) => e.name === "GovernanceParamsUpdated");
+    assert.equal(updated.data.params.referralBps, 250);
+    assert.equal(updated.data.params.reinvestCooldown, null);
+
+    await pg.program.methods
+      .updateGovernanceParams(params)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const after = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(after.referralBps, 250);
+    assert.equal(after.keeperReward.toNumber(), 7);
+    assert.equal(after.softWashHandling, true);
+    assert.equal(after.votingPeriod.toNumber(), 3600);
+    // Fields left as null are untouched
+    assert.equal(after.reinvestCooldown.toNumber(), before.reinvestCooldown.toNumber());
+    assert.equal(after.passThresholdBps, before.passThresholdBps);
+    assert.equal(after.maxRollingVolume.toNumber(), before.maxRollingVolume.toNumber());
+
+    try {
+      await pg.program.methods
+        .updateGovernanceParams({ ...params, referralBps: 10_001 })
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("expected InvalidReferralBps");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidReferralBps");
+    }
+  });
+
+  it("rejects a zero rebate denominator and keeps claim checks working", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const governance = await pinFreshGovernance();
+
+    try {
+      await pg.program.methods
+        .setRebateDenominatorAndMigrate(new anchor.BN(0), true)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("expected InvalidDenominator");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidDenominator");
+    }
+    const gov = await pg.program.account.governance.fetch(governance.publicKey);
+    assert.equal(gov.rebateDenominator.toNumber(), 1000);
+
+    await pg.program.methods
+      .recordTrade(new anchor.BN(1_000_000), null, null, null)
+      .accounts({
+        trader: traderPda,
+        reporter: owner.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        marketVolume: null,
+        hfrtMint: null,
+        stakingVault: null,
+        mintAuthority: null,
+        tokenProgram: null,
+      })
+      .signers([owner])
+      .rpc();
+    // The claim preconditions divide by the denominator and still evaluate cleanly
+    const { events } = await pg.program.methods
+      .canClaim()
+      .accounts({ trader: traderPda, governance: governance.publicKey, globalState: globalStatePda })
+      .simulate();
+    const eligibility = events.find((e) => e.name === "ClaimEligibility");
+    assert.equal(eligibility.data.reasonCode, 0); // CLAIM_ELIGIBLE
+  });
+
+  it("tracks windowed protocol volume for the low-activity boost", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+
+    try {
+      await pg.program.methods
+        .setLowActivityBoost(new anchor.BN(3600), new anchor.BN(0), 10_001)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("a boost above 10,000 bps should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidLowActivityBoost");
+    }
+
+    await pg.program.methods
+      .setLowActivityBoost(new anchor.BN(3600), new anchor.BN(1_000_000_000), 500)
+      .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await pg.program.methods
+        .recordTrade(new anchor.BN(1000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+      const after = await pg.program.account.globalState.fetch(globalStatePda);
+      // The first trade after enabling opens a fresh window holding just this trade
+      assert.equal(after.currentWindowVolume.toNumber(), 1000);
+      assert.equal(after.lowActivityBoostBps, 500);
+    } finally {
+      await pg.program.methods
+        .setLowActivityBoost(new anchor.BN(0), new anchor.BN(0), 0)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+    }
+  });
+
+  it("caps the number of markets a trader can be active in", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(5)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setMaxMarketsPerTrader(2)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    const markets = [0, 1, 2].map(() => new web3.Keypair().publicKey);
+    const marketVolumePdas: web3.PublicKey[] = [];
+    for (const market of markets) {
+      const [marketVolumePda] = await web3.PublicKey.findProgramAddress(
+        [Buffer.from("market-vol"), owner.publicKey.toBuffer(), market.toBuffer()],
+        pg.program.programId
+      );
+      await pg.program.methods
+        .initializeMarketVolume(market)
+        .accounts({
+          marketVolume: marketVolumePda,
+          owner: owner.publicKey,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      marketVolumePdas.push(marketVolumePda);
+    }
+
+    const recordTrade = (index: number) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(1000), markets[index], null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: marketVolumePdas[index],
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    // Activating up to the cap succeeds, and trading again in an active market does not count twice
+    await recordTrade(0);
+    await recordTrade(1);
+    await recordTrade(0);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.activeMarketCount, 2);
+
+    try {
+      await recordTrade(2);
+      assert.fail("a third market should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "MarketCapReached");
+    }
+
+    // The cap is read from the canonical governance, so a trader's own uncapped governance cannot lift it
+    const ownGovernance = new web3.Keypair();
+    await pg.program.methods
+      .initializeGovernance(10, 20)
+      .accounts({
+        governance: ownGovernance.publicKey,
+        authority: owner.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([ownGovernance, owner])
+      .rpc();
+    try {
+      await pg.program.methods
+        .recordTrade(new anchor.BN(1000), markets[2], null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: ownGovernance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: marketVolumePdas[2],
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner])
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("rejects or executes an exact vote tie per the ties-pass setting", async () => {
+    const governance = await pinFreshGovernance();
+    const { feeDiscount } = await pg.program.account.globalState.fetch(globalStatePda);
+
+    // Creates a proposal that leaves the fee discount unchanged, with one vote each way
+    const tiedProposal = async () => {
+      const proposal = new web3.Keypair();
+      await pg.program.methods
+        .createDaoProposal(new anchor.BN(4), 0, feeDiscount)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          proposer: pg.wallet.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([proposal])
+        .rpc();
+      for (const support of [true, false]) {
+        const voter = new web3.Keypair();
+        await pg.program.methods
+          .voteDaoProposal(support)
+          .accounts({
+            daoProposal: proposal.publicKey,
+            voter: voter.publicKey,
+            governance: governance.publicKey,
+            trader: null,
+            globalState: globalStatePda,
+            voterState: null,
+            hfrtMint: null,
+            voterTokenAccount: null,
+            mintAuthority: null,
+            tokenProgram: null,
+            voteRecord: null,
+            systemProgram: null,
+          })
+          .signers([voter])
+          .rpc();
+      }
+      return proposal.publicKey;
+    };
+    const execute = (proposal: web3.PublicKey) =>
+      pg.program.methods.executeDaoProposal().accounts({
+        daoProposal: proposal,
+        globalState: globalStatePda,
+        governance: governance.publicKey,
+        authority: pg.wallet.publicKey,
+      });
+
+    // By default a tie is rejected
+    const rejected = await tiedProposal();
+    try {
+      await execute(rejected).rpc();
+      assert.fail("a tied proposal should be rejected by default");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ProposalRejected");
+    }
+
+    await pg.program.methods
+      .setTiesPass(true)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const passed = await tiedProposal();
+    const { events } = await execute(passed).simulate();
+    const executed = events.find((e) => e.name === "DAOProposalExecuted");
+    assert.isTrue(executed.data.tie);
+    assert.equal(executed.data.votesFor.toNumber(), 1);
+    assert.equal(executed.data.votesAgainst.toNumber(), 1);
+    await execute(passed).rpc();
+    const proposal = await pg.program.account.daoProposal.fetch(passed);
+    assert.isTrue(proposal.executed);
+  });
+
+  it("pins the canonical governance to one controlled by the global authority", async () => {
+    // A governance created by anyone else cannot be pinned
+    const outsider = await fundedKeypair();
+    const foreign = new web3.Keypair();
+    await pg.program.methods
+      .initializeGovernance(10, 20)
+      .accounts({
+        governance: foreign.publicKey,
+        authority: outsider.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([foreign, outsider])
+      .rpc();
+    try {
+      await pg.program.methods
+        .setGovernance()
+        .accounts({ globalState: globalStatePda, governance: foreign.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("a governance with another authority should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    const governance = await pinFreshGovernance();
+    const globalState = await pg.program.account.globalState.fetch(globalStatePda);
+    assert(globalState.governance.equals(governance.publicKey));
+  });
+
+  it("rejects trades checked against a non-canonical governance", async () => {
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+
+    // A self-made governance that would lift the fast-trade throttle
+    const spoofed = await createGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    try {
+      await pg.program.methods
+        .recordTrade(new anchor.BN(1000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: pg.wallet.publicKey,
+          governance: spoofed.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("rejects a trade batch wash-checked against a non-canonical governance", async () => {
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+
+    // A self-made governance that would only flag wash trades
+    const spoofed = await createGovernance();
+    await pg.program.methods
+      .setSoftWashHandling(true)
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    try {
+      await pg.program.methods
+        .recordTradeBatch([new anchor.BN(2_000_000), new anchor.BN(2_000_000)])
+        .accounts({
+          trader: traderPda,
+          reporter: pg.wallet.publicKey,
+          governance: spoofed.publicKey,
+          globalState: globalStatePda,
+        })
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("rejects market fills checked against a governance with the wash window disabled", async () => {
+    const [traderPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("trader"), pg.wallet.publicKey.toBuffer()],
+      pg.program.programId
+    );
+    const market = new web3.Keypair().publicKey;
+    const [marketVolumePda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("market-vol"), pg.wallet.publicKey.toBuffer(), market.toBuffer()],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .initializeMarketVolume(market)
+      .accounts({
+        marketVolume: marketVolumePda,
+        owner: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // A self-made governance whose zero wash window volume turns the windowed check off
+    const spoofed = await createGovernance();
+    await pg.program.methods
+      .setWashWindow(new anchor.BN(0), new anchor.BN(0))
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    try {
+      await pg.program.methods
+        .batchRecordTradesForMarkets([{ marketIndex: 0, amount: new anchor.BN(1000) }])
+        .accounts({
+          trader: traderPda,
+          reporter: pg.wallet.publicKey,
+          governance: spoofed.publicKey,
+          globalState: globalStatePda,
+        })
+        .remainingAccounts([{ pubkey: marketVolumePda, isWritable: true, isSigner: false }])
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("rejects votes weighed against a non-canonical governance", async () => {
+    const spoofed = await createGovernance();
+    const { feeDiscount, governance } = await pg.program.account.globalState.fetch(globalStatePda);
+    const proposal = new web3.Keypair();
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(5), 0, feeDiscount)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+
+    const voter = new web3.Keypair();
+    try {
+      await pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: spoofed.publicKey,
+          trader: null,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: null,
+          systemProgram: null,
+        })
+        .signers([voter])
+        .rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("stakes each mint into its own vault", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const hfrtAccount = await claimHfrt(owner, traderPda, governance);
+    const otherMint = await createMint(pg.wallet.publicKey);
+    const otherAccount = await createTokenAccount(otherMint, owner.publicKey);
+    await mintTestTokens(otherMint, otherAccount, 5000);
+
+    // Each mint's vault is created once, by the authority, at its own PDA
+    for (const mint of [hfrtMint, otherMint]) {
+      await createStakeVault(mint);
+    }
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    assert(hfrtVault.equals(stakeVaultPda(hfrtMint)));
+
+    const [stakePositionPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("stake-position"), traderPda.toBuffer(), otherMint.toBuffer()],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .initializeStakePosition()
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        stakeMint: otherMint,
+        stakePosition: stakePositionPda,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([owner])
+      .rpc();
+    const stakeOther = (stakingVault: web3.PublicKey) =>
+      pg.program.methods
+        .stakeTokens(new anchor.BN(2000))
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          stakeMint: otherMint,
+          traderTokenAccount: otherAccount,
+          vaultAuthority: stakeVaultPda(otherMint),
+          stakingVault,
+          stakePosition: stakePositionPda,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([owner])
+        .rpc();
+
+    // The other mint cannot be parked in the HFRT vault
+    try {
+      await stakeOther(hfrtVault);
+      assert.fail("staking into another mint's vault should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongVault");
+    }
+
+    await stakeHfrt(owner, traderPda, hfrtAccount, 1000);
+    await stakeOther(stakeVaultPda(otherMint));
+
+    assert.equal(await tokenBalance(stakeVaultPda(otherMint)), 2000);
+    assert.equal(await tokenBalance(otherAccount), 3000);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), 1000);
+    const position = await pg.program.account.stakePosition.fetch(stakePositionPda);
+    assert.equal(position.amount.toNumber(), 2000);
+  });
+
+  it("unstakes penalty-free up to the free allowance, against the canonical governance only", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFreeUnstakeAllowance(new anchor.BN(300), new anchor.BN(3600))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+
+    // Within the allowance there is no penalty
+    let before = await tokenBalance(wallet);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 200)).rpc();
+    assert.equal((await tokenBalance(wallet)) - before, 200);
+
+    // 100 of the allowance is left, so the 10% early penalty applies to the other 200 only
+    before = await tokenBalance(wallet);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 300)).rpc();
+    assert.equal((await tokenBalance(wallet)) - before, 280);
+
+    // A self-made governance with an unlimited allowance cannot waive the penalty
+    const spoofed = await createGovernance();
+    await pg.program.methods
+      .setFreeUnstakeAllowance(new anchor.BN("18446744073709551615"), new anchor.BN(1))
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await (await unstakeHfrt(owner, traderPda, wallet, spoofed.publicKey, 500)).rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), 500);
+  });
+
+  it("routes stakes above the notice threshold through request and withdraw", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setUnstakeNotice(new anchor.BN(1000), new anchor.BN(3))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const [pendingUnstakePda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("pending-unstake"), traderPda.toBuffer()],
+      pg.program.programId
+    );
+    const requestUnstake = (amount: number, governanceKey = governance.publicKey) =>
+      pg.program.methods
+        .requestUnstake(new anchor.BN(amount))
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          governance: governanceKey,
+          pendingUnstake: pendingUnstakePda,
+          globalState: globalStatePda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+    const withdrawUnstake = () =>
+      pg.program.methods
+        .withdrawUnstake()
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          governance: governance.publicKey,
+          pendingUnstake: pendingUnstakePda,
+          traderTokenAccount: wallet,
+          vaultAuthority: stakeVaultPda(hfrtMint),
+          stakingVault: hfrtVault,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([owner])
+        .rpc();
+
+    // A stake within the threshold unstakes instantly and needs no notice
+    await stakeHfrt(owner, traderPda, wallet, 800);
+    try {
+      await requestUnstake(800);
+      assert.fail("expected UnstakeNoticeNotRequired");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "UnstakeNoticeNotRequired");
+    }
+    let before = await tokenBalance(wallet);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 100)).rpc();
+    assert.equal((await tokenBalance(wallet)) - before, 90);
+
+    // Above the threshold the position is gated, so even a small instant unstake is refused
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    try {
+      await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 100)).rpc();
+      assert.fail("expected UnstakeNoticeRequired");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "UnstakeNoticeRequired");
+    }
+
+    // The notice settings come from the canonical governance only
+    const spoofed = await createGovernance();
+    try {
+      await requestUnstake(800, spoofed.publicKey);
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+
+    await requestUnstake(800);
+    try {
+      await withdrawUnstake();
+      assert.fail("expected UnstakeNoticeNotElapsed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "UnstakeNoticeNotElapsed");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+    before = await tokenBalance(wallet);
+    await withdrawUnstake();
+    assert.equal((await tokenBalance(wallet)) - before, 720);
+    assert.isNull(await pg.connection.getAccountInfo(pendingUnstakePda));
+
+    // Back within the threshold, the rest unstakes instantly again
+    before = await tokenBalance(wallet);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 900)).rpc();
+    assert.equal((await tokenBalance(wallet)) - before, 810);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), 0);
+  });
+
+  it("vests compounded rewards on the canonical governance's cliff and schedule", async () => {
+    const governance = await pinFreshGovernance();
+    const setVesting = (cliff: number, vestDuration: number) =>
+      pg.program.methods
+        .setCompoundVesting(new anchor.BN(cliff), new anchor.BN(vestDuration))
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+    await setVesting(3600, 1_000_000);
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+    await autoCompound(owner, traderPda, governance);
+    const { compoundedAmount } = await pg.program.account.trader.fetch(traderPda);
+    const compounded = compoundedAmount.toNumber();
+    assert.isAbove(compounded, 1000);
+    const unstake = async (amount: number) =>
+      (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, amount)).rpc();
+
+    // Before the cliff nothing has vested, and there is no principal to fall back on
+    try {
+      await unstake(1);
+      assert.fail("expected CompoundedRewardsVesting before the cliff");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "CompoundedRewardsVesting");
+    }
+
+    // Past a one-second cliff only a sliver of the million-second schedule has vested
+    await setVesting(1, 1_000_000);
+    await new Promise((resolve) => setTimeout(resolve, 3000));
+    await unstake(1);
+    try {
+      await unstake(compounded - 1);
+      assert.fail("expected CompoundedRewardsVesting while partially vested");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "CompoundedRewardsVesting");
+    }
+
+    // Once cliff and schedule have both passed, everything left is withdrawable
+    await setVesting(1, 1);
+    await unstake(compounded - 1);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), 0);
+    assert.equal(trader.compoundedAmount.toNumber(), 0);
+  });
+
+  it("slashes small amounts immediately and large ones only after the confirmation delay", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setSlashParams(new anchor.BN(500), new anchor.BN(3))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const [pendingSlashPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("pending-slash"), traderPda.toBuffer()],
+      pg.program.programId
+    );
+    const slashStake = (amount: number, governanceKey = governance.publicKey) =>
+      pg.program.methods
+        .slashStake(new anchor.BN(amount))
+        .accounts({
+          globalState: globalStatePda,
+          governance: governanceKey,
+          trader: traderPda,
+          hfrtMint,
+          vaultAuthority: stakeVaultPda(hfrtMint),
+          stakingVault: hfrtVault,
+          authority: pg.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+    const confirmSlash = () =>
+      pg.program.methods
+        .confirmSlash()
+        .accounts({
+          globalState: globalStatePda,
+          trader: traderPda,
+          pendingSlash: pendingSlashPda,
+          hfrtMint,
+          vaultAuthority: stakeVaultPda(hfrtMint),
+          stakingVault: hfrtVault,
+          authority: pg.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .rpc();
+
+    // A self-made governance with an unlimited threshold cannot skip the confirmation step
+    const spoofed = await createGovernance();
+    await pg.program.methods
+      .setSlashParams(new anchor.BN("18446744073709551615"), new anchor.BN(0))
+      .accounts({ governance: spoofed.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    try {
+      await slashStake(900, spoofed.publicKey);
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+
+    // Up to the threshold the slash burns from the vault at once
+    const vaultBefore = await tokenBalance(hfrtVault);
+    await slashStake(300);
+    assert.equal(vaultBefore - (await tokenBalance(hfrtVault)), 300);
+    try {
+      await slashStake(600);
+      assert.fail("expected SlashRequiresConfirmation");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "SlashRequiresConfirmation");
+    }
+
+    // Above it the slash is proposed first and cannot be confirmed before the delay
+    await pg.program.methods
+      .proposeSlash(new anchor.BN(600))
+      .accounts({
+        globalState: globalStatePda,
+        governance: governance.publicKey,
+        trader: traderPda,
+        pendingSlash: pendingSlashPda,
+        authority: pg.wallet.publicKey,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .rpc();
+    try {
+      await confirmSlash();
+      assert.fail("expected SlashDelayNotElapsed");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "SlashDelayNotElapsed");
+    }
+
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+    await confirmSlash();
+    assert.isNull(await pg.connection.getAccountInfo(pendingSlashPda));
+    assert.equal(vaultBefore - (await tokenBalance(hfrtVault)), 900);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), 100);
+  });
+
+  it("lets whoever holds a transferred position token redeem the position", async () => {
+    const governance = await pinFreshGovernance();
+    const stakeMint = await createMint(pg.wallet.publicKey);
+    await createStakeVault(stakeMint);
+    const staker = await fundedKeypair();
+    const buyer = await fundedKeypair();
+    const stakerAccount = await createTokenAccount(stakeMint, staker.publicKey);
+    const buyerAccount = await createTokenAccount(stakeMint, buyer.publicKey);
+    await mintTestTokens(stakeMint, stakerAccount, 1000);
+
+    const positionMint = new web3.Keypair();
+    const [positionTokenPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("position-token"), positionMint.publicKey.toBuffer()],
+      pg.program.programId
+    );
+    const [tokenizedPositionPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("position"), positionMint.publicKey.toBuffer()],
+      pg.program.programId
+    );
+    await pg.program.methods
+      .stakeTokensAsPosition(new anchor.BN(1000))
+      .accounts({
+        owner: staker.publicKey,
+        stakeMint,
+        ownerTokenAccount: stakerAccount,
+        vaultAuthority: stakeVaultPda(stakeMint),
+        stakingVault: stakeVaultPda(stakeMint),
+        positionMint: positionMint.publicKey,
+        positionTokenAccount: positionTokenPda,
+        tokenizedPosition: tokenizedPositionPda,
+        mintAuthority: mintAuthorityPda,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([staker, positionMint])
+      .rpc();
+
+    // Hand the position token to the buyer
+    const buyerPositionAccount = await createTokenAccount(positionMint.publicKey, buyer.publicKey);
+    await transferTokens(positionTokenPda, buyerPositionAccount, staker, 1);
+    const unstakePosition = (
+      holder: web3.Keypair,
+      holderPositionAccount: web3.PublicKey,
+      holderTokenAccount: web3.PublicKey,
+      governanceKey = governance.publicKey
+    ) =>
+      pg.program.methods
+        .unstakePosition()
+        .accounts({
+          holder: holder.publicKey,
+          tokenizedPosition: tokenizedPositionPda,
+          positionMint: positionMint.publicKey,
+          holderPositionAccount,
+          stakeMint,
+          holderTokenAccount,
+          vaultAuthority: stakeVaultPda(stakeMint),
+          stakingVault: stakeVaultPda(stakeMint),
+          governance: governanceKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([holder])
+        .rpc();
+
+    // The original staker no longer holds the token, so cannot redeem it
+    try {
+      await unstakePosition(staker, positionTokenPda, stakerAccount);
+      assert.fail("expected PositionNotHeld");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "PositionNotHeld");
+    }
+
+    // Nor can the buyer price the penalty against a self-made governance
+    const spoofed = await createGovernance();
+    try {
+      await unstakePosition(buyer, buyerPositionAccount, buyerAccount, spoofed.publicKey);
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+
+    // The buyer redeems it, less the 10% early penalty on the position's own clock
+    await unstakePosition(buyer, buyerPositionAccount, buyerAccount);
+    assert.equal(await tokenBalance(buyerAccount), 900);
+    assert.equal(await tokenBalance(buyerPositionAccount), 0);
+    assert.isNull(await pg.connection.getAccountInfo(tokenizedPositionPda));
+  });
+
+  it("previews an unstake exactly as the real unstake pays it", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFreeUnstakeAllowance(new anchor.BN(100), new anchor.BN(3600))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    const simulateUnstake = (governanceKey: web3.PublicKey) =>
+      pg.program.methods
+        .simulateUnstake(new anchor.BN(400))
+        .accounts({ trader: traderPda, governance: governanceKey, globalState: globalStatePda });
+
+    const { events } = await simulateUnstake(governance.publicKey).simulate();
+    const preview = events.find((e) => e.name === "UnstakeSimulation").data;
+    // 10% early penalty on the 300 past the free allowance
+    assert.equal(preview.penalty.toNumber(), 30);
+    assert.equal(preview.net.toNumber(), 370);
+    assert.equal(preview.penaltyTier, 0);
+
+    const before = await tokenBalance(wallet);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 400)).rpc();
+    assert.equal((await tokenBalance(wallet)) - before, preview.net.toNumber());
+
+    // A preview priced against a self-made governance is refused rather than misquoted
+    const spoofed = await createGovernance();
+    try {
+      await simulateUnstake(spoofed.publicKey).rpc();
+      assert.fail("a non-canonical governance should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongGovernance");
+    }
+  });
+
+  it("counts a trader's stake once per proposal across an ownership transfer", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setVoteWeighting(true, false)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    const buyer = await fundedKeypair();
+
+    const proposal = new web3.Keypair();
+    await pg.program.methods
+      .createDaoProposal(new anchor.BN(6), 0, 12)
+      .accounts({
+        daoProposal: proposal.publicKey,
+        proposer: pg.wallet.publicKey,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        systemProgram: web3.SystemProgram.programId,
+      })
+      .signers([proposal])
+      .rpc();
+    const [voteRecordPda] = await web3.PublicKey.findProgramAddress(
+      [Buffer.from("vote-record"), proposal.publicKey.toBuffer(), traderPda.toBuffer()],
+      pg.program.programId
+    );
+    const vote = (voter: web3.Keypair) =>
+      pg.program.methods
+        .voteDaoProposal(true)
+        .accounts({
+          daoProposal: proposal.publicKey,
+          voter: voter.publicKey,
+          governance: governance.publicKey,
+          trader: traderPda,
+          globalState: globalStatePda,
+          voterState: null,
+          hfrtMint: null,
+          voterTokenAccount: null,
+          mintAuthority: null,
+          tokenProgram: null,
+          voteRecord: voteRecordPda,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([voter])
+        .rpc();
+
+    await vote(owner);
+    await pg.program.methods
+      .transferTraderOwnership(buyer.publicKey)
+      .accounts({ trader: traderPda, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+    await pg.program.methods
+      .acceptTraderOwnership()
+      .accounts({ trader: traderPda, newOwner: buyer.publicKey })
+      .signers([buyer])
+      .rpc();
+
+    // The new owner's vote would need the same trader-keyed record, which already exists
+    let revoted = true;
+    try {
+      await vote(buyer);
+    } catch (err) {
+      revoted = false;
+    }
+    assert.isFalse(revoted, "the transferred stake should not vote twice");
+    const daoProposal = await pg.program.account.daoProposal.fetch(proposal.publicKey);
+    assert.equal(daoProposal.votesFor.toNumber(), 1000);
+    assert.equal(daoProposal.voterCount.toNumber(), 1);
+  });
+
+  it("pays a rebate from a USDC-style reward vault to the trader's own account", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+
+    const usdcMint = await createMint(pg.wallet.publicKey, 6);
+    await pg.program.methods
+      .setRewardMint(usdcMint)
+      .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+      .rpc();
+    const [rewardVaultAuthority] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reward-vault-authority")],
+      pg.program.programId
+    );
+    const rewardVault = await createTokenAccount(usdcMint, rewardVaultAuthority);
+    await mintTestTokens(usdcMint, rewardVault, 10_000_000_000);
+    const ownerAccount = await createTokenAccount(usdcMint, owner.publicKey);
+    const otherAccount = await createTokenAccount(usdcMint, pg.wallet.publicKey);
+    const claim = (traderRewardAccount: web3.PublicKey) =>
+      pg.program.methods
+        .claimRebateInRewardToken()
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          hfrtMint,
+          rewardMint: usdcMint,
+          rewardVault,
+          rewardVaultAuthority,
+          traderRewardAccount,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          referrerState: null,
+        })
+        .signers([owner])
+        .rpc();
+
+    try {
+      await claim(otherAccount);
+      assert.fail("A payout to someone else's account should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    const vaultBefore = await tokenBalance(rewardVault);
+    await claim(ownerAccount);
+    const paid = await tokenBalance(ownerAccount);
+    assert.isAbove(paid, 0);
+    assert.equal(vaultBefore - (await tokenBalance(rewardVault)), paid);
+    assert.equal(await tokenBalance(otherAccount), 0);
+  });
+
+  it("projects the staked balance that live compounds reach", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    // At the wash threshold, so rebuilding the volume each period is neither a wash trade nor throttled
+    const volume = 1_000_000;
+    await recordVolume(owner, traderPda, governance, volume);
+
+    const { events } = await pg.program.methods
+      .estimateCompoundGrowth(3)
+      .accounts({ trader: traderPda, governance: governance.publicKey, globalState: globalStatePda })
+      .simulate();
+    const projected = events.find((e) => e.name === "GrowthProjection").data.projectedBalance.toNumber();
+    assert.isAbove(projected, 0);
+
+    for (let period = 0; period < 3; period++) {
+      if (period > 0) {
+        await recordVolume(owner, traderPda, governance, volume);
+      }
+      await autoCompound(owner, traderPda, governance);
+    }
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), projected);
+  });
+
+  it("pays a stagnant high-volume trader a decayed multiplier", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setMultiplierDecayWindow(new anchor.BN(4))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const stagnant = await createOwnerWithTrader();
+    const active = await createOwnerWithTrader();
+    // Both reach the top tier
+    await recordVolume(stagnant.owner, stagnant.traderPda, governance, 1_000_000_000);
+    await recordVolume(active.owner, active.traderPda, governance, 1_000_000_000);
+
+    await new Promise((resolve) => setTimeout(resolve, 9000));
+
+    // The stagnant trader claims after at least two decay windows without growth
+    const stagnantAccount = await createTokenAccount(hfrtMint, stagnant.owner.publicKey);
+    await pg.program.methods
+      .claimRebate()
+      .accounts({
+        trader: stagnant.traderPda,
+        owner: stagnant.owner.publicKey,
+        hfrtMint,
+        traderTokenAccount: stagnantAccount,
+        mintAuthority: mintAuthorityPda,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        referrerState: null,
+        claimReceipt: null,
+        systemProgram: null,
+      })
+      .signers([stagnant.owner])
+      .rpc();
+    // The active trader grows their volume and claims straight away
+    const activeAccount = await claimHfrt(active.owner, active.traderPda, governance, 1_000_000);
+
+    const stagnantPaid = await tokenBalance(stagnantAccount);
+    const activePaid = await tokenBalance(activeAccount);
+    assert.isAbove(stagnantPaid, 0);
+    // At most 3x against at least 4x on nearly the same volume
+    assert.isBelow(stagnantPaid * 5, activePaid * 4);
+  });
+
+  it("migrates the funded HFRT vault to its associated token account", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+
+    const legacyVault = stakeVaultPda(hfrtMint);
+    const ASSOCIATED_TOKEN_PROGRAM_ID = new web3.PublicKey("ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL");
+    const [newVault] = web3.PublicKey.findProgramAddressSync(
+      [legacyVault.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), hfrtMint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const funded = await tokenBalance(legacyVault);
+    assert.isAtLeast(funded, 1000);
+    const migrate = () =>
+      pg.program.methods
+        .migrateVault()
+        .accounts({
+          globalState: globalStatePda,
+          authority: pg.wallet.publicKey,
+          hfrtMint,
+          legacyVault,
+          newVault,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .rpc();
+
+    await migrate();
+    assert.equal(await tokenBalance(legacyVault), 0);
+    assert.equal(await tokenBalance(newVault), funded);
+    const state = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.equal(state.hfrtVault.toBase58(), newVault.toBase58());
+    assert.isTrue(state.vaultMigrated);
+
+    let migratedTwice = true;
+    try {
+      await migrate();
+    } catch (err) {
+      migratedTwice = false;
+    }
+    assert.isFalse(migratedTwice, "the vault should migrate only once");
+
+    // Stake now lands in, and is paid out of, the new vault
+    await stakeHfrt(owner, traderPda, wallet, 500);
+    assert.equal(await tokenBalance(newVault), funded + 500);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 500)).rpc();
+    assert.isBelow(await tokenBalance(newVault), funded + 500);
+  });
+
+  it("rejects staking a token into a vault for a different mint", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const otherMint = await createMint(pg.wallet.publicKey);
+    const otherAccount = await createTokenAccount(otherMint, owner.publicKey);
+    await mintTestTokens(otherMint, otherAccount, 1000);
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const vaultBefore = await tokenBalance(hfrtVault);
+    const stake = (stakeMint: web3.PublicKey) =>
+      pg.program.methods
+        .stakeTokens(new anchor.BN(1000))
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          stakeMint,
+          traderTokenAccount: otherAccount,
+          vaultAuthority: stakeVaultPda(stakeMint),
+          stakingVault: hfrtVault,
+          stakePosition: null,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([owner])
+        .rpc();
+
+    // Declared as an HFRT stake
+    try {
+      await stake(hfrtMint);
+      assert.fail("A stake of another mint into the HFRT vault should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongMint");
+    }
+    // Declared as a stake of the other mint, still aimed at the HFRT vault
+    try {
+      await stake(otherMint);
+      assert.fail("A stake into another mint's vault should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WrongVault");
+    }
+    assert.equal(await tokenBalance(otherAccount), 1000);
+    assert.equal(await tokenBalance(hfrtVault), vaultBefore);
+  });
+
+  it("compounds opted-in traders in one batch, skipping one in cooldown", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setReinvestCooldown(new anchor.BN(3600))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await approveRelayer(pg.wallet.publicKey);
+
+    const traders = [await createOwnerWithTrader(), await createOwnerWithTrader(), await createOwnerWithTrader()];
+    for (const { owner, traderPda } of traders) {
+      await recordVolume(owner, traderPda, governance, 1_000_000_000);
+      await pg.program.methods
+        .setAutoReinvest(true)
+        .accounts({ trader: traderPda, owner: owner.publicKey })
+        .signers([owner])
+        .rpc();
+    }
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const compoundAll = (traderPdas: web3.PublicKey[]) =>
+      pg.program.methods
+        .autoCompoundAll()
+        .accounts({
+          relayer: pg.wallet.publicKey,
+          relayerAllowlist: relayerAllowlistPda,
+          hfrtMint,
+          stakingVault: hfrtVault,
+          mintAuthority: mintAuthorityPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(traderPdas.map((pubkey) => ({ pubkey, isWritable: true, isSigner: false })))
+        .rpc();
+
+    // The last trader compounds now, so it is inside the cooldown when it earns more volume
+    const cooling = traders[2];
+    await compoundAll([cooling.traderPda]);
+    await recordVolume(cooling.owner, cooling.traderPda, governance, 1_000_000);
+    const coolingBefore = await pg.program.account.trader.fetch(cooling.traderPda);
+    assert.isAbove(coolingBefore.stakedAmount.toNumber(), 0);
+    assert.isAbove(coolingBefore.rollingVolume.toNumber(), 0);
+
+    const vaultBefore = await tokenBalance(hfrtVault);
+    await compoundAll(traders.map(({ traderPda }) => traderPda));
+    let compounded = 0;
+    for (const { traderPda } of traders.slice(0, 2)) {
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      assert.isAbove(trader.stakedAmount.toNumber(), 0);
+      assert.equal(trader.rollingVolume.toNumber(), 0);
+      compounded += trader.stakedAmount.toNumber();
+    }
+    const coolingAfter = await pg.program.account.trader.fetch(cooling.traderPda);
+    assert.equal(coolingAfter.stakedAmount.toNumber(), coolingBefore.stakedAmount.toNumber());
+    assert.equal(coolingAfter.rollingVolume.toNumber(), coolingBefore.rollingVolume.toNumber());
+    assert.equal((await tokenBalance(hfrtVault)) - vaultBefore, compounded);
+  });
+
+  it("caps HFRT minted per epoch and resets the cap when the epoch rolls", async () => {
+    const governance = await pinFreshGovernance();
+    const setCap = (maxMintPerEpoch: number, epochDuration: number) =>
+      pg.program.methods
+        .setEpochInflationCap(new anchor.BN(maxMintPerEpoch), new anchor.BN(epochDuration))
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+    // Equal volume earns an equal rebate, so an uncapped claim prices the capped ones
+    const first = await createOwnerWithTrader();
+    const rebate = await tokenBalance(await claimHfrt(first.owner, first.traderPda, governance));
+    assert.isAbove(rebate, 0);
+
+    await setCap(rebate, 8);
+    try {
+      // A claim that mints exactly up to the cap
+      const atCap = await createOwnerWithTrader();
+      assert.equal(await tokenBalance(await claimHfrt(atCap.owner, atCap.traderPda, governance)), rebate);
+
+      const overCap = await createOwnerWithTrader();
+      const overCapAccount = await createTokenAccount(hfrtMint, overCap.owner.publicKey);
+      await recordVolume(overCap.owner, overCap.traderPda, governance, 1_000_000_000);
+      try {
+        await claimRebate(overCap.owner, overCap.traderPda, governance, overCapAccount);
+        assert.fail("A claim past the epoch cap should be rejected");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "EpochInflationCapExceeded");
+      }
+
+      await new Promise((resolve) => setTimeout(resolve, 9000));
+
+      // The next epoch starts from zero
+      await claimRebate(overCap.owner, overCap.traderPda, governance, overCapAccount);
+      assert.equal(await tokenBalance(overCapAccount), rebate);
+      const state = await pg.program.account.globalState.fetch(globalStatePda);
+      assert.equal(state.mintedThisEpoch.toNumber(), rebate);
+    } finally {
+      await setCap(0, 0);
+    }
+  });
+
+  it("stakes and records a fresh trader's first trade in one call", async () => {
+    const governance = await pinFreshGovernance();
+    // An existing trader earns HFRT and hands some to a newcomer
+    const funder = await createOwnerWithTrader();
+    const funderAccount = await claimHfrt(funder.owner, funder.traderPda, governance);
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const ownerAccount = await createTokenAccount(hfrtMint, owner.publicKey);
+    await transferTokens(funderAccount, ownerAccount, funder.owner, 5000);
+
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const vaultBefore = await tokenBalance(hfrtVault);
+    await pg.program.methods
+      .stakeAndRecordTrade(new anchor.BN(5000), new anchor.BN(750_000))
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        governance: governance.publicKey,
+        traderTokenAccount: ownerAccount,
+        stakingVault: hfrtVault,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([owner])
+      .rpc();
+
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakedAmount.toNumber(), 5000);
+    assert.isTrue(trader.isStaking);
+    assert.equal(trader.rollingVolume.toNumber(), 750_000);
+    assert.equal(await tokenBalance(ownerAccount), 0);
+    assert.equal((await tokenBalance(hfrtVault)) - vaultBefore, 5000);
+  });
+
+  it("pays the volume multiplier only to traders holding the minimum stake", async () => {
+    const governance = await pinFreshGovernance();
+    const funder = await createOwnerWithTrader();
+    const funderAccount = await claimHfrt(funder.owner, funder.traderPda, governance);
+    await pg.program.methods
+      .setMinStakeForMultiplier(new anchor.BN(1000))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+
+    // A staker funded by another trader, so their first trade is the top-tier one below
+    const staker = await createOwnerWithTrader();
+    const stakerWallet = await createTokenAccount(hfrtMint, staker.owner.publicKey);
+    await transferTokens(funderAccount, stakerWallet, funder.owner, 1000);
+    await stakeHfrt(staker.owner, staker.traderPda, stakerWallet, 1000);
+    const unstaked = await createOwnerWithTrader();
+
+    const stakerPaid = await tokenBalance(await claimHfrt(staker.owner, staker.traderPda, governance));
+    const unstakedPaid = await tokenBalance(await claimHfrt(unstaked.owner, unstaked.traderPda, governance));
+    assert.isAbove(unstakedPaid, 0);
+    // The same top-tier volume: the full 5x multiplier against 1x without the stake
+    assert.equal(stakerPaid, unstakedPaid * 5);
+  });
+
+  it("rejects claims and compounds but still stakes while rebate_paused is set", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    const setRebatePaused = (paused: boolean) =>
+      pg.program.methods
+        .setRebatePaused(paused)
+        .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+        .rpc();
+
+    await setRebatePaused(true);
+    try {
+      await recordVolume(owner, traderPda, governance, 1_000_000);
+      const walletBefore = await tokenBalance(wallet);
+      try {
+        await claimRebate(owner, traderPda, governance, wallet);
+        assert.fail("A claim should be rejected while rebates are paused");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "RebatesPaused");
+      }
+      try {
+        await autoCompound(owner, traderPda, governance);
+        assert.fail("A compound should be rejected while rebates are paused");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "RebatesPaused");
+      }
+      assert.equal(await tokenBalance(wallet), walletBefore);
+
+      await stakeHfrt(owner, traderPda, wallet, 1000);
+      const trader = await pg.program.account.trader.fetch(traderPda);
+      assert.equal(trader.stakedAmount.toNumber(), 1000);
+      assert.equal(trader.rollingVolume.toNumber(), 1_000_000);
+    } finally {
+      await setRebatePaused(false);
+    }
+  });
+
+  it("writes a distinct receipt for each claim", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    const receiptPda = (claimNonce: number) =>
+      web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("receipt"), owner.publicKey.toBuffer(), new anchor.BN(claimNonce).toArrayLike(Buffer, "le", 8)],
+        pg.program.programId
+      )[0];
+    const claimWithReceipt = async (volume: number, claimNonce: number) => {
+      await recordVolume(owner, traderPda, governance, volume);
+      const before = await tokenBalance(wallet);
+      await pg.program.methods
+        .claimRebate()
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          hfrtMint,
+          traderTokenAccount: wallet,
+          mintAuthority: mintAuthorityPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          referrerState: null,
+          claimReceipt: receiptPda(claimNonce),
+          systemProgram: web3.SystemProgram.programId,
+        })
+        .signers([owner])
+        .rpc();
+      return (await tokenBalance(wallet)) - before;
+    };
+
+    // A top-tier claim, then a bottom-tier one
+    const firstPaid = await claimWithReceipt(1_000_000_000, 0);
+    const secondPaid = await claimWithReceipt(1_000_000, 1);
+    const first = await pg.program.account.claimReceipt.fetch(receiptPda(0));
+    const second = await pg.program.account.claimReceipt.fetch(receiptPda(1));
+    assert.notEqual(receiptPda(0).toBase58(), receiptPda(1).toBase58());
+    for (const [receipt, nonce, paid, multiplier] of [
+      [first, 0, firstPaid, 5],
+      [second, 1, secondPaid, 1],
+    ] as const) {
+      assert.equal(receipt.owner.toBase58(), owner.publicKey.toBase58());
+      assert.equal(receipt.claimNonce.toNumber(), nonce);
+      assert.equal(receipt.amount.toNumber(), paid);
+      assert.equal(receipt.rebateRate, 10);
+      assert.equal(receipt.multiplier, multiplier);
+      assert.isAbove(receipt.claimedAt.toNumber(), 0);
+    }
+    assert.isAtLeast(second.claimedAt.toNumber(), first.claimedAt.toNumber());
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.claimNonce.toNumber(), 2);
+  });
+
+  it("boosts claims after a quiet activity window but not after a busy one", async () => {
+    const governance = await pinFreshGovernance();
+    const setBoost = (window: number, volumeThreshold: string, boostBps: number) =>
+      pg.program.methods
+        .setLowActivityBoost(new anchor.BN(window), new anchor.BN(volumeThreshold), boostBps)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+    const quiet = await createOwnerWithTrader();
+    const busy = await createOwnerWithTrader();
+
+    try {
+      // Every window counts as quiet under an unreachable threshold
+      await setBoost(6, "1000000000000000000", 500);
+      const quietPaid = await tokenBalance(await claimHfrt(quiet.owner, quiet.traderPda, governance));
+
+      // Under a low threshold, the window holding both trades is busy once it completes
+      await setBoost(6, "1000000", 500);
+      const busyAccount = await createTokenAccount(hfrtMint, busy.owner.publicKey);
+      await recordVolume(busy.owner, busy.traderPda, governance, 1_000_000_000);
+      await new Promise((resolve) => setTimeout(resolve, 7000));
+      await claimRebate(busy.owner, busy.traderPda, governance, busyAccount);
+      const busyPaid = await tokenBalance(busyAccount);
+
+      assert.isAbove(busyPaid, 0);
+      assert.equal(quietPaid, busyPaid + Math.floor((busyPaid * 500) / 10_000));
+    } finally {
+      await setBoost(0, "0", 0);
+    }
+  });
+
+  it("reports the resulting lock in TokensUnstaked for partial and full unstakes", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    const staked = await pg.program.account.trader.fetch(traderPda);
+    assert.isAbove(staked.stakeStartTime.toNumber(), 0);
+    const unstakedEvent = async (amount: number) => {
+      const unstake = await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, amount);
+      const { events } = await unstake.simulate();
+      await unstake.rpc();
+      return events.find((e) => e.name === "TokensUnstaked").data;
+    };
+
+    const partial = await unstakedEvent(400);
+    assert.isFalse(partial.fullyUnstaked);
+    assert.equal(partial.newStakeStartTime.toNumber(), staked.stakeStartTime.toNumber());
+    assert.equal(partial.stakedAmount.toNumber(), 600);
+
+    const full = await unstakedEvent(600);
+    assert.isTrue(full.fullyUnstaked);
+    assert.equal(full.newStakeStartTime.toNumber(), 0);
+    assert.equal(full.stakedAmount.toNumber(), 0);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.stakeStartTime.toNumber(), 0);
+  });
+
+  it("claims a rebate through a mock caller program's CPI", async function () {
+    // The mock aggregator in mock_caller/, which must be deployed alongside HFRT
+    const MOCK_CALLER_PROGRAM_ID = new web3.PublicKey("Bfwbzpnyk42jmUKr2iPkmTZnrtaDpzPKKbxYqsB6T5Vq");
+    if ((await pg.connection.getAccountInfo(MOCK_CALLER_PROGRAM_ID)) === null) {
+      this.skip();
+    }
+    const [aggregatorPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("aggregator")],
+      MOCK_CALLER_PROGRAM_ID
+    );
+    const [traderPda] = web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("trader"), aggregatorPda.toBuffer()],
+      pg.program.programId
+    );
+    // Anchor discriminator of the mock's `forward(data: Vec<u8>)`
+    const FORWARD_DISCRIMINATOR = Buffer.from([45, 165, 201, 116, 206, 225, 241, 18]);
+    // Has the mock invoke `ix` by CPI; the aggregator PDA cannot sign here, so the mock signs for it
+    const forward = (ix: web3.TransactionInstruction) => {
+      const length = Buffer.alloc(4);
+      length.writeUInt32LE(ix.data.length);
+      return pg.program.provider.sendAndConfirm(
+        new web3.Transaction().add(
+          new web3.TransactionInstruction({
+            programId: MOCK_CALLER_PROGRAM_ID,
+            keys: [
+              { pubkey: aggregatorPda, isSigner: false, isWritable: true },
+              { pubkey: pg.program.programId, isSigner: false, isWritable: false },
+              ...ix.keys.map((key) => ({ ...key, isSigner: false })),
+            ],
+            data: Buffer.concat([FORWARD_DISCRIMINATOR, length, ix.data]),
+          })
+        )
+      );
+    };
+
+    const governance = await pinFreshGovernance();
+    // The aggregator PDA pays for its own trader account
+    await pg.program.provider.sendAndConfirm(
+      new web3.Transaction().add(
+        web3.SystemProgram.transfer({
+          fromPubkey: pg.wallet.publicKey,
+          toPubkey: aggregatorPda,
+          lamports: web3.LAMPORTS_PER_SOL / 10,
+        })
+      )
+    );
+    if ((await pg.connection.getAccountInfo(traderPda)) === null) {
+      await forward(
+        await pg.program.methods
+          .initializeTrader()
+          .accounts({
+            trader: traderPda,
+            owner: aggregatorPda,
+            systemProgram: web3.SystemProgram.programId,
+            traderRegistry: null,
+            registryPage: null,
+          })
+          .instruction()
+      );
+    }
+    await forward(
+      await pg.program.methods
+        .recordTrade(new anchor.BN(1_000_000_000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: aggregatorPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .instruction()
+    );
+
+    // The rebate lands in a token account the aggregator PDA owns
+    const aggregatorAccount = await createTokenAccount(hfrtMint, aggregatorPda);
+    await forward(
+      await pg.program.methods
+        .claimRebate()
+        .accounts({
+          trader: traderPda,
+          owner: aggregatorPda,
+          hfrtMint,
+          traderTokenAccount: aggregatorAccount,
+          mintAuthority: mintAuthorityPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          referrerState: null,
+          claimReceipt: null,
+          systemProgram: null,
+        })
+        .instruction()
+    );
+    assert.isAbove(await tokenBalance(aggregatorAccount), 0);
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 0);
+    assert.equal(trader.owner.toBase58(), aggregatorPda.toBase58());
+  });
+
+  it("reports each can_claim blocking reason and agrees with the claim", async () => {
+    const governance = await pinFreshGovernance();
+    const reason = async (traderPda: web3.PublicKey) => {
+      const { events } = await pg.program.methods
+        .canClaim()
+        .accounts({ trader: traderPda, governance: governance.publicKey, globalState: globalStatePda })
+        .simulate();
+      const eligibility = events.find((e) => e.name === "ClaimEligibility").data;
+      assert.equal(eligibility.eligible, eligibility.reasonCode === 0);
+      return eligibility.reasonCode;
+    };
+    const expectClaimError = async (
+      owner: web3.Keypair,
+      traderPda: web3.PublicKey,
+      wallet: web3.PublicKey,
+      code: string
+    ) => {
+      try {
+        await claimRebate(owner, traderPda, governance, wallet);
+        assert.fail(`expected ${code}`);
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, code);
+      }
+    };
+
+    const idle = await createOwnerWithTrader();
+    const idleWallet = await createTokenAccount(hfrtMint, idle.owner.publicKey);
+    assert.equal(await reason(idle.traderPda), 1); // CLAIM_BLOCKED_NO_VOLUME
+    await expectClaimError(idle.owner, idle.traderPda, idleWallet, "NoVolume");
+
+    // Too little volume to earn a whole base unit
+    const dust = await createOwnerWithTrader();
+    const dustWallet = await createTokenAccount(hfrtMint, dust.owner.publicKey);
+    await recordVolume(dust.owner, dust.traderPda, governance, 50);
+    assert.equal(await reason(dust.traderPda), 2); // CLAIM_BLOCKED_ZERO_REBATE
+    await expectClaimError(dust.owner, dust.traderPda, dustWallet, "RebateTooSmall");
+
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    await recordVolume(owner, traderPda, governance, 1_000_000);
+    assert.equal(await reason(traderPda), 0); // CLAIM_ELIGIBLE
+
+    const setMinRebate = (minimum: string) =>
+      pg.program.methods
+        .setMinRebateToMint(new anchor.BN(minimum))
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+    await setMinRebate("1000000000000");
+    assert.equal(await reason(traderPda), 5); // CLAIM_BLOCKED_BELOW_MINIMUM
+    await expectClaimError(owner, traderPda, wallet, "RebateTooSmall");
+    await setMinRebate("0");
+
+    const setBlocked = (blocked: boolean) =>
+      pg.program.methods
+        .setTraderBlocked(blocked)
+        .accounts({ globalState: globalStatePda, trader: traderPda, authority: pg.wallet.publicKey })
+        .rpc();
+    await setBlocked(true);
+    assert.equal(await reason(traderPda), 4); // CLAIM_BLOCKED_ADDRESS
+    await expectClaimError(owner, traderPda, wallet, "AddressBlocked");
+    await setBlocked(false);
+
+    const setRebatePaused = (paused: boolean) =>
+      pg.program.methods
+        .setRebatePaused(paused)
+        .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+        .rpc();
+    const setPaused = (paused: boolean) =>
+      pg.program.methods
+        .setPaused(paused, paused ? 1 : 0)
+        .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+        .rpc();
+    await setRebatePaused(true);
+    try {
+      assert.equal(await reason(traderPda), 6); // CLAIM_BLOCKED_REBATES_PAUSED
+      await expectClaimError(owner, traderPda, wallet, "RebatesPaused");
+      // A full pause outranks the rebate pause
+      await setPaused(true);
+      try {
+        assert.equal(await reason(traderPda), 3); // CLAIM_BLOCKED_PAUSED
+        await expectClaimError(owner, traderPda, wallet, "ProgramPaused");
+      } finally {
+        await setPaused(false);
+      }
+    } finally {
+      await setRebatePaused(false);
+    }
+
+    // Once every block is lifted the claim goes through, as reported
+    assert.equal(await reason(traderPda), 0); // CLAIM_ELIGIBLE
+    await claimRebate(owner, traderPda, governance, wallet);
+    assert.isAbove(await tokenBalance(wallet), 0);
+    assert.equal(await reason(traderPda), 1); // CLAIM_BLOCKED_NO_VOLUME
+  });
+
+  it("pays the elevated rate only while volatility mode is on", async () => {
+    const governance = await pinFreshGovernance(10, 20);
+    await pg.program.methods
+      .updateVolatileRebateRate(20)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const setVolatilityMode = (enabled: boolean) =>
+      pg.program.methods
+        .setVolatilityMode(enabled)
+        .accounts({ globalState: globalStatePda, signer: pg.wallet.publicKey })
+        .rpc();
+    // Claims with equal volume, returning the amount paid and the regime RebateClaimed reported
+    const claimOnce = async () => {
+      const { owner, traderPda } = await createOwnerWithTrader();
+      const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+      await recordVolume(owner, traderPda, governance, 1_000_000_000);
+      const signature = await claimRebate(owner, traderPda, governance, wallet);
+      const tx = await pg.connection.getTransaction(signature, { commitment: "confirmed" });
+      const prefix = "Program data: ";
+      const claimed = tx.meta.logMessages
+        .filter((line) => line.startsWith(prefix))
+        .map((line) => pg.program.coder.events.decode(line.slice(prefix.length)))
+        .find((e) => e && e.name.toLowerCase() === "rebateclaimed");
+      return { paid: await tokenBalance(wallet), volatilityMode: claimed.data.volatilityMode };
+    };
+
+    const calm = await claimOnce();
+    assert.isFalse(calm.volatilityMode);
+    await setVolatilityMode(true);
+    try {
+      const volatile = await claimOnce();
+      assert.isTrue(volatile.volatilityMode);
+      assert.equal(volatile.paid, calm.paid * 2);
+    } finally {
+      await setVolatilityMode(false);
+    }
+  });
+
+  it("emits the multiplier tier each trade lands in", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const trade = async (owner: web3.Keypair, traderPda: web3.PublicKey, amount: number) => {
+      const recordTrade = pg.program.methods
+        .recordTrade(new anchor.BN(amount), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: owner.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([owner]);
+      const { events } = await recordTrade.simulate();
+      await recordTrade.rpc();
+      return events.find((e) => e.name === "TradeRecorded").data.currentMultiplier;
+    };
+
+    // Each trader lands just under a tier threshold (10, 50 and 100 HFRT of volume), then a small trade
+    // that is neither a wash trade nor throttled reaches it
+    for (const [below, multiplierBelow, multiplierAt] of [
+      [9_500_000, 1, 2],
+      [49_500_000, 2, 3],
+      [99_500_000, 3, 5],
+    ]) {
+      const { owner, traderPda } = await createOwnerWithTrader();
+      assert.equal(await trade(owner, traderPda, below), multiplierBelow);
+      assert.equal(await trade(owner, traderPda, 500_000), multiplierAt);
+    }
+  });
+
+  it("lets only the authority force a trader's volume, recording the change", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    await recordVolume(owner, traderPda, governance, 750_000);
+    const forceReset = (authority: web3.PublicKey, newVolume: number) =>
+      pg.program.methods
+        .forceResetVolume(new anchor.BN(newVolume))
+        .accounts({ globalState: globalStatePda, trader: traderPda, authority });
+
+    try {
+      await forceReset(owner.publicKey, 1_000_000_000).signers([owner]).rpc();
+      assert.fail("Only the authority may force a trader's volume");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ConstraintHasOne");
+    }
+
+    const reset = forceReset(pg.wallet.publicKey, 250_000);
+    const { events } = await reset.simulate();
+    await reset.rpc();
+    const event = events.find((e) => e.name === "VolumeForceReset").data;
+    assert.equal(event.owner.toBase58(), owner.publicKey.toBase58());
+    assert.equal(event.old.toNumber(), 750_000);
+    assert.equal(event.new.toNumber(), 250_000);
+    assert.equal(event.by.toBase58(), pg.wallet.publicKey.toBase58());
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 250_000);
+  });
+
+  it("weights the stake start time by amount when adding to a stake", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+
+    await stakeHfrt(owner, traderPda, wallet, 100);
+    const small = await pg.program.account.trader.fetch(traderPda);
+    const firstStart = small.stakeStartTime.toNumber();
+    assert.isAbove(firstStart, 0);
+
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+    await stakeHfrt(owner, traderPda, wallet, 900);
+    const large = await pg.program.account.trader.fetch(traderPda);
+    const now = (await pg.connection.getBlockTime(await pg.connection.getSlot("confirmed"))) ?? firstStart;
+    const weightedStart = large.stakeStartTime.toNumber();
+    assert.equal(large.stakedAmount.toNumber(), 1000);
+    // 90% of the stake is new, so the lock moves at least 90% of the way from the first stake to the second
+    assert.isAtLeast(weightedStart, firstStart + Math.floor((9 * 5) / 10));
+    assert.isAtMost(weightedStart, now);
+  });
+
+  it("compounds through an approved relayer and rejects an unapproved one", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const relay = (relayer: web3.Keypair) =>
+      pg.program.methods
+        .relayAutoCompound()
+        .accounts({
+          trader: traderPda,
+          relayer: relayer.publicKey,
+          relayerAllowlist: relayerAllowlistPda,
+          hfrtMint,
+          stakingVault: hfrtVault,
+          mintAuthority: mintAuthorityPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([relayer])
+        .rpc();
+
+    const approved = await fundedKeypair();
+    const stranger = await fundedKeypair();
+    await approveRelayer(approved.publicKey);
+
+    try {
+      await relay(stranger);
+      assert.fail("expected UnauthorizedRelayer");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "UnauthorizedRelayer");
+    }
+    const untouched = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(untouched.stakedAmount.toNumber(), 0);
+    assert.equal(untouched.rollingVolume.toNumber(), 1_000_000_000);
+
+    await relay(approved);
+    const compounded = await pg.program.account.trader.fetch(traderPda);
+    assert.isAbove(compounded.stakedAmount.toNumber(), 0);
+    assert.equal(compounded.rollingVolume.toNumber(), 0);
+  });
+
+  it("lets the emergency council pause but not unpause, and the authority do both", async () => {
+    const council = await fundedKeypair();
+    const setCouncil = (emergencyCouncil: web3.PublicKey) =>
+      pg.program.methods
+        .setEmergencyCouncil(emergencyCouncil)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+    const setPaused = (signer: web3.Keypair | null, paused: boolean) => {
+      const builder = pg.program.methods
+        .setPaused(paused, paused ? 7 : 0)
+        .accounts({ globalState: globalStatePda, signer: signer ? signer.publicKey : pg.wallet.publicKey });
+      return (signer ? builder.signers([signer]) : builder).rpc();
+    };
+
+    await setCouncil(council.publicKey);
+    try {
+      await setPaused(council, true);
+      let state = await pg.program.account.globalState.fetch(globalStatePda);
+      assert.isTrue(state.paused);
+      assert.equal(state.pauseReason, 7);
+
+      try {
+        await setPaused(council, false);
+        assert.fail("expected Unauthorized");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "Unauthorized");
+      }
+      state = await pg.program.account.globalState.fetch(globalStatePda);
+      assert.isTrue(state.paused);
+
+      await setPaused(null, false);
+      state = await pg.program.account.globalState.fetch(globalStatePda);
+      assert.isFalse(state.paused);
+      await setPaused(null, true);
+      assert.isTrue((await pg.program.account.globalState.fetch(globalStatePda)).paused);
+    } finally {
+      await setPaused(null, false);
+      await setCouncil(web3.PublicKey.default);
+    }
+  });
+
+  it("records trades from the trusted reporter and rejects an untrusted key", async () => {
+    const governance = await pinFreshGovernance();
+    const { traderPda } = await createOwnerWithTrader();
+    const reporter = await fundedKeypair();
+    const stranger = await fundedKeypair();
+    const setTrustedReporter = (trustedReporter: web3.PublicKey) =>
+      pg.program.methods
+        .setTrustedReporter(trustedReporter)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+    const report = (signer: web3.Keypair) =>
+      pg.program.methods
+        .recordTrade(new anchor.BN(500_000), null, null, null)
+        .accounts({
+          trader: traderPda,
+          reporter: signer.publicKey,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          marketVolume: null,
+          hfrtMint: null,
+          stakingVault: null,
+          mintAuthority: null,
+          tokenProgram: null,
+        })
+        .signers([signer])
+        .rpc();
+
+    await setTrustedReporter(reporter.publicKey);
+    try {
+      await report(reporter);
+      assert.equal((await pg.program.account.trader.fetch(traderPda)).rollingVolume.toNumber(), 500_000);
+
+      try {
+        await report(stranger);
+        assert.fail("expected UnauthorizedReporter");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "UnauthorizedReporter");
+      }
+      assert.equal((await pg.program.account.trader.fetch(traderPda)).rollingVolume.toNumber(), 500_000);
+    } finally {
+      await setTrustedReporter(web3.PublicKey.default);
+    }
+  });
+
+  it("denies a blocked trader claims, unstakes and compounds until unblocked", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    await recordVolume(owner, traderPda, governance, 1_000_000);
+    const setBlocked = (blocked: boolean) =>
+      pg.program.methods
+        .setTraderBlocked(blocked)
+        .accounts({ globalState: globalStatePda, trader: traderPda, authority: pg.wallet.publicKey })
+        .rpc();
+    const expectBlocked = async (action: () => Promise<unknown>) => {
+      try {
+        await action();
+        assert.fail("expected AddressBlocked");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "AddressBlocked");
+      }
+    };
+
+    await setBlocked(true);
+    await expectBlocked(() => claimRebate(owner, traderPda, governance, wallet));
+    await expectBlocked(async () => (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 100)).rpc());
+    await expectBlocked(() => autoCompound(owner, traderPda, governance));
+    const frozen = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(frozen.stakedAmount.toNumber(), 1000);
+    assert.equal(frozen.rollingVolume.toNumber(), 1_000_000);
+
+    await setBlocked(false);
+    await autoCompound(owner, traderPda, governance);
+    const walletBefore = await tokenBalance(wallet);
+    await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 100)).rpc();
+    assert.isAbove(await tokenBalance(wallet), walletBefore);
+  });
+
+  it("counts stakers up and back down to where it started", async () => {
+    const governance = await pinFreshGovernance();
+    const totalStakers = async () =>
+      (await pg.program.account.globalState.fetch(globalStatePda)).totalStakers.toNumber();
+    const traders = [];
+    for (let i = 0; i < 2; i++) {
+      const { owner, traderPda } = await createOwnerWithTrader();
+      traders.push({ owner, traderPda, wallet: await claimHfrt(owner, traderPda, governance) });
+    }
+    const before = await totalStakers();
+
+    for (const { owner, traderPda, wallet } of traders) {
+      await stakeHfrt(owner, traderPda, wallet, 1000);
+    }
+    assert.equal(await totalStakers(), before + 2);
+    // A second stake from an existing staker does not count them again
+    await stakeHfrt(traders[0].owner, traders[0].traderPda, traders[0].wallet, 500);
+    assert.equal(await totalStakers(), before + 2);
+
+    const [first, second] = traders;
+    await (await unstakeHfrt(first.owner, first.traderPda, first.wallet, governance.publicKey, 1500)).rpc();
+    assert.equal(await totalStakers(), before + 1);
+    const lastUnstake = await unstakeHfrt(second.owner, second.traderPda, second.wallet, governance.publicKey, 1000);
+    const { events } = await lastUnstake.simulate();
+    const unstaked = events.find((e) => e.name === "TokensUnstaked").data;
+    assert.isTrue(unstaked.fullyUnstaked);
+    assert.equal(unstaked.totalStakers.toNumber(), before);
+    await lastUnstake.rpc();
+    assert.equal(await totalStakers(), before);
+  });
+
+  it("transfers trader ownership only once the nominee accepts", async () => {
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const buyer = await fundedKeypair();
+    const interloper = await fundedKeypair();
+    const accept = (signer: web3.Keypair) =>
+      pg.program.methods
+        .acceptTraderOwnership()
+        .accounts({ trader: traderPda, newOwner: signer.publicKey })
+        .signers([signer])
+        .rpc();
+    const expectNotPending = async (signer: web3.Keypair) => {
+      try {
+        await accept(signer);
+        assert.fail("expected NotPendingOwner");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "NotPendingOwner");
+      }
+    };
+    const setAutoReinvest = (signer: web3.Keypair) =>
+      pg.program.methods
+        .setAutoReinvest(true)
+        .accounts({ trader: traderPda, owner: signer.publicKey })
+        .signers([signer])
+        .rpc();
+
+    // Nobody can accept before a nomination, and only the nominee can accept after one
+    await expectNotPending(buyer);
+    await pg.program.methods
+      .transferTraderOwnership(buyer.publicKey)
+      .accounts({ trader: traderPda, owner: owner.publicKey })
+      .signers([owner])
+      .rpc();
+    await expectNotPending(interloper);
+    let trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.owner.toBase58(), owner.publicKey.toBase58());
+    assert.equal(trader.pendingOwner.toBase58(), buyer.publicKey.toBase58());
+
+    await accept(buyer);
+    trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.owner.toBase58(), buyer.publicKey.toBase58());
+    assert.equal(trader.pendingOwner.toBase58(), web3.PublicKey.default.toBase58());
+
+    // The new owner now controls the trader and the old one no longer does
+    await setAutoReinvest(buyer);
+    try {
+      await setAutoReinvest(owner);
+      assert.fail("expected ConstraintHasOne");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ConstraintHasOne");
+    }
+    await expectNotPending(buyer);
+  });
+
+  it("flags a burst of medium trades that together exceed the wash window volume", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setWashWindow(new anchor.BN(60), new anchor.BN(2_000_000))
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+
+    // Each trade is under WASH_TRADE_THRESHOLD, so only the window total can trip detection
+    await recordVolume(owner, traderPda, governance, 600_000);
+    await recordVolume(owner, traderPda, governance, 600_001);
+    await recordVolume(owner, traderPda, governance, 600_002);
+    try {
+      await recordVolume(owner, traderPda, governance, 600_003);
+      assert.fail("expected WashTrade");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "WashTrade");
+    }
+    const trader = await pg.program.account.trader.fetch(traderPda);
+    assert.equal(trader.rollingVolume.toNumber(), 1_800_003);
+  });
+
+  it("raises the supply cap but refuses one below circulating supply", async () => {
+    const setCap = (cap: anchor.BN) =>
+      pg.program.methods
+        .setMaxTotalSupply(cap)
+        .accounts({ globalState: globalStatePda, hfrtMint, authority: pg.wallet.publicKey });
+    const supply = new anchor.BN((await pg.connection.getTokenSupply(hfrtMint)).value.amount);
+    const { maxTotalSupply: oldCap } = await pg.program.account.globalState.fetch(globalStatePda);
+
+    try {
+      const raised = supply.addn(1_000_000);
+      const { events } = await setCap(raised).simulate();
+      const changed = events.find((e) => e.name === "SupplyCapChanged").data;
+      assert.equal(changed.oldCap.toString(), oldCap.toString());
+      assert.equal(changed.newCap.toString(), raised.toString());
+      await setCap(raised).rpc();
+      assert.equal(
+        (await pg.program.account.globalState.fetch(globalStatePda)).maxTotalSupply.toString(),
+        raised.toString()
+      );
+
+      // The circulating supply itself is an allowed cap; one unit below is not
+      await setCap(supply).rpc();
+      try {
+        await setCap(supply.subn(1)).rpc();
+        assert.fail("expected InvalidSupplyCap");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "InvalidSupplyCap");
+      }
+      assert.equal(
+        (await pg.program.account.globalState.fetch(globalStatePda)).maxTotalSupply.toString(),
+        supply.toString()
+      );
+    } finally {
+      // A zero cap cannot be restored once tokens exist, so lift the cap out of reach instead
+      await setCap(oldCap.isZero() ? new anchor.BN("18446744073709551615") : oldCap).rpc();
+    }
+  });
+
+  it("refuses to mint a rebate into a token account the trader owner does not hold", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+    const foreignAccount = await createTokenAccount(hfrtMint, (await fundedKeypair()).publicKey);
+
+    try {
+      await claimRebate(owner, traderPda, governance, foreignAccount);
+      assert.fail("A rebate into someone else's account should be rejected");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+    assert.equal(await tokenBalance(foreignAccount), 0);
+    assert.equal((await pg.program.account.trader.fetch(traderPda)).rollingVolume.toNumber(), 1_000_000_000);
+
+    const ownAccount = await createTokenAccount(hfrtMint, owner.publicKey);
+    await claimRebate(owner, traderPda, governance, ownAccount);
+    assert.isAbove(await tokenBalance(ownAccount), 0);
+  });
+
+  it("hands the mint authority away so program mints fail, then takes it back", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+    const mintAuthority = async () => {
+      const mint = await pg.connection.getParsedAccountInfo(hfrtMint);
+      return (mint.value.data as web3.ParsedAccountData).parsed.info.mintAuthority;
+    };
+
+    await pg.program.methods
+      .transferMintAuthority(pg.wallet.publicKey)
+      .accounts({
+        globalState: globalStatePda,
+        hfrtMint,
+        mintAuthority: mintAuthorityPda,
+        authority: pg.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    try {
+      assert.equal(await mintAuthority(), pg.wallet.publicKey.toBase58());
+      let minted = true;
+      try {
+        await claimRebate(owner, traderPda, governance, wallet);
+      } catch (err) {
+        minted = false;
+      }
+      assert.isFalse(minted, "the program should no longer be able to mint");
+      assert.equal(await tokenBalance(wallet), 0);
+    } finally {
+      // Hand the authority back to the PDA (SetAuthority, MintTokens) so later tests can mint
+      await pg.program.provider.sendAndConfirm(
+        new web3.Transaction().add(
+          tokenInstruction(
+            [
+              { pubkey: hfrtMint, isSigner: false, isWritable: true },
+              { pubkey: pg.wallet.publicKey, isSigner: true, isWritable: false },
+            ],
+            Buffer.concat([Buffer.from([6, 0, 1]), mintAuthorityPda.toBuffer()])
+          )
+        )
+      );
+    }
+    assert.equal(await mintAuthority(), mintAuthorityPda.toBase58());
+    await claimRebate(owner, traderPda, governance, wallet);
+    assert.isAbove(await tokenBalance(wallet), 0);
+  });
+
+  it("reports vault stats whose drift moves only by retained unstake penalties", async () => {
+    const governance = await pinFreshGovernance();
+    const vaultStats = async () => {
+      const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+      const { events } = await pg.program.methods
+        .getVaultStats()
+        .accounts({ globalState: globalStatePda, stakingVault: hfrtVault })
+        .simulate();
+      const stats = events.find((e) => e.name === "VaultStats").data;
+      assert.equal(stats.drift.toNumber(), stats.vaultBalance.toNumber() - stats.totalStaked.toNumber());
+      return stats;
+    };
+    const traders = [];
+    for (let i = 0; i < 2; i++) {
+      const { owner, traderPda } = await createOwnerWithTrader();
+      traders.push({ owner, traderPda, wallet: await claimHfrt(owner, traderPda, governance) });
+    }
+    const before = await vaultStats();
+
+    await stakeHfrt(traders[0].owner, traders[0].traderPda, traders[0].wallet, 1000);
+    await stakeHfrt(traders[1].owner, traders[1].traderPda, traders[1].wallet, 2000);
+    const staked = await vaultStats();
+    assert.equal(staked.totalStaked.toNumber(), before.totalStaked.toNumber() + 3000);
+    assert.equal(staked.drift.toNumber(), before.drift.toNumber());
+
+    // Penalties stay in the vault, so they are the only drift the unstakes add
+    let penalties = 0;
+    for (const [{ owner, traderPda, wallet }, amount] of [
+      [traders[0], 400],
+      [traders[1], 2000],
+    ] as const) {
+      const unstake = await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, amount);
+      const { events } = await unstake.simulate();
+      penalties += events.find((e) => e.name === "TokensUnstaked").data.penalty.toNumber();
+      await unstake.rpc();
+    }
+    assert.isAbove(penalties, 0);
+    const after = await vaultStats();
+    assert.equal(after.totalStaked.toNumber(), before.totalStaked.toNumber() + 600);
+    assert.equal(after.drift.toNumber(), before.drift.toNumber() + penalties);
+    assert.equal(after.totalStakers.toNumber(), before.totalStakers.toNumber() + 1);
+  });
+
+  it("splits a claim between wallet and stake at 0%, 100% and a partial ratio", async () => {
+    const governance = await pinFreshGovernance();
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const splitClaim = (owner: web3.Keypair, traderPda: web3.PublicKey, wallet: web3.PublicKey, stakeBps: number) =>
+      pg.program.methods
+        .claimRebateSplit(stakeBps)
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          hfrtMint,
+          traderTokenAccount: wallet,
+          stakingVault: hfrtVault,
+          mintAuthority: mintAuthorityPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          referrerState: null,
+        })
+        .signers([owner]);
+    // Claims an equal rebate split at `stakeBps` for a fresh trader, checking the event against the balances
+    const claimSplit = async (stakeBps: number) => {
+      const { owner, traderPda } = await createOwnerWithTrader();
+      const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+      await recordVolume(owner, traderPda, governance, 1_000_000_000);
+      const claim = splitClaim(owner, traderPda, wallet, stakeBps);
+      const { events } = await claim.simulate();
+      const split = events.find((e) => e.name === "RebateSplitClaimed").data;
+      await claim.rpc();
+      assert.equal(split.stakeBps, stakeBps);
+      assert.equal(await tokenBalance(wallet), split.walletAmount.toNumber());
+      assert.equal(
+        (await pg.program.account.trader.fetch(traderPda)).stakedAmount.toNumber(),
+        split.stakedAmount.toNumber()
+      );
+      return { wallet: split.walletAmount.toNumber(), staked: split.stakedAmount.toNumber() };
+    };
+
+    const allWallet = await claimSplit(0);
+    const total = allWallet.wallet;
+    assert.isAbove(total, 0);
+    assert.equal(allWallet.staked, 0);
+    assert.deepEqual(await claimSplit(10_000), { wallet: 0, staked: total });
+    const staked = Math.floor((total * 3000) / 10_000);
+    assert.deepEqual(await claimSplit(3000), { wallet: total - staked, staked });
+
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+    try {
+      await splitClaim(owner, traderPda, wallet, 10_001).rpc();
+      assert.fail("expected InvalidStakeBps");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidStakeBps");
+    }
+  });
+
+  it("stores each bond forfeit policy and rejects an unknown one", async () => {
+    const governance = await createGovernance();
+    const setPolicy = (policy: number) =>
+      pg.program.methods
+        .setBondForfeitPolicy(policy)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+
+    // Treasury, burn and redistribute; proposals post no bond yet, so only the stored policy is observable
+    for (const policy of [1, 2, 0]) {
+      await setPolicy(policy);
+      assert.equal((await pg.program.account.governance.fetch(governance.publicKey)).bondForfeitPolicy, policy);
+    }
+    try {
+      await setPolicy(3);
+      assert.fail("expected InvalidBondForfeitPolicy");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "InvalidBondForfeitPolicy");
+    }
+    assert.equal((await pg.program.account.governance.fetch(governance.publicKey)).bondForfeitPolicy, 0);
+  });
+
+  it("keeps or restarts the remaining stake's clock on a partial unstake", async () => {
+    const governance = await pinFreshGovernance();
+    // Stakes, partially unstakes after a short wait and unstakes the rest, returning the clocks and penalty
+    const partialThenFull = async (resetsClock: boolean) => {
+      await pg.program.methods
+        .setPartialUnstakeResetsClock(resetsClock)
+        .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+        .rpc();
+      const { owner, traderPda } = await createOwnerWithTrader();
+      const wallet = await claimHfrt(owner, traderPda, governance);
+      await stakeHfrt(owner, traderPda, wallet, 1000);
+      const stakedAt = (await pg.program.account.trader.fetch(traderPda)).stakeStartTime.toNumber();
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+
+      await (await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 400)).rpc();
+      const remainingStart = (await pg.program.account.trader.fetch(traderPda)).stakeStartTime.toNumber();
+      const rest = await unstakeHfrt(owner, traderPda, wallet, governance.publicKey, 600);
+      const { events } = await rest.simulate();
+      await rest.rpc();
+      return { stakedAt, remainingStart, penalty: events.find((e) => e.name === "TokensUnstaked").data.penalty };
+    };
+
+    const kept = await partialThenFull(false);
+    assert.equal(kept.remainingStart, kept.stakedAt);
+    const reset = await partialThenFull(true);
+    assert.isAbove(reset.remainingStart, reset.stakedAt);
+    // Both remaining stakes are still inside the first week, so the follow-up unstake pays 10% either way;
+    // the settings only diverge once the original clock has crossed a tier
+    assert.equal(kept.penalty.toNumber(), 60);
+    assert.equal(reset.penalty.toNumber(), 60);
+  });
+
+  it("rejects a compound in the same transaction as a claim", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    await recordVolume(owner, traderPda, governance, 1_000_000_000);
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const compound = await pg.program.methods
+      .autoCompound()
+      .accounts({
+        trader: traderPda,
+        owner: owner.publicKey,
+        hfrtMint,
+        stakingVault: hfrtVault,
+        mintAuthority: mintAuthorityPda,
+        governance: governance.publicKey,
+        globalState: globalStatePda,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .instruction();
+
+    try {
+      await pg.program.methods
+        .claimRebate()
+        .accounts({
+          trader: traderPda,
+          owner: owner.publicKey,
+          hfrtMint,
+          traderTokenAccount: wallet,
+          mintAuthority: mintAuthorityPda,
+          governance: governance.publicKey,
+          globalState: globalStatePda,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          referrerState: null,
+          claimReceipt: null,
+          systemProgram: null,
+        })
+        .postInstructions([compound])
+        .signers([owner])
+        .rpc();
+      assert.fail("expected AlreadyProcessedThisSlot");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "AlreadyProcessedThisSlot");
+    }
+    // The failed compound reverts the claim with it
+    assert.equal(await tokenBalance(wallet), 0);
+    assert.equal((await pg.program.account.trader.fetch(traderPda)).rollingVolume.toNumber(), 1_000_000_000);
+
+    await claimRebate(owner, traderPda, governance, wallet);
+    assert.isAbove(await tokenBalance(wallet), 0);
+  });
+
+  it("shrinks a trader's rebate once lifetime claims pass the diminishing threshold", async () => {
+    const governance = await pinFreshGovernance();
+    await pg.program.methods
+      .setFrequentTradeAllowance(255)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    await pg.program.methods
+      .setDiminishingReturns(new anchor.BN(5_000), 0)
+      .accounts({ governance: governance.publicKey, authority: pg.wallet.publicKey })
+      .rpc();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await createTokenAccount(hfrtMint, owner.publicKey);
+    // Equal volume each time, small enough to repeat without tripping wash detection
+    const claimEqualVolume = async () => {
+      const before = await tokenBalance(wallet);
+      await recordVolume(owner, traderPda, governance, 1_000_000);
+      await claimRebate(owner, traderPda, governance, wallet);
+      return (await tokenBalance(wallet)) - before;
+    };
+
+    // 1_000_000 volume at rate 10 and multiplier 1 earns 10_000
+    assert.equal(await claimEqualVolume(), 10_000);
+    assert.equal((await pg.program.account.trader.fetch(traderPda)).lifetimeRebates.toNumber(), 10_000);
+    // Past the threshold the payout shrinks by threshold / lifetime rebates
+    assert.equal(await claimEqualVolume(), (10_000 * 5_000) / 10_000);
+    assert.equal(await claimEqualVolume(), Math.floor((10_000 * Math.floor((5_000 * 10_000) / 15_000)) / 10_000));
+  });
+
+  it("recovers a stuck stake to a fresh owner account without a penalty", async () => {
+    const governance = await pinFreshGovernance();
+    const { owner, traderPda } = await createOwnerWithTrader();
+    const wallet = await claimHfrt(owner, traderPda, governance);
+    await stakeHfrt(owner, traderPda, wallet, 1000);
+    const recoveryAccount = await createTokenAccount(hfrtMint, owner.publicKey);
+    const { hfrtVault } = await pg.program.account.globalState.fetch(globalStatePda);
+    const forceUnstake = (destination: web3.PublicKey, amount: number) =>
+      pg.program.methods
+        .forceUnstakeToOwner(new anchor.BN(amount))
+        .accounts({
+          globalState: globalStatePda,
+          trader: traderPda,
+          hfrtMint,
+          vaultAuthority: stakeVaultPda(hfrtMint),
+          stakingVault: hfrtVault,
+          recoveryTokenAccount: destination,
+          authority: pg.wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        });
+
+    // The recovered stake may only go to an account the trader owner holds
+    const foreignAccount = await createTokenAccount(hfrtMint, (await fundedKeypair()).publicKey);
+    try {
+      await forceUnstake(foreignAccount, 600).rpc();
+      assert.fail("expected Unauthorized");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "Unauthorized");
+    }
+
+    const { events } = await forceUnstake(recoveryAccount, 600).simulate();
+    const forced = events.find((e) => e.name === "ForceUnstaked").data;
+    assert.equal(forced.owner.toBase58(), owner.publicKey.toBase58());
+    assert.equal(forced.amount.toNumber(), 600);
+    assert.equal(forced.destination.toBase58(), recoveryAccount.toBase58());
+    assert.equal(forced.stakedAmount.toNumber(), 400);
+    assert.equal(forced.by.toBase58(), pg.wallet.publicKey.toBase58());
+
+    await forceUnstake(recoveryAccount, 600).rpc();
+    // The full amount arrives, with none of the fresh-stake penalty an owner unstake would pay
+    assert.equal(await tokenBalance(recoveryAccount), 600);
+    assert.equal((await pg.program.account.trader.fetch(traderPda)).stakedAmount.toNumber(), 400);
+  });
+
+  // Renouncing is permanent, so this must stay the last test in the file
+  it("renounces authority, clearing the emergency council so nobody can pause", async () => {
+    const council = await fundedKeypair();
+    await pg.program.methods
+      .setEmergencyCouncil(council.publicKey)
+      .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+      .rpc();
+    assert.isFalse((await pg.program.account.globalState.fetch(globalStatePda)).paused);
+
+    const { events } = await pg.program.methods
+      .renounceAuthority()
+      .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+      .simulate();
+    const renounced = events.find((e) => e.name === "AuthorityRenounced").data;
+    assert.equal(renounced.previousAuthority.toBase58(), pg.wallet.publicKey.toBase58());
+    await pg.program.methods
+      .renounceAuthority()
+      .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+      .rpc();
+    const state = await pg.program.account.globalState.fetch(globalStatePda);
+    assert.isTrue(state.renounced);
+    assert.equal(state.authority.toBase58(), web3.PublicKey.default.toBase58());
+    assert.equal(state.emergencyCouncil.toBase58(), web3.PublicKey.default.toBase58());
+
+    // A pause now could never be lifted, so the former council and authority are both refused
+    for (const signer of [council, null]) {
+      try {
+        const pause = pg.program.methods
+          .setPaused(true, 1)
+          .accounts({ globalState: globalStatePda, signer: signer ? signer.publicKey : pg.wallet.publicKey });
+        await (signer ? pause.signers([signer]) : pause).rpc();
+        assert.fail("expected Unauthorized");
+      } catch (err) {
+        assert.equal(err.error.errorCode.code, "Unauthorized");
+      }
+    }
+    try {
+      await pg.program.methods
+        .setEmergencyCouncil(council.publicKey)
+        .accounts({ globalState: globalStatePda, authority: pg.wallet.publicKey })
+        .rpc();
+      assert.fail("expected ConstraintHasOne");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "ConstraintHasOne");
+    }
+    assert.isFalse((await pg.program.account.globalState.fetch(globalStatePda)).paused);
+  });
+});
@@ -0,0 +1,60 @@
+//! Mock aggregator used by the HFRT CPI test. It owns an HFRT trader through its `aggregator` PDA and
+//! forwards any HFRT instruction by CPI, signing for that PDA with `invoke_signed`.
+//!
+//! Deploy it next to `hfrt` to run the "claims a rebate through a mock caller program's CPI" test; the test
+//! skips itself when this program is not deployed.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+
+declare_id!("Bfwbzpnyk42jmUKr2iPkmTZnrtaDpzPKKbxYqsB6T5Vq");
+
+/// Seed for the PDA that acts as the aggregator's HFRT trader owner.
+const AGGREGATOR_SEED: &[u8] = b"aggregator";
+
+#[program]
+pub mod mock_caller {
+    use super::*;
+
+    /// Invokes `hfrt_program` with the serialized instruction `data` over the remaining accounts, in order.
+    /// The aggregator PDA is passed as a signer wherever it appears, so it can act as the trader `owner`.
+    pub fn forward(ctx: Context<Forward>, data: Vec<u8>) -> Result<()> {
+        let aggregator = ctx.accounts.aggregator.key();
+        let accounts = ctx
+            .remaining_accounts
+            .iter()
+            .map(|account| AccountMeta {
+                pubkey: account.key(),
+                is_signer: account.is_signer || account.key() == aggregator,
+                is_writable: account.is_writable,
+            })
+            .collect();
+        let instruction = Instruction {
+            program_id: ctx.accounts.hfrt_program.key(),
+            accounts,
+            data,
+        };
+        let mut account_infos = ctx.remaining_accounts.to_vec();
+        account_infos.push(ctx.accounts.hfrt_program.to_account_info());
+        let signer_seeds: &[&[&[u8]]] = &[&[AGGREGATOR_SEED, &[ctx.bumps.aggregator]]];
+        invoke_signed(&instruction, &account_infos, signer_seeds)?;
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct Forward<'info> {
+    /// PDA (seeded by "aggregator") that owns this program's HFRT trader and token account.
+    #[account(
+        mut,
+        seeds = [AGGREGATOR_SEED],
+        bump,
+    )]
+    /// CHECK: This PDA is derived deterministically.
+    pub aggregator: UncheckedAccount<'info>,
+    /// The HFRT program being invoked.
+    #[account(executable)]
+    /// CHECK: Any program may be forwarded to; the test passes HFRT.
+    pub hfrt_program: UncheckedAccount<'info>,
+}